@@ -0,0 +1,186 @@
+extern crate ply_rs;
+use ply_rs::parser::Parser;
+use ply_rs::ply::{DefaultElement, Payload, Property, PropertyAccess, PropertyType, ScalarType};
+use std::fmt::Debug;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Times reading an ascii mesh, compared to splitting lines and calling `str::parse()` for every value.
+///
+/// Both readers fill the same element type, so the ratios show the gain of the tokenizer and number parsing.
+///
+/// Run with `cargo run --release --example bench_ascii [vertex count]`.
+fn main() {
+    let count = std::env::args()
+        .nth(1)
+        .map_or(300_000, |n| n.parse().expect("vertex count"));
+    let data = mesh(count);
+    println!(
+        "{} vertices and faces, {} MB",
+        count,
+        data.len() / 1_000_000
+    );
+
+    compare::<DefaultElement>(&data, "DefaultElement");
+    compare::<Item>(&data, "struct");
+}
+
+fn compare<E: PropertyAccess>(data: &[u8], name: &str) {
+    let reference = best_of(|| read_with_str_parse::<E>(&mut &data[..]).len());
+    let parser = best_of(|| {
+        let p = Parser::<E>::new();
+        p.read_ply(&mut &data[..]).unwrap().payload.len()
+    });
+    println!(
+        "{:<15} str::parse {:>8.1?}, Parser {:>8.1?}, {:.2}x",
+        name,
+        reference,
+        parser,
+        reference.as_secs_f64() / parser.as_secs_f64()
+    );
+}
+
+/// Shortest of five runs.
+fn best_of<F: FnMut() -> usize>(mut run: F) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(run());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+/// Vertices with position and color, and as many triangles, from a fixed pseudo random sequence.
+fn mesh(count: usize) -> Vec<u8> {
+    let mut text = format!(
+        "ply\nformat ascii 1.0\nelement vertex {0}\nproperty float x\nproperty float y\nproperty float z\n\
+         property uchar red\nproperty uchar green\nproperty uchar blue\n\
+         element face {0}\nproperty list uchar int vertex_indices\nend_header\n",
+        count
+    );
+    let mut state = 12345u64;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as u32
+    };
+    for _ in 0..count {
+        let x = next() as f32 / u32::MAX as f32;
+        let y = next() as f32 / u32::MAX as f32 * 100.0;
+        let z = -(next() as f32 / u32::MAX as f32);
+        let (r, g, b) = (next() % 256, next() % 256, next() % 256);
+        text += &format!("{:.6} {:.6} {:.6} {} {} {}\n", x, y, z, r, g, b);
+    }
+    for _ in 0..count {
+        let c = count as u32;
+        text += &format!("3 {} {} {}\n", next() % c, next() % c, next() % c);
+    }
+    text.into_bytes()
+}
+
+/// Vertex or triangle, filled through the typed setters.
+#[derive(Default)]
+struct Item {
+    position: [f32; 3],
+    color: [u8; 3],
+    indices: [i32; 3],
+}
+
+impl PropertyAccess for Item {
+    fn new() -> Self {
+        Item::default()
+    }
+    fn set_float(&mut self, key: &str, value: f32) {
+        match key {
+            "x" => self.position[0] = value,
+            "y" => self.position[1] = value,
+            "z" => self.position[2] = value,
+            _ => (),
+        }
+    }
+    fn set_uchar(&mut self, key: &str, value: u8) {
+        match key {
+            "red" => self.color[0] = value,
+            "green" => self.color[1] = value,
+            "blue" => self.color[2] = value,
+            _ => (),
+        }
+    }
+    fn set_list_i32_from(&mut self, _key: &str, values: &[i32]) {
+        self.indices.copy_from_slice(&values[..3]);
+    }
+    fn set_property(&mut self, key: &str, property: Property) {
+        match property {
+            Property::Float(v) => self.set_float(key, v),
+            Property::UChar(v) => self.set_uchar(key, v),
+            Property::ListInt(ref v) => self.set_list_i32_from(key, v),
+            _ => (),
+        }
+    }
+}
+
+/// The ascii path before the tokenizer: `read_line()`, `__fast_data_line_split()` into a `Vec<&str>`,
+/// then `str::parse()` into a `Property` and `set_property()` for every value.
+fn read_with_str_parse<E: PropertyAccess>(reader: &mut &[u8]) -> Payload<E> {
+    let reader = &mut BufReader::new(reader);
+    let header = Parser::<E>::new().read_header(reader).unwrap();
+    let mut payload = Payload::new();
+    let mut line = String::new();
+    for e in &header.elements {
+        let mut elements = Vec::with_capacity(e.count);
+        for _ in 0..e.count {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+            let mut tokens = tokens.iter();
+            let mut element = E::new();
+            for p in &e.properties {
+                element.set_property(&p.name, read_property(&mut tokens, &p.data_type));
+            }
+            elements.push(element);
+        }
+        payload.insert(e.name.clone(), elements);
+    }
+    payload
+}
+
+fn parse<D: FromStr>(s: &str) -> D
+where
+    D::Err: Debug,
+{
+    s.parse().unwrap()
+}
+
+fn read_property(tokens: &mut std::slice::Iter<&str>, data_type: &PropertyType) -> Property {
+    let s = tokens.next().unwrap();
+    match *data_type {
+        PropertyType::Scalar(scalar_type) => match scalar_type {
+            ScalarType::Char => Property::Char(parse(s)),
+            ScalarType::UChar => Property::UChar(parse(s)),
+            ScalarType::Short => Property::Short(parse(s)),
+            ScalarType::UShort => Property::UShort(parse(s)),
+            ScalarType::Int => Property::Int(parse(s)),
+            ScalarType::UInt => Property::UInt(parse(s)),
+            ScalarType::Float => Property::Float(parse(s)),
+            ScalarType::Double => Property::Double(parse(s)),
+        },
+        PropertyType::List(_, scalar_type) => {
+            let count: usize = parse(s);
+            let items = tokens.take(count);
+            match scalar_type {
+                ScalarType::Char => Property::ListChar(items.map(|s| parse(s)).collect()),
+                ScalarType::UChar => Property::ListUChar(items.map(|s| parse(s)).collect()),
+                ScalarType::Short => Property::ListShort(items.map(|s| parse(s)).collect()),
+                ScalarType::UShort => Property::ListUShort(items.map(|s| parse(s)).collect()),
+                ScalarType::Int => Property::ListInt(items.map(|s| parse(s)).collect()),
+                ScalarType::UInt => Property::ListUInt(items.map(|s| parse(s)).collect()),
+                ScalarType::Float => Property::ListFloat(items.map(|s| parse(s)).collect()),
+                ScalarType::Double => Property::ListDouble(items.map(|s| parse(s)).collect()),
+            }
+        }
+    }
+}
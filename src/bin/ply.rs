@@ -0,0 +1,410 @@
+//! Command-line tool to inspect and convert PLY files.
+//!
+//! Files are read from stdin and written to stdout if no path or `-` is given,
+//! so commands can be chained in pipes:
+//!
+//! ```text
+//! cat scan.ply | ply strip --property nx,ny,nz | ply convert --to binary_le > small.ply
+//! ```
+//!
+//! Except for `head`, payloads are streamed element by element.
+//! Inputs compressed with gzip or zstd are read if the matching feature is enabled.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::process;
+
+use ply_rs::compression::Decompressor;
+use ply_rs::mesh::Mesh;
+use ply_rs::parser::Parser;
+use ply_rs::ply::{DefaultElement, DiffOptions, ElementDef, Encoding, Header, Ply};
+use ply_rs::writer::Writer;
+
+const USAGE: &str = "\
+Usage: ply <command> [options] [input] [output]
+
+Commands:
+    info [input]                      Print the header, element counts and byte sizes.
+    convert --to <encoding> [input] [output]
+                                      Change the encoding: ascii, binary_le or binary_be.
+    validate [input]                  Read the entire file and check it strictly.
+    head [-n <count>] [input] [output]
+                                      Keep the first elements of each element, as ascii.
+    strip --property <a,b,..> [--element <name>] [input] [output]
+                                      Remove properties, from all elements by default.
+    comment add <text> [input] [output]
+                                      Append a comment to the header.
+    comment remove [--matching <text>] [input] [output]
+                                      Remove comments containing <text>, or all comments.
+    diff [--abs <tolerance>] [--rel <tolerance>] [-n <rows>] <left> <right>
+                                      Compare headers and values, exits with 1 on differences
+                                      and with 2 if a file can't be read.
+
+Input and output default to stdin and stdout, `-` selects them explicitly.";
+
+/// Why a command failed.
+#[derive(Debug)]
+enum Failure {
+    /// The command line can't be understood.
+    Usage(String),
+    /// Reading, parsing or writing failed.
+    Io(io::Error),
+    /// Like `Io`, but exits with 2, for commands that exit with 1 for another reason.
+    Trouble(io::Error),
+    /// `diff` found differences.
+    Differ,
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+type Result<T> = std::result::Result<T, Failure>;
+
+/// Command line split into options and positional arguments.
+#[derive(Debug, Default)]
+struct Args {
+    options: HashMap<String, String>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Splits `args`, every name in `options` takes a value.
+    fn parse(args: &[String], options: &[&str]) -> Result<Self> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+        while let Some(a) = args.next() {
+            if a != "-" && a.starts_with('-') {
+                if !options.contains(&a.as_str()) {
+                    return Err(Failure::Usage(format!("Unknown option `{}`.", a)));
+                }
+                let value = args
+                    .next()
+                    .ok_or_else(|| Failure::Usage(format!("Option `{}` needs a value.", a)))?;
+                parsed.options.insert(a.clone(), value.clone());
+            } else {
+                parsed.positional.push(a.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|v| v.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str> {
+        self.option(name)
+            .ok_or_else(|| Failure::Usage(format!("Option `{}` is required.", name)))
+    }
+
+    /// Input and output paths, at most `count` of them.
+    fn paths(&self, skip: usize, count: usize) -> Result<Vec<&str>> {
+        let paths: Vec<&str> = self
+            .positional
+            .iter()
+            .skip(skip)
+            .map(|p| p.as_str())
+            .collect();
+        if paths.len() > count {
+            return Err(Failure::Usage(format!(
+                "Unexpected argument `{}`.",
+                paths[count]
+            )));
+        }
+        Ok(paths)
+    }
+}
+
+fn open_input(path: Option<&&str>) -> io::Result<Box<dyn BufRead>> {
+    match path {
+        None | Some(&"-") => Ok(Box::new(Decompressor::new(BufReader::new(io::stdin()))?)),
+        Some(p) => Ok(Box::new(Decompressor::new(BufReader::new(File::open(p)?))?)),
+    }
+}
+
+fn open_output(path: Option<&&str>) -> io::Result<Box<dyn Write>> {
+    match path {
+        None | Some(&"-") => Ok(Box::new(BufWriter::new(io::stdout()))),
+        Some(p) => Ok(Box::new(BufWriter::new(File::create(p)?))),
+    }
+}
+
+fn parse_encoding(name: &str) -> Result<Encoding> {
+    match name {
+        "ascii" => Ok(Encoding::Ascii),
+        "binary_le" | "binary_little_endian" => Ok(Encoding::BinaryLittleEndian),
+        "binary_be" | "binary_big_endian" => Ok(Encoding::BinaryBigEndian),
+        _ => Err(Failure::Usage(format!("Unknown encoding `{}`.", name))),
+    }
+}
+
+fn write_element<T: Write>(
+    w: &Writer<DefaultElement>,
+    out: &mut T,
+    element: &DefaultElement,
+    element_def: &ElementDef,
+    encoding: Encoding,
+) -> io::Result<usize> {
+    match encoding {
+        Encoding::Ascii => w.write_ascii_element(out, element, element_def),
+        Encoding::BinaryBigEndian => w.write_big_endian_element(out, element, element_def),
+        Encoding::BinaryLittleEndian => w.write_little_endian_element(out, element, element_def),
+    }
+}
+
+/// Writes `header` and converts every element of `input` to the definitions and encoding of `header`.
+fn transcode<T: BufRead, U: Write>(
+    input: &mut T,
+    input_header: &Header,
+    out: &mut U,
+    header: &Header,
+) -> io::Result<()> {
+    let p = Parser::<DefaultElement>::new();
+    let w = Writer::<DefaultElement>::new();
+    w.write_header(out, header)?;
+    p.read_payload_with(input, input_header, |def, e| {
+        match header.elements.iter().find(|d| d.name == def.name) {
+            Some(output_def) => write_element(&w, out, &e, output_def, header.encoding)?,
+            None => 0,
+        };
+        Ok(())
+    })?;
+    out.flush()
+}
+
+fn info(args: &Args) -> Result<()> {
+    let paths = args.paths(1, 1)?;
+    let mut input = open_input(paths.first())?;
+    let p = Parser::<DefaultElement>::new();
+    let (header, statistics) = p.read_statistics(&mut input)?;
+    let mut header_text = Vec::new();
+    Writer::<DefaultElement>::new().write_header(&mut header_text, &header)?;
+
+    let mut out = open_output(None)?;
+    out.write_all(&header_text)?;
+    writeln!(out)?;
+    write!(out, "{}", statistics)?;
+    writeln!(
+        out,
+        "header: {} bytes, payload: {} bytes",
+        header_text.len(),
+        statistics.byte_size(header.encoding)
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+fn convert(args: &Args) -> Result<()> {
+    let encoding = parse_encoding(args.required("--to")?)?;
+    let paths = args.paths(1, 2)?;
+    let mut input = open_input(paths.first())?;
+    let p = Parser::<DefaultElement>::new();
+    let input_header = p.read_header(&mut input)?;
+    let mut header = input_header.clone();
+    header.encoding = encoding;
+    let mut out = open_output(paths.get(1))?;
+    transcode(&mut input, &input_header, &mut out, &header)?;
+    Ok(())
+}
+
+fn validate(args: &Args) -> Result<()> {
+    let paths = args.paths(1, 1)?;
+    let mut input = open_input(paths.first())?;
+    // errors name lines of the file, not of the payload
+    let mut ply = Parser::<DefaultElement>::new().read_ply_from_buf(&mut input)?;
+    let invalid = |message: String| Failure::Io(io::Error::new(ErrorKind::InvalidData, message));
+
+    let mut rest = Vec::new();
+    input.read_to_end(&mut rest)?;
+    let trailing = match ply.header.encoding {
+        Encoding::Ascii => rest.iter().any(|b| !b.is_ascii_whitespace()),
+        _ => !rest.is_empty(),
+    };
+    if trailing {
+        return Err(invalid(format!(
+            "{} bytes of data after the last element.",
+            rest.len()
+        )));
+    }
+
+    ply.make_consistent().map_err(|e| invalid(e.to_string()))?;
+    let names: Vec<&str> = ply
+        .header
+        .elements
+        .iter()
+        .map(|e| e.name.as_str())
+        .collect();
+    if names.contains(&"vertex") && names.contains(&"face") {
+        Mesh::from_ply(&ply)
+            .and_then(|m| m.check())
+            .map_err(|e| invalid(e.to_string()))?;
+    }
+    let count: usize = ply.payload.values().map(|v| v.len()).sum();
+    println!("ok: {} elements", count);
+    Ok(())
+}
+
+fn head(args: &Args) -> Result<()> {
+    let n: usize = parse_number(args, "-n", 10)?;
+    let paths = args.paths(1, 2)?;
+    let mut input = open_input(paths.first())?;
+    let p = Parser::<DefaultElement>::new();
+    let header = p.read_header(&mut input)?;
+    let mut ply = Ply::<DefaultElement>::new();
+    ply.header = header.clone();
+    ply.header.encoding = Encoding::Ascii;
+    p.read_payload_with(&mut input, &header, |def, e| {
+        let elements = ply.payload.entry(def.name.clone()).or_default();
+        if elements.len() < n {
+            elements.push(e);
+        }
+        Ok(())
+    })?;
+    let mut out = open_output(paths.get(1))?;
+    Writer::new().write_ply(&mut out, &mut ply)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn strip(args: &Args) -> Result<()> {
+    let properties: Vec<&str> = args.required("--property")?.split(',').collect();
+    let element = args.option("--element");
+    let paths = args.paths(1, 2)?;
+    let mut input = open_input(paths.first())?;
+    let p = Parser::<DefaultElement>::new();
+    let input_header = p.read_header(&mut input)?;
+    let mut header = input_header.clone();
+    for e in header.elements.iter_mut() {
+        if element.is_none_or(|name| name == e.name) {
+            e.properties
+                .retain(|p| !properties.contains(&p.name.as_str()));
+        }
+    }
+    let mut out = open_output(paths.get(1))?;
+    transcode(&mut input, &input_header, &mut out, &header)?;
+    Ok(())
+}
+
+fn comment(args: &Args) -> Result<()> {
+    let action = args.positional.get(1).map(|a| a.as_str());
+    let (text, paths) = match action {
+        Some("add") => {
+            let text = args
+                .positional
+                .get(2)
+                .ok_or_else(|| Failure::Usage("Missing comment text.".to_string()))?;
+            (Some(text.as_str()), args.paths(3, 2)?)
+        }
+        Some("remove") => (args.option("--matching"), args.paths(2, 2)?),
+        _ => {
+            return Err(Failure::Usage(
+                "Expected `comment add` or `comment remove`.".to_string(),
+            ))
+        }
+    };
+    if action == Some("add") && args.option("--matching").is_some() {
+        return Err(Failure::Usage(
+            "`--matching` only applies to `comment remove`.".to_string(),
+        ));
+    }
+    let mut input = open_input(paths.first())?;
+    let (mut header, layout) =
+        Parser::<DefaultElement>::new().read_header_with_layout(&mut input)?;
+    match (action, text) {
+        (Some("add"), Some(text)) => header.comments.push(text.to_string()),
+        (_, Some(text)) => header.comments.retain(|c| !c.contains(text)),
+        _ => header.comments.clear(),
+    }
+    let mut out = open_output(paths.get(1))?;
+    Writer::<DefaultElement>::new().write_header_with_layout(&mut out, &header, &layout)?;
+    // the payload is left untouched
+    io::copy(&mut input, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn parse_number<N: std::str::FromStr>(args: &Args, name: &str, default: N) -> Result<N> {
+    match args.option(name) {
+        Some(v) => v
+            .parse()
+            .map_err(|_| Failure::Usage(format!("Invalid value `{}` for `{}`.", v, name))),
+        None => Ok(default),
+    }
+}
+
+fn diff(args: &Args) -> Result<()> {
+    let defaults = DiffOptions::default();
+    let options = DiffOptions {
+        absolute_tolerance: parse_number(args, "--abs", defaults.absolute_tolerance)?,
+        relative_tolerance: parse_number(args, "--rel", defaults.relative_tolerance)?,
+        max_rows: parse_number(args, "-n", defaults.max_rows)?,
+    };
+    let paths = args.paths(1, 2)?;
+    if paths.len() != 2 {
+        return Err(Failure::Usage("Expected two files to compare.".to_string()));
+    }
+    if paths[0] == "-" && paths[1] == "-" {
+        return Err(Failure::Usage("Only one input can be stdin.".to_string()));
+    }
+    // exit code 1 is taken by differences, like diff(1)
+    let compare = || -> io::Result<bool> {
+        let mut left = open_input(paths.first())?;
+        let mut right = open_input(paths.get(1))?;
+        let diff = Parser::<DefaultElement>::new().read_diff(&mut left, &mut right, &options)?;
+        let mut out = open_output(None)?;
+        write!(out, "{}", diff)?;
+        out.flush()?;
+        Ok(diff.is_equal())
+    };
+    match compare() {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Failure::Differ),
+        Err(e) => Err(Failure::Trouble(e)),
+    }
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let command = args
+        .first()
+        .ok_or_else(|| Failure::Usage("Missing command.".to_string()))?;
+    match command.as_str() {
+        "info" => info(&Args::parse(args, &[])?),
+        "convert" => convert(&Args::parse(args, &["--to"])?),
+        "validate" => validate(&Args::parse(args, &[])?),
+        "head" => head(&Args::parse(args, &["-n"])?),
+        "strip" => strip(&Args::parse(args, &["--property", "--element"])?),
+        "comment" => comment(&Args::parse(args, &["--matching"])?),
+        "diff" => diff(&Args::parse(args, &["--abs", "--rel", "-n"])?),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(Failure::Usage(format!("Unknown command `{}`.", command))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => {}
+        Err(Failure::Usage(message)) => {
+            eprintln!("ply: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+        Err(Failure::Io(e) | Failure::Trouble(e)) if e.kind() == ErrorKind::BrokenPipe => {}
+        Err(Failure::Io(e)) => {
+            eprintln!("ply: {}", e);
+            process::exit(1);
+        }
+        Err(Failure::Trouble(e)) => {
+            eprintln!("ply: {}", e);
+            process::exit(2);
+        }
+        Err(Failure::Differ) => process::exit(1),
+    }
+}
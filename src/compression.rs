@@ -0,0 +1,265 @@
+//! Transparent gzip and zstd compression of whole PLY files.
+//!
+//! `Decompressor` recognizes compressed input by its magic bytes and otherwise passes it through unchanged.
+//! `Parser::read_ply()` and the other `read_` functions taking a whole file use it, so `.ply.gz` and `.ply.zst` files can be read as they are.
+//! Decoding requires the `gzip` or `zstd` feature, detection always works and results in a helpful error.
+//!
+//! # Examples
+//!
+//! Streaming a file that might be compressed:
+//!
+//! ```rust
+//! # use ply_rs::*;
+//! # let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+//! let f = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+//! let mut f = compression::Decompressor::new(f).unwrap();
+//! let p = parser::Parser::<ply::DefaultElement>::new();
+//! let header = p.read_header(&mut f).unwrap();
+//! let payload = p.read_payload(&mut f, &header).unwrap();
+//! ```
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, Cursor, ErrorKind, Read, Result};
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use std::io::{BufReader, Write};
+
+/// Compressed formats recognized by their magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Gzip,
+    Zstd,
+}
+
+impl CompressionFormat {
+    /// Longest magic byte sequence, enough input to call `detect()`.
+    pub const MAGIC_LEN: usize = 4;
+
+    /// Recognizes the format from the first bytes of a file.
+    pub fn detect(prefix: &[u8]) -> Option<Self> {
+        if prefix.starts_with(&[0x1f, 0x8b]) {
+            Some(CompressionFormat::Gzip)
+        } else if prefix.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(CompressionFormat::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for CompressionFormat {
+    fn fmt(&self, f: &mut Formatter) -> std::result::Result<(), fmt::Error> {
+        f.write_str(match *self {
+            CompressionFormat::Gzip => "gzip",
+            CompressionFormat::Zstd => "zstd",
+        })
+    }
+}
+
+/// The input after the magic bytes have been looked at.
+type Source<R> = io::Chain<Cursor<Vec<u8>>, R>;
+
+enum Input<R: BufRead> {
+    Plain(Source<R>),
+    #[cfg(feature = "gzip")]
+    Gzip(BufReader<flate2::bufread::MultiGzDecoder<Source<R>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(BufReader<zstd::Decoder<'static, Source<R>>>),
+}
+
+/// Reads plain or compressed input, see the module documentation.
+pub struct Decompressor<R: BufRead> {
+    input: Input<R>,
+    format: Option<CompressionFormat>,
+}
+
+impl<R: BufRead> Decompressor<R> {
+    /// Looks at the first bytes of `reader` and sets up a decoder if needed.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the input is compressed in a format whose feature is disabled.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut prefix = Vec::with_capacity(CompressionFormat::MAGIC_LEN);
+        (&mut reader)
+            .take(CompressionFormat::MAGIC_LEN as u64)
+            .read_to_end(&mut prefix)?;
+        let format = CompressionFormat::detect(&prefix);
+        let source = Cursor::new(prefix).chain(reader);
+        let input = match format {
+            None => Input::Plain(source),
+            #[cfg(feature = "gzip")]
+            Some(CompressionFormat::Gzip) => {
+                Input::Gzip(BufReader::new(flate2::bufread::MultiGzDecoder::new(source)))
+            }
+            #[cfg(feature = "zstd")]
+            Some(CompressionFormat::Zstd) => {
+                Input::Zstd(BufReader::new(zstd::Decoder::with_buffer(source)?))
+            }
+            #[allow(unreachable_patterns)]
+            Some(format) => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Input is {} compressed, enable the `{}` feature to read it.",
+                        format, format
+                    ),
+                ))
+            }
+        };
+        Ok(Decompressor { input, format })
+    }
+
+    /// Format of the input, `None` if it isn't compressed.
+    pub fn format(&self) -> Option<CompressionFormat> {
+        self.format
+    }
+}
+
+impl<R: BufRead> Read for Decompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self.input {
+            Input::Plain(ref mut r) => r.read(buf),
+            #[cfg(feature = "gzip")]
+            Input::Gzip(ref mut r) => r.read(buf),
+            #[cfg(feature = "zstd")]
+            Input::Zstd(ref mut r) => r.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for Decompressor<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        match self.input {
+            Input::Plain(ref mut r) => r.fill_buf(),
+            #[cfg(feature = "gzip")]
+            Input::Gzip(ref mut r) => r.fill_buf(),
+            #[cfg(feature = "zstd")]
+            Input::Zstd(ref mut r) => r.fill_buf(),
+        }
+    }
+    fn consume(&mut self, amt: usize) {
+        match self.input {
+            Input::Plain(ref mut r) => r.consume(amt),
+            #[cfg(feature = "gzip")]
+            Input::Gzip(ref mut r) => r.consume(amt),
+            #[cfg(feature = "zstd")]
+            Input::Zstd(ref mut r) => r.consume(amt),
+        }
+    }
+}
+
+/// Output compression and its level, available with the `gzip` or `zstd` feature.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Levels range from 0 (none) to 9 (best), 6 is the usual default.
+    #[cfg(feature = "gzip")]
+    Gzip(u32),
+    /// Levels range from 1 to 22, 0 selects the default of 3.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+enum Output<W: Write> {
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+/// Compresses everything written to it, call `finish()` at the end.
+///
+/// Use it with the `write_` functions of `Writer` to stream a compressed file,
+/// or see `Writer::write_ply_compressed()`.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub struct Compressor<W: Write> {
+    output: Output<W>,
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl<W: Write> Compressor<W> {
+    /// Starts a compressed stream on `out`.
+    pub fn new(out: W, compression: Compression) -> Result<Self> {
+        let output = match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip(level) => Output::Gzip(flate2::write::GzEncoder::new(
+                out,
+                flate2::Compression::new(level),
+            )),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => Output::Zstd(zstd::Encoder::new(out, level)?),
+        };
+        Ok(Compressor { output })
+    }
+
+    /// Writes the remaining compressed data and returns the underlying output.
+    pub fn finish(self) -> Result<W> {
+        match self.output {
+            #[cfg(feature = "gzip")]
+            Output::Gzip(w) => w.finish(),
+            #[cfg(feature = "zstd")]
+            Output::Zstd(w) => w.finish(),
+        }
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl<W: Write> Write for Compressor<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match self.output {
+            #[cfg(feature = "gzip")]
+            Output::Gzip(ref mut w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            Output::Zstd(ref mut w) => w.write(buf),
+        }
+    }
+    fn flush(&mut self) -> Result<()> {
+        match self.output {
+            #[cfg(feature = "gzip")]
+            Output::Gzip(ref mut w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            Output::Zstd(ref mut w) => w.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(input: &[u8]) -> Result<(Option<CompressionFormat>, Vec<u8>)> {
+        let mut d = Decompressor::new(input)?;
+        let mut out = Vec::new();
+        d.read_to_end(&mut out)?;
+        Ok((d.format(), out))
+    }
+
+    #[test]
+    fn detect_ok() {
+        assert_eq!(
+            CompressionFormat::detect(&[0x1f, 0x8b, 8, 0]),
+            Some(CompressionFormat::Gzip)
+        );
+        assert_eq!(
+            CompressionFormat::detect(&[0x28, 0xb5, 0x2f, 0xfd]),
+            Some(CompressionFormat::Zstd)
+        );
+        assert_eq!(CompressionFormat::detect(b"ply\n"), None);
+        assert_eq!(CompressionFormat::detect(&[0x28, 0xb5]), None);
+    }
+    #[test]
+    fn plain_ok() {
+        for input in [&b""[..], b"p", b"ply\nformat ascii 1.0\n"] {
+            assert_eq!(read_all(input).unwrap(), (None, input.to_vec()));
+        }
+    }
+    #[test]
+    #[cfg(not(feature = "zstd"))]
+    fn disabled_err() {
+        let e = read_all(&[0x28, 0xb5, 0x2f, 0xfd, 0]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(e.to_string().contains("`zstd` feature"));
+    }
+}
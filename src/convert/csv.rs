@@ -0,0 +1,698 @@
+//! Element tables as delimited text: CSV, TSV and whitespace separated XYZ files.
+//!
+//! Every element becomes one row, every property one or more columns.
+//! List properties are either joined into one cell or expanded into numbered columns `name_0`, `name_1`, ...,
+//! see `ListColumns`.
+//!
+//! `CsvWriter` and `CsvReader` work row by row. Combined with `Parser::read_payload_with()`
+//! they convert files larger than memory:
+//!
+//! ```rust
+//! # use ply_rs::convert::csv::{CsvOptions, CsvWriter};
+//! # use ply_rs::parser::Parser;
+//! # use ply_rs::ply::DefaultElement;
+//! let path = "example_plys/house_ok_ascii.ply";
+//! let mut f = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+//! let p = Parser::<DefaultElement>::new();
+//! let header = p.read_header(&mut f).unwrap();
+//!
+//! let mut w = CsvWriter::new(CsvOptions::csv());
+//! let mut out = Vec::new();
+//! w.write_header_row(&mut out, &header.elements[0]).unwrap();
+//! p.read_payload_with(&mut f, &header, |def, e| {
+//!     if def.name == "vertex" {
+//!         w.write_row(&mut out, &e, def)?;
+//!     }
+//!     Ok(())
+//! }).unwrap();
+//! let text = String::from_utf8(out).unwrap();
+//! assert!(text.starts_with("x,y,z,nx,ny,nz\n1,-1,0,-0,0,1\n"));
+//! ```
+//!
+//! Cells are written without quotes, quotes around cells are removed when reading.
+
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+
+use crate::ply::{
+    DefaultElement, ElementDef, Ply, Property, PropertyAccess, PropertyDef, PropertyType,
+    ScalarType, SchemaCache,
+};
+
+/// How list properties map onto columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListColumns {
+    /// All entries in one cell, separated by the given character.
+    Join(char),
+    /// One column per entry, `name_0` to `name_{n-1}`.
+    ///
+    /// Shorter lists leave the remaining cells empty, longer lists are an error.
+    Expand(usize),
+}
+
+/// Layout of a delimited text file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Separates cells, `None` splits at any whitespace and writes a single space.
+    pub delimiter: Option<char>,
+    /// Whether the first line contains the column names.
+    pub header: bool,
+    /// How list properties map onto columns.
+    pub lists: ListColumns,
+}
+
+impl CsvOptions {
+    /// Comma separated values with a header row.
+    pub fn csv() -> Self {
+        CsvOptions {
+            delimiter: Some(','),
+            header: true,
+            lists: ListColumns::Join(' '),
+        }
+    }
+    /// Tab separated values with a header row.
+    pub fn tsv() -> Self {
+        CsvOptions {
+            delimiter: Some('\t'),
+            ..CsvOptions::csv()
+        }
+    }
+    /// Whitespace separated values without a header row.
+    ///
+    /// When reading, the columns are called `x`, `y`, `z`, followed by `column_3`, `column_4`, ...
+    pub fn xyz() -> Self {
+        CsvOptions {
+            delimiter: None,
+            header: false,
+            lists: ListColumns::Expand(0),
+        }
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions::csv()
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message)
+}
+
+/// Writes elements as rows of delimited text.
+#[derive(Debug, Clone, Default)]
+pub struct CsvWriter {
+    options: CsvOptions,
+    schemas: SchemaCache,
+}
+
+impl CsvWriter {
+    /// Creates a writer using the given layout.
+    pub fn new(options: CsvOptions) -> Self {
+        CsvWriter {
+            options,
+            schemas: SchemaCache::default(),
+        }
+    }
+
+    fn write_cells<T: Write>(&self, out: &mut T, cells: &[String]) -> io::Result<usize> {
+        let delimiter = self.options.delimiter.unwrap_or(' ').to_string();
+        let line = cells.join(&delimiter) + "\n";
+        out.write_all(line.as_bytes())?;
+        Ok(line.len())
+    }
+
+    /// Column names of `element_def`.
+    pub fn column_names(&self, element_def: &ElementDef) -> Vec<String> {
+        let mut names = Vec::new();
+        for p in &element_def.properties {
+            match (p.data_type, self.options.lists) {
+                (PropertyType::List(_, _), ListColumns::Expand(n)) => {
+                    names.extend((0..n).map(|i| format!("{}_{}", p.name, i)))
+                }
+                _ => names.push(p.name.clone()),
+            }
+        }
+        names
+    }
+
+    /// Writes the column names, does nothing if the layout has no header row.
+    pub fn write_header_row<T: Write>(
+        &self,
+        out: &mut T,
+        element_def: &ElementDef,
+    ) -> io::Result<usize> {
+        if !self.options.header {
+            return Ok(0);
+        }
+        self.write_cells(out, &self.column_names(element_def))
+    }
+
+    /// Writes a single element as one row.
+    ///
+    /// The schema of `E` is bound once per element name, see `PropertyAccess::bind_schema()`.
+    pub fn write_row<T: Write, E: PropertyAccess>(
+        &mut self,
+        out: &mut T,
+        element: &E,
+        element_def: &ElementDef,
+    ) -> io::Result<usize> {
+        let mut cells = Vec::with_capacity(element_def.properties.len());
+        let schema = self.schemas.get::<E>(element_def);
+        for (index, def) in element_def.properties.iter().enumerate() {
+            let property = Property::read_bound(element, element_def, schema, index);
+            let property = property.ok_or_else(|| {
+                invalid(format!("No value for property `{}` available.", def.name))
+            })?;
+            match property.list_as_strings() {
+                None => cells.push(property.scalar_to_string()),
+                Some(values) => match self.options.lists {
+                    ListColumns::Join(separator) => cells.push(values.join(&separator.to_string())),
+                    ListColumns::Expand(n) => {
+                        if values.len() > n {
+                            return Err(invalid(format!(
+                                "List `{}` has {} entries, but only {} columns are available.",
+                                def.name,
+                                values.len(),
+                                n
+                            )));
+                        }
+                        let len = values.len();
+                        cells.extend(values);
+                        cells.extend((len..n).map(|_| String::new()));
+                    }
+                },
+            }
+        }
+        self.write_cells(out, &cells)
+    }
+
+    /// Writes all elements named `element_name` of `ply`, including the header row.
+    pub fn write_element<T: Write, E: PropertyAccess>(
+        &mut self,
+        out: &mut T,
+        ply: &Ply<E>,
+        element_name: &str,
+    ) -> io::Result<usize> {
+        let element_def = ply
+            .header
+            .elements
+            .iter()
+            .find(|e| e.name == element_name)
+            .ok_or_else(|| invalid(format!("No element `{}` found.", element_name)))?;
+        let mut written = self.write_header_row(out, element_def)?;
+        if let Some(elements) = ply.payload.get(element_name) {
+            for e in elements {
+                written += self.write_row(out, e, element_def)?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+impl Property {
+    fn scalar_to_string(&self) -> String {
+        match *self {
+            Property::Char(v) => v.to_string(),
+            Property::UChar(v) => v.to_string(),
+            Property::Short(v) => v.to_string(),
+            Property::UShort(v) => v.to_string(),
+            Property::Int(v) => v.to_string(),
+            Property::UInt(v) => v.to_string(),
+            Property::Float(v) => v.to_string(),
+            Property::Double(v) => v.to_string(),
+            _ => String::new(),
+        }
+    }
+    fn list_as_strings(&self) -> Option<Vec<String>> {
+        fn s<V: ToString>(l: &[V]) -> Option<Vec<String>> {
+            Some(l.iter().map(|v| v.to_string()).collect())
+        }
+        match *self {
+            Property::ListChar(ref l) => s(l),
+            Property::ListUChar(ref l) => s(l),
+            Property::ListShort(ref l) => s(l),
+            Property::ListUShort(ref l) => s(l),
+            Property::ListInt(ref l) => s(l),
+            Property::ListUInt(ref l) => s(l),
+            Property::ListFloat(ref l) => s(l),
+            Property::ListDouble(ref l) => s(l),
+            _ => None,
+        }
+    }
+}
+
+/// Narrowest type that holds every value of a column seen so far.
+#[derive(Debug, Clone, Copy)]
+struct ColumnGuess {
+    int: bool,
+    uint: bool,
+    float: bool,
+}
+
+impl ColumnGuess {
+    fn new() -> Self {
+        ColumnGuess {
+            int: true,
+            uint: true,
+            float: true,
+        }
+    }
+    /// Returns the value of `cell`, or `None` if it isn't a number.
+    fn add(&mut self, cell: &str) -> Option<f64> {
+        let v: f64 = cell.parse().ok()?;
+        self.int &= cell.parse::<i32>().is_ok();
+        self.uint &= cell.parse::<u32>().is_ok();
+        // a float suffices if it reproduces the same decimal value
+        let f = v as f32;
+        self.float &= f.to_string().parse::<f64>() == Ok(v) || v.is_nan();
+        Some(v)
+    }
+    fn scalar_type(&self) -> ScalarType {
+        if self.int {
+            ScalarType::Int
+        } else if self.uint {
+            ScalarType::UInt
+        } else if self.float {
+            ScalarType::Float
+        } else {
+            ScalarType::Double
+        }
+    }
+}
+
+/// Column types inferred row by row.
+struct Inference<'a> {
+    names: Option<&'a [String]>,
+    guesses: Vec<ColumnGuess>,
+    rows: usize,
+}
+
+impl<'a> Inference<'a> {
+    fn new(names: Option<&'a [String]>) -> Self {
+        Inference {
+            names,
+            guesses: vec![ColumnGuess::new(); names.map_or(0, |n| n.len())],
+            rows: 0,
+        }
+    }
+    /// Updates the guesses with `row` and appends the values of its cells to `values`.
+    fn add_row(&mut self, row: &[String], values: &mut Vec<f64>) -> io::Result<()> {
+        let i = self.rows;
+        if i == 0 && self.names.is_none() {
+            self.guesses = vec![ColumnGuess::new(); row.len()];
+        }
+        if row.len() != self.guesses.len() {
+            return Err(invalid(format!(
+                "Row {} has {} cells, expected {}.",
+                i,
+                row.len(),
+                self.guesses.len()
+            )));
+        }
+        for (k, (g, cell)) in self.guesses.iter_mut().zip(row.iter()).enumerate() {
+            match g.add(cell) {
+                Some(v) => values.push(v),
+                None => {
+                    return Err(invalid(format!(
+                        "Row {}, column {}: `{}` is not a number.",
+                        i, k, cell
+                    )))
+                }
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+    fn element_def(&self, name: &str) -> ElementDef {
+        let mut e = ElementDef::new(name);
+        for (k, g) in self.guesses.iter().enumerate() {
+            let column_name = match self.names {
+                Some(n) => n[k].clone(),
+                None => match k {
+                    0 => "x".to_string(),
+                    1 => "y".to_string(),
+                    2 => "z".to_string(),
+                    k => format!("column_{}", k),
+                },
+            };
+            e.properties.push(PropertyDef::new(
+                &column_name,
+                PropertyType::Scalar(g.scalar_type()),
+            ));
+        }
+        e.count = self.rows;
+        e
+    }
+}
+
+/// Reads rows of delimited text as elements.
+#[derive(Debug, Clone, Default)]
+pub struct CsvReader {
+    options: CsvOptions,
+}
+
+impl CsvReader {
+    /// Creates a reader using the given layout.
+    pub fn new(options: CsvOptions) -> Self {
+        CsvReader { options }
+    }
+
+    fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let cells: Vec<&str> = match self.options.delimiter {
+            Some(d) => {
+                // delimiters within quotes belong to the cell
+                let mut quoted = false;
+                line.split(|c| {
+                    if c == '"' {
+                        quoted = !quoted;
+                    }
+                    c == d && !quoted
+                })
+                .collect()
+            }
+            None => line.split_whitespace().collect(),
+        };
+        cells
+            .into_iter()
+            .map(|c| {
+                let c = c.trim();
+                c.strip_prefix('"')
+                    .and_then(|c| c.strip_suffix('"'))
+                    .unwrap_or(c)
+            })
+            .collect()
+    }
+
+    /// Reads the next non empty line and splits it into cells, `None` at the end of the input.
+    pub fn read_cells<T: BufRead>(&self, reader: &mut T) -> io::Result<Option<Vec<String>>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            if !line.trim().is_empty() {
+                return Ok(Some(
+                    self.split(&line).into_iter().map(String::from).collect(),
+                ));
+            }
+        }
+    }
+
+    /// Reads the header row, or returns `None` if the layout has no header row.
+    pub fn read_header_row<T: BufRead>(&self, reader: &mut T) -> io::Result<Option<Vec<String>>> {
+        if !self.options.header {
+            return Ok(None);
+        }
+        self.read_cells(reader)?
+            .map(Some)
+            .ok_or_else(|| invalid("Missing header row.".to_string()))
+    }
+
+    /// Derives an element definition with one scalar property per column from sample rows.
+    ///
+    /// Integer columns become `int` or `uint`, other numbers `float` if no digits are lost, `double` otherwise.
+    /// Without `names`, the columns are called `x`, `y`, `z`, `column_3`, ...
+    pub fn infer_element_def(
+        &self,
+        name: &str,
+        names: Option<&[String]>,
+        rows: &[Vec<String>],
+    ) -> io::Result<ElementDef> {
+        let mut inference = Inference::new(names);
+        let mut values = Vec::new();
+        for row in rows {
+            values.clear();
+            inference.add_row(row, &mut values)?;
+        }
+        Ok(inference.element_def(name))
+    }
+
+    /// Reads the next row as an element of `element_def`, `None` at the end of the input.
+    pub fn read_row<T: BufRead, E: PropertyAccess>(
+        &self,
+        reader: &mut T,
+        element_def: &ElementDef,
+    ) -> io::Result<Option<E>> {
+        match self.read_cells(reader)? {
+            Some(cells) => self.element_from_cells(&cells, element_def).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts the cells of one row into an element of `element_def`.
+    pub fn element_from_cells<E: PropertyAccess>(
+        &self,
+        cells: &[String],
+        element_def: &ElementDef,
+    ) -> io::Result<E> {
+        let mut element = E::new();
+        let mut cells = cells.iter();
+        for def in &element_def.properties {
+            let mut next = || {
+                cells
+                    .next()
+                    .ok_or_else(|| invalid(format!("Missing cell for property `{}`.", def.name)))
+            };
+            let property = match def.data_type {
+                PropertyType::Scalar(s) => parse_scalar(next()?, s)?,
+                PropertyType::List(_, s) => {
+                    let entries: Vec<&str> = match self.options.lists {
+                        ListColumns::Join(separator) => next()?
+                            .split(separator)
+                            .filter(|e| !e.trim().is_empty())
+                            .collect(),
+                        ListColumns::Expand(n) => {
+                            let mut entries = Vec::with_capacity(n);
+                            for _ in 0..n {
+                                let c = next()?;
+                                if !c.is_empty() {
+                                    entries.push(c.as_str());
+                                }
+                            }
+                            entries
+                        }
+                    };
+                    parse_list(&entries, s)?
+                }
+            };
+            element.set_property(&def.name, property);
+        }
+        Ok(element)
+    }
+
+    /// Reads an entire table into a `Ply` with a single element named `element_name`.
+    ///
+    /// With `schema`, cells are parsed as declared and the header row, if any, is skipped.
+    /// Without it, the types are inferred from all rows, see `infer_element_def()`.
+    /// Rows are then kept as one `f64` per cell until the types are known, not as text.
+    pub fn read_ply<T: Read>(
+        &self,
+        source: &mut T,
+        element_name: &str,
+        schema: Option<&ElementDef>,
+    ) -> io::Result<Ply<DefaultElement>> {
+        let mut reader = BufReader::new(source);
+        let names = self.read_header_row(&mut reader)?;
+        let mut elements = Vec::new();
+        let element_def = match schema {
+            Some(schema) => {
+                let mut def = schema.clone();
+                def.name = element_name.to_string();
+                while let Some(e) = self.read_row(&mut reader, &def)? {
+                    elements.push(e);
+                }
+                def
+            }
+            None => {
+                let mut inference = Inference::new(names.as_deref());
+                let mut values = Vec::new();
+                while let Some(cells) = self.read_cells(&mut reader)? {
+                    inference.add_row(&cells, &mut values)?;
+                }
+                let def = inference.element_def(element_name);
+                let columns = def.properties.len();
+                for row in 0..def.count {
+                    let mut e = DefaultElement::new();
+                    for (k, p) in def.properties.iter().enumerate() {
+                        let v = values[row * columns + k];
+                        e.insert(p.name.clone(), inferred_value(v, p.data_type));
+                    }
+                    elements.push(e);
+                }
+                def
+            }
+        };
+        let mut ply = Ply::<DefaultElement>::new();
+        ply.header.elements.push(element_def);
+        ply.payload.insert(element_name.to_string(), elements);
+        ply.make_consistent().map_err(|e| invalid(e.to_string()))?;
+        Ok(ply)
+    }
+}
+
+/// Converts a value checked by `ColumnGuess` to the type it inferred.
+fn inferred_value(v: f64, data_type: PropertyType) -> Property {
+    match data_type {
+        PropertyType::Scalar(ScalarType::Int) => Property::Int(v as i32),
+        PropertyType::Scalar(ScalarType::UInt) => Property::UInt(v as u32),
+        PropertyType::Scalar(ScalarType::Float) => Property::Float(v as f32),
+        _ => Property::Double(v),
+    }
+}
+
+fn parse<D: std::str::FromStr>(cell: &str) -> io::Result<D> {
+    cell.trim()
+        .parse()
+        .map_err(|_| invalid(format!("Can't parse `{}`.", cell)))
+}
+
+fn parse_scalar(cell: &str, scalar_type: ScalarType) -> io::Result<Property> {
+    Ok(match scalar_type {
+        ScalarType::Char => Property::Char(parse(cell)?),
+        ScalarType::UChar => Property::UChar(parse(cell)?),
+        ScalarType::Short => Property::Short(parse(cell)?),
+        ScalarType::UShort => Property::UShort(parse(cell)?),
+        ScalarType::Int => Property::Int(parse(cell)?),
+        ScalarType::UInt => Property::UInt(parse(cell)?),
+        ScalarType::Float => Property::Float(parse(cell)?),
+        ScalarType::Double => Property::Double(parse(cell)?),
+    })
+}
+
+fn parse_list(entries: &[&str], scalar_type: ScalarType) -> io::Result<Property> {
+    fn l<D: std::str::FromStr>(entries: &[&str]) -> io::Result<Vec<D>> {
+        entries.iter().map(|e| parse(e)).collect()
+    }
+    Ok(match scalar_type {
+        ScalarType::Char => Property::ListChar(l(entries)?),
+        ScalarType::UChar => Property::ListUChar(l(entries)?),
+        ScalarType::Short => Property::ListShort(l(entries)?),
+        ScalarType::UShort => Property::ListUShort(l(entries)?),
+        ScalarType::Int => Property::ListInt(l(entries)?),
+        ScalarType::UInt => Property::ListUInt(l(entries)?),
+        ScalarType::Float => Property::ListFloat(l(entries)?),
+        ScalarType::Double => Property::ListDouble(l(entries)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn house() -> Ply<DefaultElement> {
+        let path = "example_plys/house_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        Parser::<DefaultElement>::new().read_ply(&mut f).unwrap()
+    }
+
+    #[test]
+    fn lists_joined_ok() {
+        let ply = house();
+        let mut w = CsvWriter::new(CsvOptions::csv());
+        let mut out = Vec::new();
+        w.write_element(&mut out, &ply, "face").unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "vertex_indices\n0 1 2\n0 3 1\n1 3 4\n"
+        );
+        let r = CsvReader::new(CsvOptions::csv());
+        let schema = &ply.header.elements[1];
+        let read = r
+            .read_ply(&mut out.as_slice(), "face", Some(schema))
+            .unwrap();
+        assert_eq!(read.payload["face"], ply.payload["face"]);
+    }
+    #[test]
+    fn lists_expanded_ok() {
+        let ply = house();
+        let options = CsvOptions {
+            lists: ListColumns::Expand(4),
+            ..CsvOptions::tsv()
+        };
+        let mut out = Vec::new();
+        CsvWriter::new(options.clone())
+            .write_element(&mut out, &ply, "face")
+            .unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.starts_with(
+            "vertex_indices_0\tvertex_indices_1\tvertex_indices_2\tvertex_indices_3\n0\t1\t2\t\n"
+        ));
+        let schema = &ply.header.elements[1];
+        let read = CsvReader::new(options)
+            .read_ply(&mut out.as_slice(), "face", Some(schema))
+            .unwrap();
+        assert_eq!(read.payload["face"], ply.payload["face"]);
+
+        let short = CsvOptions {
+            lists: ListColumns::Expand(2),
+            ..CsvOptions::csv()
+        };
+        assert!(CsvWriter::new(short)
+            .write_element(&mut Vec::new(), &ply, "face")
+            .is_err());
+    }
+    #[test]
+    fn infer_types_ok() {
+        let source = "i,u,f,d\n-1,1,0.5,0.1234567891\n2,4000000000,1e10,2\n";
+        let ply = CsvReader::new(CsvOptions::csv())
+            .read_ply(&mut source.as_bytes(), "row", None)
+            .unwrap();
+        let types: Vec<PropertyType> = ply.header.elements[0]
+            .properties
+            .iter()
+            .map(|p| p.data_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                PropertyType::Scalar(ScalarType::Int),
+                PropertyType::Scalar(ScalarType::UInt),
+                PropertyType::Scalar(ScalarType::Float),
+                PropertyType::Scalar(ScalarType::Double),
+            ]
+        );
+        assert_eq!(ply.header.elements[0].count, 2);
+    }
+    #[test]
+    fn xyz_ok() {
+        let source = "0 0 0 255\n1.5 2 3 128\n\n";
+        let ply = CsvReader::new(CsvOptions::xyz())
+            .read_ply(&mut source.as_bytes(), "vertex", None)
+            .unwrap();
+        let names: Vec<&str> = ply.header.elements[0]
+            .properties
+            .iter()
+            .map(|p| p.name.as_ref())
+            .collect();
+        assert_eq!(names, vec!["x", "y", "z", "column_3"]);
+        assert_eq!(ply.payload["vertex"][1]["x"], Property::Float(1.5));
+        let mut out = Vec::new();
+        CsvWriter::new(CsvOptions::xyz())
+            .write_element(&mut out, &ply, "vertex")
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "0 0 0 255\n1.5 2 3 128\n");
+    }
+    #[test]
+    fn quoted_delimiter_ok() {
+        let source = "\"x,m\",\"label\"\n1.5,\"2\"\n";
+        let ply = CsvReader::new(CsvOptions::csv())
+            .read_ply(&mut source.as_bytes(), "row", None)
+            .unwrap();
+        let def = &ply.header.elements[0];
+        assert_eq!(def.properties[0].name, "x,m");
+        assert_eq!(def.properties[1].name, "label");
+        assert_eq!(ply.payload["row"][0]["x,m"], Property::Float(1.5));
+        assert_eq!(ply.payload["row"][0]["label"], Property::Int(2));
+    }
+    #[test]
+    fn not_a_number_err() {
+        let source = "x,y\n1,a\n";
+        assert!(CsvReader::new(CsvOptions::csv())
+            .read_ply(&mut source.as_bytes(), "vertex", None)
+            .is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! Binary glTF (`.glb`) export, requires the `gltf` feature.
+//!
+//! The file holds a single scene with one node and one mesh primitive.
+//! Its binary buffer contains, in this order:
+//!
+//! - `POSITION` as `float` vectors, with the bounding box as `min` and `max`.
+//! - `NORMAL` as `float` vectors, normalized.
+//! - `COLOR_0` as normalized `unsigned byte` RGBA.
+//! - `TEXCOORD_0` as `float` pairs, with `t` flipped to the top left origin of glTF.
+//! - The triangle indices in the smallest type that can address all vertices.
+//!
+//! Polygons are triangulated with ear clipping.
+//! A mesh without faces becomes a point primitive.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::convert::gltf;
+//! # use ply_rs::parser::Parser;
+//! # use ply_rs::ply::DefaultElement;
+//! let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+//! let mut f = std::fs::File::open(path).unwrap();
+//! let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+//!
+//! let mut out = Vec::new();
+//! let written = gltf::write_glb(&mut out, &ply).unwrap();
+//! assert_eq!(written, out.len());
+//! assert_eq!(&out[0..4], b"glTF");
+//! ```
+
+use std::io;
+use std::io::{ErrorKind, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use serde_json::{json, Value};
+
+use super::mesh_error;
+use crate::mesh::{triangulate_polygon, Mesh, TriangulationMethod};
+use crate::ply::{Ply, PropertyAccess};
+
+const MAGIC: u32 = 0x4654_6C67;
+const VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const MODE_POINTS: u32 = 0;
+const MODE_TRIANGLES: u32 = 4;
+
+/// Binary buffer and the JSON describing its parts.
+#[derive(Debug, Default)]
+struct Buffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffer {
+    /// Appends `bytes` as a new buffer view, returns the index of its accessor.
+    fn push(&mut self, bytes: &[u8], target: u32, mut accessor: Value) -> usize {
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        accessor["bufferView"] = json!(self.views.len());
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.data.extend_from_slice(bytes);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_floats<const N: usize>(&mut self, values: &[[f32; N]], type_name: &str) -> usize {
+        let mut bytes = Vec::with_capacity(values.len() * N * 4);
+        for v in values {
+            for c in v {
+                bytes.write_f32::<LittleEndian>(*c).unwrap();
+            }
+        }
+        self.push(
+            &bytes,
+            ARRAY_BUFFER,
+            json!({
+                "componentType": FLOAT,
+                "count": values.len(),
+                "type": type_name,
+            }),
+        )
+    }
+}
+
+fn normalized(n: [f32; 3]) -> [f32; 3] {
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len > 0.0 {
+        [n[0] / len, n[1] / len, n[2] / len]
+    } else {
+        n
+    }
+}
+
+/// Writes `mesh` as binary glTF, returns the number of bytes written.
+pub fn write_mesh<T: Write>(out: &mut T, mesh: &Mesh) -> io::Result<usize> {
+    mesh.check().map_err(mesh_error)?;
+    let v = &mesh.vertices;
+    let bounding_box = v.bounding_box().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            "glTF requires at least one vertex.",
+        )
+    })?;
+
+    let mut buffer = Buffer::default();
+    let mut attributes = json!({});
+    let position = buffer.push_floats(&v.positions, "VEC3");
+    buffer.accessors[position]["min"] = json!(bounding_box.0);
+    buffer.accessors[position]["max"] = json!(bounding_box.1);
+    attributes["POSITION"] = json!(position);
+    if let Some(ref normals) = v.normals {
+        let normals: Vec<[f32; 3]> = normals.iter().map(|n| normalized(*n)).collect();
+        attributes["NORMAL"] = json!(buffer.push_floats(&normals, "VEC3"));
+    }
+    if let Some(ref colors) = v.colors {
+        let bytes: Vec<u8> = colors
+            .iter()
+            .flat_map(|c| c.map(|d| (d.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .collect();
+        let accessor = json!({
+            "componentType": UNSIGNED_BYTE,
+            "normalized": true,
+            "count": colors.len(),
+            "type": "VEC4",
+        });
+        attributes["COLOR_0"] = json!(buffer.push(&bytes, ARRAY_BUFFER, accessor));
+    }
+    if let Some(ref tex_coords) = v.tex_coords {
+        let flipped: Vec<[f32; 2]> = tex_coords.iter().map(|t| [t[0], 1.0 - t[1]]).collect();
+        attributes["TEXCOORD_0"] = json!(buffer.push_floats(&flipped, "VEC2"));
+    }
+
+    let mut primitive = json!({ "attributes": attributes });
+    let indices: Vec<u32> = mesh
+        .faces
+        .iter()
+        .flat_map(|f| triangulate_polygon(f, &v.positions, TriangulationMethod::EarClipping))
+        .flatten()
+        .collect();
+    // without triangles, e.g. if all faces are degenerate, write the vertices as points
+    if indices.is_empty() {
+        primitive["mode"] = json!(MODE_POINTS);
+    } else {
+        // the maximum value of each type is reserved for primitive restart
+        let mut bytes = Vec::new();
+        let component_type = if v.len() <= u8::MAX as usize {
+            bytes.extend(indices.iter().map(|i| *i as u8));
+            UNSIGNED_BYTE
+        } else if v.len() <= u16::MAX as usize {
+            for i in &indices {
+                bytes.write_u16::<LittleEndian>(*i as u16)?;
+            }
+            UNSIGNED_SHORT
+        } else {
+            for i in &indices {
+                bytes.write_u32::<LittleEndian>(*i)?;
+            }
+            UNSIGNED_INT
+        };
+        let accessor = json!({
+            "componentType": component_type,
+            "count": indices.len(),
+            "type": "SCALAR",
+        });
+        primitive["indices"] = json!(buffer.push(&bytes, ELEMENT_ARRAY_BUFFER, accessor));
+        primitive["mode"] = json!(MODE_TRIANGLES);
+    }
+    while !buffer.data.len().is_multiple_of(4) {
+        buffer.data.push(0);
+    }
+
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "ply-rs" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{ "primitives": [primitive] }],
+        "buffers": [{ "byteLength": buffer.data.len() }],
+        "bufferViews": buffer.views,
+        "accessors": buffer.accessors,
+    });
+    let mut json = serde_json::to_vec(&document)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+
+    let length = 12 + 8 + json.len() + 8 + buffer.data.len();
+    let length_u32 = u32::try_from(length)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "glTF files are limited to 4 GiB."))?;
+    out.write_u32::<LittleEndian>(MAGIC)?;
+    out.write_u32::<LittleEndian>(VERSION)?;
+    out.write_u32::<LittleEndian>(length_u32)?;
+    out.write_u32::<LittleEndian>(json.len() as u32)?;
+    out.write_u32::<LittleEndian>(CHUNK_JSON)?;
+    out.write_all(&json)?;
+    out.write_u32::<LittleEndian>(buffer.data.len() as u32)?;
+    out.write_u32::<LittleEndian>(CHUNK_BIN)?;
+    out.write_all(&buffer.data)?;
+    Ok(length)
+}
+
+/// Writes the `vertex` and `face` elements of `ply` as binary glTF, see `write_mesh()`.
+pub fn write_glb<T: Write, E: PropertyAccess>(out: &mut T, ply: &Ply<E>) -> io::Result<usize> {
+    let mesh = Mesh::from_ply(ply).map_err(mesh_error)?;
+    write_mesh(out, &mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::PointCloud;
+    use crate::parser::Parser;
+    use crate::ply::DefaultElement;
+    use byteorder::ByteOrder;
+
+    /// Splits a `.glb` file into its JSON document and binary chunk.
+    fn parse(glb: &[u8]) -> (Value, &[u8]) {
+        assert_eq!(LittleEndian::read_u32(&glb[0..4]), MAGIC);
+        assert_eq!(LittleEndian::read_u32(&glb[4..8]), VERSION);
+        assert_eq!(LittleEndian::read_u32(&glb[8..12]) as usize, glb.len());
+        let json_len = LittleEndian::read_u32(&glb[12..16]) as usize;
+        assert_eq!(LittleEndian::read_u32(&glb[16..20]), CHUNK_JSON);
+        let document = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        let bin = &glb[20 + json_len..];
+        let bin_len = LittleEndian::read_u32(&bin[0..4]) as usize;
+        assert_eq!(LittleEndian::read_u32(&bin[4..8]), CHUNK_BIN);
+        assert_eq!(bin.len(), 8 + bin_len);
+        (document, &bin[8..])
+    }
+
+    fn accessor_bytes<'a>(document: &Value, bin: &'a [u8], accessor: &Value) -> &'a [u8] {
+        let view = &document["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap() as usize;
+        assert_eq!(offset % 4, 0);
+        &bin[offset..offset + view["byteLength"].as_u64().unwrap() as usize]
+    }
+
+    #[test]
+    fn cube_ok() {
+        let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+        let mut out = Vec::new();
+        write_glb(&mut out, &ply).unwrap();
+        let (document, bin) = parse(&out);
+
+        let primitive = &document["meshes"][0]["primitives"][0];
+        assert_eq!(primitive["mode"], json!(MODE_TRIANGLES));
+        let accessors = &document["accessors"];
+        let position = &accessors[primitive["attributes"]["POSITION"].as_u64().unwrap() as usize];
+        assert_eq!(position["count"], json!(8));
+        assert_eq!(position["min"], json!([0.0, 0.0, 0.0]));
+        assert_eq!(position["max"], json!([1.0, 1.0, 1.0]));
+
+        let indices = &accessors[primitive["indices"].as_u64().unwrap() as usize];
+        assert_eq!(indices["componentType"], json!(UNSIGNED_BYTE));
+        assert_eq!(indices["count"], json!(36));
+        let bytes = accessor_bytes(&document, bin, indices);
+        assert!(bytes.iter().all(|i| *i < 8));
+    }
+    #[test]
+    fn point_cloud_ok() {
+        let mut vertices = PointCloud::new();
+        vertices.positions = (0..300).map(|i| [i as f32, 0.0, 0.0]).collect();
+        vertices.colors = Some(vec![[1.0, 0.5, 0.0, 1.0]; 300]);
+        vertices.normals = Some(vec![[0.0, 0.0, 2.0]; 300]);
+        let mesh = Mesh {
+            vertices,
+            faces: Vec::new(),
+        };
+        let mut out = Vec::new();
+        write_mesh(&mut out, &mesh).unwrap();
+        let (document, bin) = parse(&out);
+
+        let primitive = &document["meshes"][0]["primitives"][0];
+        assert_eq!(primitive["mode"], json!(MODE_POINTS));
+        assert!(primitive.get("indices").is_none());
+        let accessors = &document["accessors"];
+        let color = &accessors[primitive["attributes"]["COLOR_0"].as_u64().unwrap() as usize];
+        assert_eq!(color["normalized"], json!(true));
+        assert_eq!(
+            &accessor_bytes(&document, bin, color)[0..4],
+            &[255, 128, 0, 255]
+        );
+        let normal = &accessors[primitive["attributes"]["NORMAL"].as_u64().unwrap() as usize];
+        let bytes = accessor_bytes(&document, bin, normal);
+        assert_eq!(LittleEndian::read_f32(&bytes[8..12]), 1.0);
+    }
+    #[test]
+    fn index_type_ok() {
+        let mut vertices = PointCloud::new();
+        vertices.positions = (0..300).map(|i| [i as f32, (i % 2) as f32, 0.0]).collect();
+        let mesh = Mesh {
+            vertices,
+            faces: vec![vec![0, 1, 299]],
+        };
+        let mut out = Vec::new();
+        write_mesh(&mut out, &mesh).unwrap();
+        let (document, bin) = parse(&out);
+        let indices = &document["accessors"][1];
+        assert_eq!(indices["componentType"], json!(UNSIGNED_SHORT));
+        let bytes = accessor_bytes(&document, bin, indices);
+        assert_eq!(LittleEndian::read_u16(&bytes[4..6]), 299);
+    }
+    #[test]
+    fn degenerate_faces_ok() {
+        let mut vertices = PointCloud::new();
+        vertices.positions = vec![[0.0; 3], [1.0, 0.0, 0.0]];
+        let mesh = Mesh {
+            vertices,
+            faces: vec![vec![0, 1], vec![1]],
+        };
+        let mut out = Vec::new();
+        write_mesh(&mut out, &mesh).unwrap();
+        let (document, _) = parse(&out);
+        let primitive = &document["meshes"][0]["primitives"][0];
+        assert_eq!(primitive["mode"], json!(MODE_POINTS));
+        assert!(primitive.get("indices").is_none());
+        assert_eq!(document["accessors"].as_array().unwrap().len(), 1);
+    }
+    #[test]
+    fn empty_err() {
+        assert!(write_mesh(&mut Vec::new(), &Mesh::new()).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! Conversion between PLY and other mesh and point cloud formats.
+//!
+//! Converters work on the conventions of the `mesh` module:
+//! a `vertex` element with positions and optional attributes and a `face` element with index lists.
+//! Readers return a `Ply<DefaultElement>` using the property names and types written by `Mesh::to_ply()`.
+//!
+//! Errors are reported as `io::Error`, malformed input uses `ErrorKind::InvalidInput` like the `Parser`.
+
+use std::io;
+use std::io::ErrorKind;
+
+use crate::mesh::MeshError;
+
+pub mod csv;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod obj;
+pub mod off;
+pub mod pcd;
+pub mod stl;
+
+/// Largest capacity reserved up front for a count read from a file, bigger collections grow on demand.
+const MAX_RESERVE: usize = 1 << 16;
+
+/// Capacity to reserve for `count` entries announced by a file, which might be corrupt.
+fn reserve(count: usize) -> usize {
+    count.min(MAX_RESERVE)
+}
+
+fn mesh_error(e: MeshError) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, e.to_string())
+}
+
+fn parse_error(line_index: usize, line: &str, message: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("Line {}: {}\n\tString: '{}'", line_index, message, line),
+    )
+}
@@ -0,0 +1,378 @@
+//! Wavefront OBJ import and export.
+//!
+//! Supported statements are `v x y z [r g b]`, `vt u [v [w]]`, `vn x y z`,
+//! `f` with corners in the forms `v`, `v/vt`, `v/vt/vn` or `v//vn`, and `#` comments.
+//! Indices may be absolute (starting at 1) or relative to the end (negative).
+//! Other statements, e.g. groups, materials or lines, are ignored.
+//!
+//! OBJ indexes texture coordinates and normals per face corner, while PLY stores them per vertex.
+//! When reading, a vertex is only duplicated if its position is used with different attributes,
+//! so positions and topology survive a round trip unchanged.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::convert::obj;
+//! let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+//! let ply = obj::read_obj(&mut source.as_bytes()).unwrap();
+//! assert_eq!(ply.payload["vertex"].len(), 3);
+//!
+//! let mut out = Vec::new();
+//! obj::write_obj(&mut out, &ply).unwrap();
+//! assert!(String::from_utf8(out).unwrap().ends_with("f 1 2 3\n"));
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::{mesh_error, parse_error};
+use crate::mesh::{Mesh, PointCloud};
+use crate::ply::{Comment, DefaultElement, Encoding, Ply, PropertyAccess};
+
+/// Writes `mesh` as OBJ, returns the number of bytes written.
+///
+/// Every vertex with texture coordinates or a normal gets its own `vt` and `vn` statement,
+/// so all three indices of a face corner are the same.
+pub fn write_mesh<T: Write>(out: &mut T, mesh: &Mesh) -> io::Result<usize> {
+    write_obj_with_comments(out, mesh, &[])
+}
+
+/// Writes the `vertex` and `face` elements of `ply` as OBJ, returns the number of bytes written.
+///
+/// Header comments are written as `#` comments, see `write_mesh()`.
+pub fn write_obj<T: Write, E: PropertyAccess>(out: &mut T, ply: &Ply<E>) -> io::Result<usize> {
+    let mesh = Mesh::from_ply(ply).map_err(mesh_error)?;
+    write_obj_with_comments(out, &mesh, &ply.header.comments)
+}
+
+fn write_obj_with_comments<T: Write>(
+    out: &mut T,
+    mesh: &Mesh,
+    comments: &[Comment],
+) -> io::Result<usize> {
+    mesh.check().map_err(mesh_error)?;
+    let v = &mesh.vertices;
+    let mut written = 0;
+    let mut line = |s: String| -> io::Result<()> {
+        out.write_all(s.as_bytes())?;
+        written += s.len();
+        Ok(())
+    };
+    for c in comments {
+        line(format!("# {}\n", c))?;
+    }
+    for (i, p) in v.positions.iter().enumerate() {
+        match v.colors {
+            Some(ref c) => line(format!(
+                "v {} {} {} {} {} {}\n",
+                p[0], p[1], p[2], c[i][0], c[i][1], c[i][2]
+            ))?,
+            None => line(format!("v {} {} {}\n", p[0], p[1], p[2]))?,
+        }
+    }
+    if let Some(ref tex_coords) = v.tex_coords {
+        for t in tex_coords {
+            line(format!("vt {} {}\n", t[0], t[1]))?;
+        }
+    }
+    if let Some(ref normals) = v.normals {
+        for n in normals {
+            line(format!("vn {} {} {}\n", n[0], n[1], n[2]))?;
+        }
+    }
+    for f in &mesh.faces {
+        let corners: Vec<String> = f
+            .iter()
+            .map(|i| {
+                let i = i + 1;
+                match (v.tex_coords.is_some(), v.normals.is_some()) {
+                    (false, false) => format!("{}", i),
+                    (true, false) => format!("{}/{}", i, i),
+                    (false, true) => format!("{}//{}", i, i),
+                    (true, true) => format!("{}/{}/{}", i, i, i),
+                }
+            })
+            .collect();
+        line(format!("f {}\n", corners.join(" ")))?;
+    }
+    Ok(written)
+}
+
+/// Reads an OBJ file into a `Mesh`.
+pub fn read_mesh<T: Read>(source: &mut T) -> io::Result<Mesh> {
+    Ok(read(source)?.0)
+}
+
+/// Reads an OBJ file into a `Ply` with a `vertex` and a `face` element.
+///
+/// The header is the one of `Mesh::to_ply()` using ascii encoding,
+/// comments before the first statement become header comments.
+pub fn read_obj<T: Read>(source: &mut T) -> io::Result<Ply<DefaultElement>> {
+    let (mesh, comments) = read(source)?;
+    let mut ply = mesh.to_ply(Encoding::Ascii).map_err(mesh_error)?;
+    ply.header.comments = comments;
+    Ok(ply)
+}
+
+/// Indices into positions, texture coordinates and normals, starting at 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Corner {
+    v: usize,
+    vt: Option<usize>,
+    vn: Option<usize>,
+}
+
+fn parse_floats<const N: usize>(
+    tokens: &[&str],
+    min: usize,
+    line_index: usize,
+    line: &str,
+) -> io::Result<[f32; N]> {
+    if tokens.len() < min {
+        return Err(parse_error(
+            line_index,
+            line,
+            &format!("Expected at least {} numbers.", min),
+        ));
+    }
+    let mut r = [0.0; N];
+    for (d, t) in r.iter_mut().zip(tokens.iter()) {
+        *d = t
+            .parse()
+            .map_err(|_| parse_error(line_index, line, &format!("Invalid number `{}`.", t)))?;
+    }
+    Ok(r)
+}
+
+/// Turns an absolute (1 based) or relative (negative) index into a 0 based index.
+fn resolve(token: &str, defined: usize, line_index: usize, line: &str) -> io::Result<usize> {
+    let i: i64 = token
+        .parse()
+        .map_err(|_| parse_error(line_index, line, &format!("Invalid index `{}`.", token)))?;
+    let r = match i {
+        0 => return Err(parse_error(line_index, line, "Index 0 is not allowed.")),
+        i if i > 0 => i - 1,
+        i => defined as i64 + i,
+    };
+    if r < 0 {
+        return Err(parse_error(
+            line_index,
+            line,
+            &format!("Relative index {} points before the first entry.", i),
+        ));
+    }
+    Ok(r as usize)
+}
+
+fn read<T: Read>(source: &mut T) -> io::Result<(Mesh, Vec<Comment>)> {
+    let reader = BufReader::new(source);
+    let mut comments = Vec::new();
+    let mut positions = Vec::new();
+    let mut colors: Vec<Option<[f32; 4]>> = Vec::new();
+    let mut tex_coords = Vec::new();
+    let mut normals = Vec::new();
+    let mut faces: Vec<Vec<Corner>> = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_index = i + 1;
+        let trimmed = line.trim();
+        if let Some(c) = trimmed.strip_prefix('#') {
+            if faces.is_empty() && positions.is_empty() {
+                comments.push(c.trim().to_string());
+            }
+            continue;
+        }
+        let content = trimmed.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        let (keyword, args) = match tokens.split_first() {
+            Some(t) => t,
+            None => continue,
+        };
+        match *keyword {
+            "v" => {
+                let v: [f32; 6] = parse_floats(args, 3, line_index, &line)?;
+                positions.push([v[0], v[1], v[2]]);
+                colors.push(if args.len() >= 6 {
+                    Some([v[3], v[4], v[5], 1.0])
+                } else {
+                    None
+                });
+            }
+            "vt" => tex_coords.push(parse_floats::<2>(args, 1, line_index, &line)?),
+            "vn" => normals.push(parse_floats::<3>(args, 3, line_index, &line)?),
+            "f" => {
+                if args.len() < 3 {
+                    return Err(parse_error(
+                        line_index,
+                        &line,
+                        "A face needs at least 3 corners.",
+                    ));
+                }
+                let mut face = Vec::with_capacity(args.len());
+                for corner in args {
+                    let mut parts = corner.split('/');
+                    let v = resolve(
+                        parts.next().unwrap_or(""),
+                        positions.len(),
+                        line_index,
+                        &line,
+                    )?;
+                    let vt = match parts.next() {
+                        None | Some("") => None,
+                        Some(t) => Some(resolve(t, tex_coords.len(), line_index, &line)?),
+                    };
+                    let vn = match parts.next() {
+                        None | Some("") => None,
+                        Some(t) => Some(resolve(t, normals.len(), line_index, &line)?),
+                    };
+                    face.push(Corner { v, vt, vn });
+                }
+                faces.push(face);
+            }
+            _ => (),
+        }
+    }
+
+    for (i, c) in faces.iter().flatten().enumerate() {
+        let out_of_range = c.v >= positions.len()
+            || c.vt.is_some_and(|t| t >= tex_coords.len())
+            || c.vn.is_some_and(|n| n >= normals.len());
+        if out_of_range {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Face corner {} references an undefined entry.", i),
+            ));
+        }
+    }
+
+    // Assign attributes to vertices, duplicate a vertex only on conflicting attributes.
+    let mut attributes: Vec<Option<(Option<usize>, Option<usize>)>> = vec![None; positions.len()];
+    let mut sources: Vec<usize> = (0..positions.len()).collect();
+    let mut splits: HashMap<Corner, u32> = HashMap::new();
+    let mut mesh_faces = Vec::with_capacity(faces.len());
+    for face in &faces {
+        let mut f = Vec::with_capacity(face.len());
+        for c in face {
+            let key = (c.vt, c.vn);
+            let index = match attributes[c.v] {
+                None => {
+                    attributes[c.v] = Some(key);
+                    c.v as u32
+                }
+                Some(k) if k == key => c.v as u32,
+                Some(_) => *splits.entry(*c).or_insert_with(|| {
+                    sources.push(c.v);
+                    attributes.push(Some(key));
+                    (sources.len() - 1) as u32
+                }),
+            };
+            f.push(index);
+        }
+        mesh_faces.push(f);
+    }
+
+    let has_tex = attributes.iter().any(|a| matches!(a, Some((Some(_), _))));
+    let has_normals = attributes.iter().any(|a| matches!(a, Some((_, Some(_)))));
+    let has_colors = colors.iter().any(|c| c.is_some());
+    let vertices = PointCloud {
+        positions: sources.iter().map(|s| positions[*s]).collect(),
+        normals: if has_normals {
+            Some(
+                attributes
+                    .iter()
+                    .map(|a| a.and_then(|a| a.1).map_or([0.0; 3], |n| normals[n]))
+                    .collect(),
+            )
+        } else {
+            None
+        },
+        colors: if has_colors {
+            Some(
+                sources
+                    .iter()
+                    .map(|s| colors[*s].unwrap_or([1.0; 4]))
+                    .collect(),
+            )
+        } else {
+            None
+        },
+        tex_coords: if has_tex {
+            Some(
+                attributes
+                    .iter()
+                    .map(|a| a.and_then(|a| a.0).map_or([0.0; 2], |t| tex_coords[t]))
+                    .collect(),
+            )
+        } else {
+            None
+        },
+    };
+    Ok((
+        Mesh {
+            vertices,
+            faces: mesh_faces,
+        },
+        comments,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn round_trip_ok() {
+        let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+        let mut out = Vec::new();
+        let n = write_obj(&mut out, &ply).unwrap();
+        assert_eq!(n, out.len());
+        let read = read_obj(&mut out.as_slice()).unwrap();
+        assert_eq!(read.header.comments, ply.header.comments);
+        assert_eq!(
+            Mesh::from_ply(&read).unwrap(),
+            Mesh::from_ply(&ply).unwrap()
+        );
+    }
+    #[test]
+    fn relative_indices_and_polygons_ok() {
+        let source = "\
+            v 0 0 0\n\
+            v 1 0 0\n\
+            v 1 1 0\n\
+            v 0 1 0\n\
+            vn 0 0 1\n\
+            f -4//1 -3//1 -2//1 -1//1\n\
+            f 1//1 3//1 4//-1\n";
+        let mesh = read_mesh(&mut source.as_bytes()).unwrap();
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2, 3], vec![0, 2, 3]]);
+        assert_eq!(mesh.vertices.normals.unwrap()[2], [0.0, 0.0, 1.0]);
+    }
+    #[test]
+    fn conflicting_attributes_split_ok() {
+        let source = "\
+            v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+            vt 0 0\nvt 1 0\nvt 0 1\nvt 0.5 0.5\n\
+            f 1/1 2/2 3/3\n\
+            f 1/4 3/3 2/2\n";
+        let mesh = read_mesh(&mut source.as_bytes()).unwrap();
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![3, 2, 1]]);
+        assert_eq!(mesh.vertices.positions[3], [0.0; 3]);
+        assert_eq!(mesh.vertices.tex_coords.unwrap()[3], [0.5, 0.5]);
+    }
+    #[test]
+    fn invalid_index_err() {
+        for source in [
+            "v 0 0 0\nf 0 1 1\n",
+            "v 0 0 0\nf 1 1 5\n",
+            "v 0 0 0\nf -2 1 1\n",
+        ] {
+            let e = read_obj(&mut source.as_bytes()).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Object File Format (OFF) import and export, including the `COFF`, `NOFF` and `STOFF` variants.
+//!
+//! The keyword on the first line announces the optional vertex attributes:
+//! `N` for normals, `C` for colors and `ST` for texture coordinates, e.g. `CNOFF`.
+//! Vertex lines store `x y z [nx ny nz] [r g b a] [s t]` in this order.
+//! Face lines store the number of corners followed by the vertex indices,
+//! trailing face colors are ignored.
+//!
+//! Colors are read as integers in `[0, 255]` unless one of them contains a decimal point,
+//! in which case all colors are taken as floats in `[0, 1]`. Colors are written as integers.
+//!
+//! Reading also accepts the counts on the keyword line, e.g. `OFF8 6 0`,
+//! as found in some ModelNet files.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::convert::off;
+//! let source = "COFF\n3 1 0\n0 0 0 255 0 0 255\n1 0 0 0 255 0 255\n0 1 0 0 0 255 255\n3 0 1 2\n";
+//! let ply = off::read_off(&mut source.as_bytes()).unwrap();
+//! let vertex = &ply.header.elements[0];
+//! assert_eq!(vertex.properties[3].name, "red");
+//! assert_eq!(ply.payload["face"].len(), 1);
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+
+use super::{mesh_error, parse_error};
+use crate::mesh::{Mesh, PointCloud};
+use crate::ply::{DefaultElement, Encoding, Ply, PropertyAccess};
+
+/// Writes `mesh` as OFF, returns the number of bytes written.
+///
+/// The keyword reflects the available vertex attributes, e.g. `NOFF` if the mesh has normals.
+pub fn write_mesh<T: Write>(out: &mut T, mesh: &Mesh) -> io::Result<usize> {
+    mesh.check().map_err(mesh_error)?;
+    let v = &mesh.vertices;
+    let mut s = String::new();
+    if v.tex_coords.is_some() {
+        s.push_str("ST");
+    }
+    if v.colors.is_some() {
+        s.push('C');
+    }
+    if v.normals.is_some() {
+        s.push('N');
+    }
+    s.push_str("OFF\n");
+    s.push_str(&format!("{} {} 0\n", v.len(), mesh.faces.len()));
+    for (i, p) in v.positions.iter().enumerate() {
+        s.push_str(&format!("{} {} {}", p[0], p[1], p[2]));
+        if let Some(ref normals) = v.normals {
+            let n = normals[i];
+            s.push_str(&format!(" {} {} {}", n[0], n[1], n[2]));
+        }
+        if let Some(ref colors) = v.colors {
+            for c in colors[i].iter() {
+                s.push_str(&format!(" {}", (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+            }
+        }
+        if let Some(ref tex_coords) = v.tex_coords {
+            let t = tex_coords[i];
+            s.push_str(&format!(" {} {}", t[0], t[1]));
+        }
+        s.push('\n');
+    }
+    for f in &mesh.faces {
+        s.push_str(&f.len().to_string());
+        for i in f {
+            s.push_str(&format!(" {}", i));
+        }
+        s.push('\n');
+    }
+    out.write_all(s.as_bytes())?;
+    Ok(s.len())
+}
+
+/// Writes the `vertex` and `face` elements of `ply` as OFF, see `write_mesh()`.
+pub fn write_off<T: Write, E: PropertyAccess>(out: &mut T, ply: &Ply<E>) -> io::Result<usize> {
+    let mesh = Mesh::from_ply(ply).map_err(mesh_error)?;
+    write_mesh(out, &mesh)
+}
+
+/// Reads an OFF file of any supported variant into a `Mesh`.
+pub fn read_mesh<T: Read>(source: &mut T) -> io::Result<Mesh> {
+    let reader = BufReader::new(source);
+    let mut lines = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let content = line.split('#').next().unwrap_or("").trim().to_string();
+        if !content.is_empty() {
+            lines.push((i + 1, content));
+        }
+    }
+    let mut lines = lines.into_iter();
+    let unexpected_end = || io::Error::new(ErrorKind::UnexpectedEof, "OFF file ends early.");
+
+    let (line_index, first) = lines.next().ok_or_else(unexpected_end)?;
+    let keyword_end = first
+        .find("OFF")
+        .ok_or_else(|| parse_error(line_index, &first, "Expected keyword `OFF`."))?;
+    let mut prefix = &first[..keyword_end];
+    let has_tex = prefix.starts_with("ST");
+    if has_tex {
+        prefix = &prefix[2..];
+    }
+    let has_colors = prefix.starts_with('C');
+    if has_colors {
+        prefix = &prefix[1..];
+    }
+    let has_normals = prefix.starts_with('N');
+    if has_normals {
+        prefix = &prefix[1..];
+    }
+    if !prefix.is_empty() {
+        return Err(parse_error(
+            line_index,
+            &first,
+            &format!("Unsupported OFF variant prefix `{}`.", prefix),
+        ));
+    }
+    let rest = first[keyword_end + 3..].trim();
+    let (line_index, counts_line) = if rest.is_empty() {
+        lines.next().ok_or_else(unexpected_end)?
+    } else {
+        (line_index, rest.to_string())
+    };
+    let counts = parse_numbers::<usize>(&counts_line, line_index)?;
+    if counts.len() < 2 {
+        return Err(parse_error(
+            line_index,
+            &counts_line,
+            "Expected vertex and face counts.",
+        ));
+    }
+    let (vertex_count, face_count) = (counts[0], counts[1]);
+
+    let width = 3 + if has_normals { 3 } else { 0 };
+    let mut raw = Vec::with_capacity(super::reserve(vertex_count));
+    let mut float_colors = false;
+    for _ in 0..vertex_count {
+        let (line_index, line) = lines.next().ok_or_else(unexpected_end)?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let tex_width = if has_tex { 2 } else { 0 };
+        let color_count = if has_colors {
+            tokens.len().saturating_sub(width + tex_width)
+        } else {
+            0
+        };
+        if tokens.len() < width + color_count + tex_width
+            || (has_colors && !(3..=4).contains(&color_count))
+        {
+            return Err(parse_error(
+                line_index,
+                &line,
+                "Vertex line doesn't match the announced attributes.",
+            ));
+        }
+        if has_colors
+            && tokens[width..width + color_count]
+                .iter()
+                .any(|t| t.contains('.'))
+        {
+            float_colors = true;
+        }
+        let values = parse_numbers::<f32>(&line, line_index)?;
+        raw.push((values, color_count));
+    }
+
+    let mut vertices = PointCloud::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut tex_coords = Vec::new();
+    for (values, color_count) in raw {
+        vertices.positions.push([values[0], values[1], values[2]]);
+        if has_normals {
+            normals.push([values[3], values[4], values[5]]);
+        }
+        if has_colors {
+            let scale = if float_colors { 1.0 } else { 255.0 };
+            let mut c = [1.0; 4];
+            for (k, d) in c.iter_mut().enumerate().take(color_count) {
+                *d = values[width + k] / scale;
+            }
+            colors.push(c);
+        }
+        if has_tex {
+            let k = width + color_count;
+            tex_coords.push([values[k], values[k + 1]]);
+        }
+    }
+    vertices.normals = if has_normals { Some(normals) } else { None };
+    vertices.colors = if has_colors { Some(colors) } else { None };
+    vertices.tex_coords = if has_tex { Some(tex_coords) } else { None };
+
+    let mut faces = Vec::with_capacity(super::reserve(face_count));
+    for _ in 0..face_count {
+        let (line_index, line) = lines.next().ok_or_else(unexpected_end)?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let n: usize = tokens[0]
+            .parse()
+            .map_err(|_| parse_error(line_index, &line, "Expected the number of corners."))?;
+        if tokens.len() < n + 1 {
+            return Err(parse_error(
+                line_index,
+                &line,
+                &format!("Expected {} vertex indices.", n),
+            ));
+        }
+        let face = parse_numbers::<u32>(&tokens[1..=n].join(" "), line_index)?;
+        faces.push(face);
+    }
+    let mesh = Mesh { vertices, faces };
+    mesh.check().map_err(mesh_error)?;
+    Ok(mesh)
+}
+
+/// Reads an OFF file into a `Ply` with a `vertex` and a `face` element.
+///
+/// The header is the one of `Mesh::to_ply()` using ascii encoding.
+pub fn read_off<T: Read>(source: &mut T) -> io::Result<Ply<DefaultElement>> {
+    read_mesh(source)?
+        .to_ply(Encoding::Ascii)
+        .map_err(mesh_error)
+}
+
+fn parse_numbers<N: std::str::FromStr>(line: &str, line_index: usize) -> io::Result<Vec<N>> {
+    line.split_whitespace()
+        .map(|t| {
+            t.parse()
+                .map_err(|_| parse_error(line_index, line, &format!("Invalid number `{}`.", t)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn round_trip_ok() {
+        let path = "example_plys/house_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+        let mut out = Vec::new();
+        let n = write_off(&mut out, &ply).unwrap();
+        assert_eq!(n, out.len());
+        assert!(out.starts_with(b"NOFF\n5 3 0\n"));
+        let read = read_off(&mut out.as_slice()).unwrap();
+        assert_eq!(
+            Mesh::from_ply(&read).unwrap(),
+            Mesh::from_ply(&ply).unwrap()
+        );
+    }
+    #[test]
+    fn float_colors_ok() {
+        let source = "# colored\nCOFF\n3 1 0\n\
+            0 0 0 1.0 0 0\n1 0 0 0 0.5 0 0.5\n0 1 0 0 0 1\n\
+            3 0 1 2 255 0 0\n";
+        let mesh = read_mesh(&mut source.as_bytes()).unwrap();
+        let colors = mesh.vertices.colors.unwrap();
+        assert_eq!(colors[0], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(colors[1], [0.0, 0.5, 0.0, 0.5]);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2]]);
+    }
+    #[test]
+    fn counts_on_keyword_line_ok() {
+        let source = "OFF3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        let mesh = read_mesh(&mut source.as_bytes()).unwrap();
+        assert_eq!(mesh.vertices.len(), 3);
+    }
+    #[test]
+    fn invalid_err() {
+        for source in [
+            "OFF\n3 1 0\n0 0 0\n1 0 0\n",
+            "OFF\n3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n",
+            "4OFF\n1 0 0\n0 0 0 0\n",
+            "COFF\n1 0 0\n0 0\n",
+            "PLY\n",
+            "OFF\n18446744073709551615 1 0\n0 0 0\n",
+            "OFF\n1 18446744073709551615 0\n0 0 0\n",
+        ] {
+            assert!(read_off(&mut source.as_bytes()).is_err());
+        }
+    }
+}
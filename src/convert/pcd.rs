@@ -0,0 +1,679 @@
+//! Point Cloud Library (PCD) import and export.
+//!
+//! A PCD file holds a single element type, which maps onto the `vertex` element.
+//! `FIELDS`, `SIZE` and `TYPE` map onto `PropertyDef`s with the matching `ScalarType`,
+//! a field with `COUNT n > 1` becomes the properties `name_0` to `name_{n-1}`.
+//! Padding fields named `_` are dropped.
+//!
+//! Everything else of the PCD header is kept as `obj_info` lines, so it survives a round trip:
+//!
+//! - `pcd_version 0.7`
+//! - `pcd_width 640` and `pcd_height 480` for organized point clouds
+//! - `pcd_viewpoint tx ty tz qw qx qy qz`
+//!
+//! All three data layouts are supported: `ascii`, `binary` and `binary_compressed`.
+//! Packed colors like PCL's float `rgb` field are kept bit by bit.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::convert::pcd::{self, PcdData};
+//! let source = "\
+//!     VERSION 0.7\n\
+//!     FIELDS x y z normal\n\
+//!     SIZE 4 4 4 4\n\
+//!     TYPE F F F F\n\
+//!     COUNT 1 1 1 3\n\
+//!     WIDTH 2\n\
+//!     HEIGHT 1\n\
+//!     VIEWPOINT 0 0 0 1 0 0 0\n\
+//!     POINTS 2\n\
+//!     DATA ascii\n\
+//!     0 0 0 0 0 1\n\
+//!     1 0 nan 0 0 1\n";
+//! let ply = pcd::read_pcd(&mut source.as_bytes()).unwrap();
+//! assert_eq!(ply.header.elements[0].properties[3].name, "normal_0");
+//! assert!(ply.header.obj_infos.contains(&"pcd_width 2".to_string()));
+//!
+//! let mut out = Vec::new();
+//! pcd::write_pcd(&mut out, &ply, PcdData::BinaryCompressed).unwrap();
+//! let read = pcd::read_pcd(&mut out.as_slice()).unwrap();
+//! assert_eq!(read.payload["vertex"].len(), 2);
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::parser::Parser;
+use crate::ply::{
+    DefaultElement, ElementDef, Encoding, Header, Ply, PropertyAccess, PropertyDef, PropertyType,
+    ScalarType,
+};
+use crate::writer::Writer;
+
+/// Layout of the point data in a PCD file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcdData {
+    /// One line of text per point.
+    Ascii,
+    /// Little endian records, one per point.
+    Binary,
+    /// LZF compressed, one block of values per field.
+    BinaryCompressed,
+}
+
+/// One entry of `FIELDS`, `SIZE`, `TYPE` and `COUNT`.
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    name: String,
+    scalar_type: ScalarType,
+    count: usize,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn scalar_type(size: &str, kind: &str) -> io::Result<ScalarType> {
+    Ok(match (kind, size) {
+        ("I", "1") => ScalarType::Char,
+        ("U", "1") => ScalarType::UChar,
+        ("I", "2") => ScalarType::Short,
+        ("U", "2") => ScalarType::UShort,
+        ("I", "4") => ScalarType::Int,
+        ("U", "4") => ScalarType::UInt,
+        ("F", "4") => ScalarType::Float,
+        ("F", "8") => ScalarType::Double,
+        _ => {
+            return Err(invalid(&format!(
+                "PCD type `{}` of size {} has no PLY equivalent.",
+                kind, size
+            )))
+        }
+    })
+}
+
+fn type_letter(scalar_type: ScalarType) -> char {
+    match scalar_type {
+        ScalarType::Char | ScalarType::Short | ScalarType::Int => 'I',
+        ScalarType::UChar | ScalarType::UShort | ScalarType::UInt => 'U',
+        ScalarType::Float | ScalarType::Double => 'F',
+    }
+}
+
+/// Properties of a field, numbered if `count > 1`.
+fn field_properties(field: &Field) -> Vec<PropertyDef> {
+    let t = PropertyType::Scalar(field.scalar_type);
+    if field.count == 1 {
+        vec![PropertyDef::new(&field.name, t)]
+    } else {
+        (0..field.count)
+            .map(|i| PropertyDef::new(&format!("{}_{}", field.name, i), t))
+            .collect()
+    }
+}
+
+/// Groups numbered properties `name_0`, `name_1`, ... of the same type back into one field.
+fn fields(element_def: &ElementDef) -> io::Result<Vec<Field>> {
+    // every field together with the name of its first property
+    let mut fields: Vec<(Field, &str)> = Vec::new();
+    for p in &element_def.properties {
+        let scalar_type = match p.data_type {
+            PropertyType::Scalar(s) => s,
+            PropertyType::List(_, _) => {
+                return Err(invalid(&format!(
+                    "List property `{}` can't be stored in PCD.",
+                    p.name
+                )))
+            }
+        };
+        let mut name = p.name.clone();
+        if let Some((base, index)) = p.name.rsplit_once('_') {
+            if let Some((last, _)) = fields.last_mut() {
+                let continues = last.name == base
+                    && last.scalar_type == scalar_type
+                    && index.parse() == Ok(last.count);
+                if continues {
+                    last.count += 1;
+                    continue;
+                }
+            }
+            if index == "0" {
+                name = base.to_string();
+            }
+        }
+        let field = Field {
+            name,
+            scalar_type,
+            count: 1,
+        };
+        fields.push((field, &p.name));
+    }
+    // a lone `name_0` is not a numbered field
+    Ok(fields
+        .into_iter()
+        .map(|(mut f, first)| {
+            if f.count == 1 {
+                f.name = first.to_string();
+            }
+            f
+        })
+        .collect())
+}
+
+/// Reads a PCD file into a `Ply` with a single `vertex` element.
+///
+/// The encoding of the header is ascii for `DATA ascii` and binary little endian otherwise.
+pub fn read_pcd<T: Read>(source: &mut T) -> io::Result<Ply<DefaultElement>> {
+    let mut reader = BufReader::new(source);
+    let mut header = Header::new();
+    let mut names = Vec::new();
+    let mut sizes = Vec::new();
+    let mut types = Vec::new();
+    let mut counts = Vec::new();
+    let mut points = None;
+    let data = loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "PCD header ends without `DATA`.",
+            ));
+        }
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        let values: Vec<String> = tokens.map(|t| t.to_string()).collect();
+        match keyword {
+            "VERSION" => header
+                .obj_infos
+                .push(format!("pcd_version {}", values.join(" "))),
+            "FIELDS" => names = values,
+            "SIZE" => sizes = values,
+            "TYPE" => types = values,
+            "COUNT" => counts = values,
+            "WIDTH" => header
+                .obj_infos
+                .push(format!("pcd_width {}", values.join(" "))),
+            "HEIGHT" => header
+                .obj_infos
+                .push(format!("pcd_height {}", values.join(" "))),
+            "VIEWPOINT" => header
+                .obj_infos
+                .push(format!("pcd_viewpoint {}", values.join(" "))),
+            "POINTS" => {
+                points = Some(
+                    values
+                        .first()
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .ok_or_else(|| invalid("Invalid `POINTS`."))?,
+                )
+            }
+            "DATA" => break values.first().cloned().unwrap_or_default(),
+            k => return Err(invalid(&format!("Unknown PCD header entry `{}`.", k))),
+        }
+    };
+    if sizes.len() != names.len() || types.len() != names.len() {
+        return Err(invalid(
+            "`FIELDS`, `SIZE` and `TYPE` must have the same length.",
+        ));
+    }
+    let mut fields = Vec::with_capacity(names.len());
+    for (i, name) in names.iter().enumerate() {
+        let count = match counts.get(i) {
+            Some(c) => c.parse().map_err(|_| invalid("Invalid `COUNT`."))?,
+            None => 1,
+        };
+        fields.push(Field {
+            name: name.clone(),
+            scalar_type: scalar_type(&sizes[i], &types[i])?,
+            count,
+        });
+    }
+    // padding fields are read, but dropped afterwards
+    let mut element_def = ElementDef::new("vertex");
+    let mut padding = Vec::new();
+    for (i, f) in fields.iter().enumerate() {
+        if f.name == "_" {
+            let pad = Field {
+                name: format!("_{}", i),
+                ..f.clone()
+            };
+            let properties = field_properties(&pad);
+            padding.extend(properties.iter().map(|p| p.name.clone()));
+            element_def.properties.extend(properties);
+        } else {
+            element_def.properties.extend(field_properties(f));
+        }
+    }
+    let points = points.ok_or_else(|| invalid("Missing `POINTS`."))?;
+    element_def.count = points;
+
+    let p = Parser::<DefaultElement>::new();
+    let mut elements = Vec::with_capacity(super::reserve(points));
+    match data.as_ref() {
+        "ascii" => {
+            header.encoding = Encoding::Ascii;
+            let mut line = String::new();
+            while elements.len() < points {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        format!("Expected {} points, found {}.", points, elements.len()),
+                    ));
+                }
+                if !line.trim().is_empty() {
+                    elements.push(p.read_ascii_element(&line, &element_def)?);
+                }
+            }
+        }
+        "binary" => {
+            header.encoding = Encoding::BinaryLittleEndian;
+            for _ in 0..points {
+                elements.push(p.read_little_endian_element(&mut reader, &element_def)?);
+            }
+        }
+        "binary_compressed" => {
+            header.encoding = Encoding::BinaryLittleEndian;
+            let compressed_size = reader.read_u32::<LittleEndian>()? as usize;
+            let size = reader.read_u32::<LittleEndian>()? as usize;
+            let mut compressed = Vec::new();
+            (&mut reader)
+                .take(compressed_size as u64)
+                .read_to_end(&mut compressed)?;
+            if compressed.len() != compressed_size {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!(
+                        "Expected {} bytes of compressed data, found {}.",
+                        compressed_size,
+                        compressed.len()
+                    ),
+                ));
+            }
+            let columns = lzf_decompress(&compressed, size)?;
+            let records = transpose(&columns, &fields, points, false)?;
+            let mut records = records.as_slice();
+            for _ in 0..points {
+                elements.push(p.read_little_endian_element(&mut records, &element_def)?);
+            }
+        }
+        d => return Err(invalid(&format!("Unknown PCD data layout `{}`.", d))),
+    }
+
+    element_def
+        .properties
+        .retain(|p| !padding.contains(&p.name));
+    for e in elements.iter_mut() {
+        for n in &padding {
+            e.remove(n);
+        }
+    }
+    header.elements.push(element_def);
+    let mut ply = Ply::new();
+    ply.header = header;
+    ply.payload.insert("vertex".to_string(), elements);
+    Ok(ply)
+}
+
+/// Converts between one record per point and one block per field.
+fn transpose(
+    data: &[u8],
+    fields: &[Field],
+    points: usize,
+    to_columns: bool,
+) -> io::Result<Vec<u8>> {
+    let widths: Vec<usize> = fields
+        .iter()
+        .map(|f| f.scalar_type.byte_size() * f.count)
+        .collect();
+    let record: usize = widths.iter().sum();
+    let expected = record
+        .checked_mul(points)
+        .ok_or_else(|| invalid("Point data exceeds the address space."))?;
+    if data.len() != expected {
+        return Err(invalid(&format!(
+            "Expected {} bytes of point data, found {}.",
+            expected,
+            data.len()
+        )));
+    }
+    let mut out = vec![0; data.len()];
+    let mut column_start = 0;
+    let mut offset = 0;
+    for w in widths {
+        for i in 0..points {
+            let c = column_start + i * w;
+            let r = i * record + offset;
+            if to_columns {
+                out[c..c + w].copy_from_slice(&data[r..r + w]);
+            } else {
+                out[r..r + w].copy_from_slice(&data[c..c + w]);
+            }
+        }
+        column_start += w * points;
+        offset += w;
+    }
+    Ok(out)
+}
+
+fn obj_info<'a>(header: &'a Header, key: &str) -> Option<&'a str> {
+    header.obj_infos.iter().find_map(|o| {
+        o.strip_prefix(key)
+            .and_then(|v| v.strip_prefix(' '))
+            .map(|v| v.trim())
+    })
+}
+
+/// Writes the `vertex` element of `ply` as PCD, returns the number of bytes written.
+///
+/// Width, height and viewpoint are taken from the `obj_info` lines written by `read_pcd()`.
+/// Without them, or if the width and height don't match the number of points,
+/// the point cloud is written unorganized with an identity viewpoint.
+pub fn write_pcd<T: Write, E: PropertyAccess>(
+    out: &mut T,
+    ply: &Ply<E>,
+    data: PcdData,
+) -> io::Result<usize> {
+    let element_def = ply
+        .header
+        .elements
+        .iter()
+        .find(|e| e.name == "vertex")
+        .ok_or_else(|| invalid("No `vertex` element found."))?;
+    let elements = ply.payload.get("vertex").map(|v| &v[..]).unwrap_or(&[]);
+    let fields = fields(element_def)?;
+    let points = elements.len();
+
+    let mut width = points;
+    let mut height = 1;
+    let w = obj_info(&ply.header, "pcd_width").and_then(|v| v.parse::<usize>().ok());
+    let h = obj_info(&ply.header, "pcd_height").and_then(|v| v.parse::<usize>().ok());
+    if let (Some(w), Some(h)) = (w, h) {
+        if w * h == points {
+            width = w;
+            height = h;
+        }
+    }
+    let join = |f: &dyn Fn(&Field) -> String| -> String {
+        fields.iter().map(f).collect::<Vec<String>>().join(" ")
+    };
+    let mut s = String::from("# .PCD v0.7 - Point Cloud Data file format\n");
+    s.push_str(&format!(
+        "VERSION {}\n",
+        obj_info(&ply.header, "pcd_version").unwrap_or("0.7")
+    ));
+    s.push_str(&format!("FIELDS {}\n", join(&|f| f.name.clone())));
+    s.push_str(&format!(
+        "SIZE {}\n",
+        join(&|f| f.scalar_type.byte_size().to_string())
+    ));
+    s.push_str(&format!(
+        "TYPE {}\n",
+        join(&|f| type_letter(f.scalar_type).to_string())
+    ));
+    s.push_str(&format!("COUNT {}\n", join(&|f| f.count.to_string())));
+    s.push_str(&format!("WIDTH {}\nHEIGHT {}\n", width, height));
+    s.push_str(&format!(
+        "VIEWPOINT {}\n",
+        obj_info(&ply.header, "pcd_viewpoint").unwrap_or("0 0 0 1 0 0 0")
+    ));
+    s.push_str(&format!("POINTS {}\n", points));
+    s.push_str(match data {
+        PcdData::Ascii => "DATA ascii\n",
+        PcdData::Binary => "DATA binary\n",
+        PcdData::BinaryCompressed => "DATA binary_compressed\n",
+    });
+    out.write_all(s.as_bytes())?;
+    let mut written = s.len();
+
+    let w = Writer::<E>::new();
+    match data {
+        PcdData::Ascii => {
+            for e in elements {
+                written += w.write_ascii_element(out, e, element_def)?;
+            }
+        }
+        PcdData::Binary => {
+            for e in elements {
+                written += w.write_little_endian_element(out, e, element_def)?;
+            }
+        }
+        PcdData::BinaryCompressed => {
+            let mut records = Vec::new();
+            for e in elements {
+                w.write_little_endian_element(&mut records, e, element_def)?;
+            }
+            let columns = transpose(&records, &fields, points, true)?;
+            let compressed = lzf_compress(&columns);
+            out.write_u32::<LittleEndian>(compressed.len() as u32)?;
+            out.write_u32::<LittleEndian>(columns.len() as u32)?;
+            out.write_all(&compressed)?;
+            written += 8 + compressed.len();
+        }
+    }
+    Ok(written)
+}
+
+/// Largest distance a back reference can reach.
+const LZF_MAX_OFFSET: usize = 1 << 13;
+/// Longest match a back reference can copy.
+const LZF_MAX_MATCH: usize = 264;
+const LZF_MAX_LITERAL: usize = 32;
+
+/// Compresses `data` in the LZF format used by PCL.
+fn lzf_compress(data: &[u8]) -> Vec<u8> {
+    fn flush_literals(out: &mut Vec<u8>, literals: &[u8]) {
+        for chunk in literals.chunks(LZF_MAX_LITERAL) {
+            out.push((chunk.len() - 1) as u8);
+            out.extend_from_slice(chunk);
+        }
+    }
+    let mut out = Vec::with_capacity(data.len() / 2 + 16);
+    let mut table = vec![usize::MAX; 1 << 14];
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i + 2 < data.len() {
+        let h = ((data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize)
+            .wrapping_mul(2654435761)
+            >> 18
+            & ((1 << 14) - 1);
+        let candidate = table[h];
+        table[h] = i;
+        if candidate != usize::MAX
+            && i - candidate <= LZF_MAX_OFFSET
+            && data[candidate..candidate + 3] == data[i..i + 3]
+        {
+            let mut len = 3;
+            while i + len < data.len()
+                && len < LZF_MAX_MATCH
+                && data[candidate + len] == data[i + len]
+            {
+                len += 1;
+            }
+            flush_literals(&mut out, &data[literal_start..i]);
+            let offset = i - candidate - 1;
+            let l = len - 2;
+            if l < 7 {
+                out.push(((l << 5) | (offset >> 8)) as u8);
+            } else {
+                out.push(((7 << 5) | (offset >> 8)) as u8);
+                out.push((l - 7) as u8);
+            }
+            out.push((offset & 0xff) as u8);
+            i += len;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    flush_literals(&mut out, &data[literal_start..]);
+    out
+}
+
+/// Decompresses LZF `data`, which must expand to exactly `size` bytes.
+fn lzf_decompress(data: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    let corrupt = || invalid("Corrupt LZF data.");
+    let mut out = Vec::with_capacity(super::reserve(size));
+    let mut i = 0;
+    while i < data.len() {
+        let ctrl = data[i] as usize;
+        i += 1;
+        if ctrl < LZF_MAX_LITERAL {
+            let len = ctrl + 1;
+            let literal = data.get(i..i + len).ok_or_else(corrupt)?;
+            out.extend_from_slice(literal);
+            i += len;
+        } else {
+            let mut len = ctrl >> 5;
+            if len == 7 {
+                len += *data.get(i).ok_or_else(corrupt)? as usize;
+                i += 1;
+            }
+            let offset = ((ctrl & 0x1f) << 8) + *data.get(i).ok_or_else(corrupt)? as usize + 1;
+            i += 1;
+            if offset > out.len() {
+                return Err(corrupt());
+            }
+            let start = out.len() - offset;
+            // ranges may overlap, copy byte by byte
+            for k in 0..len + 2 {
+                out.push(out[start + k]);
+            }
+        }
+        if out.len() > size {
+            return Err(corrupt());
+        }
+    }
+    if out.len() != size {
+        return Err(corrupt());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::Property;
+
+    fn sample() -> Ply<DefaultElement> {
+        let source = "\
+            # .PCD v0.7\n\
+            VERSION 0.7\n\
+            FIELDS x y z rgb normal label\n\
+            SIZE 4 4 4 4 4 2\n\
+            TYPE F F F F F U\n\
+            COUNT 1 1 1 1 3 1\n\
+            WIDTH 2\n\
+            HEIGHT 2\n\
+            VIEWPOINT 1 2 3 1 0 0 0\n\
+            POINTS 4\n\
+            DATA ascii\n\
+            0 0 0 4.2108e+06 0 0 1 7\n\
+            1 0 0 4.2108e+06 0 0 1 7\n\
+            0 1 nan 4.2108e+06 0 1 0 8\n\
+            1 1 0 4.2108e+06 0 1 0 8\n";
+        read_pcd(&mut source.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn read_ascii_ok() {
+        let ply = sample();
+        let names: Vec<&str> = ply.header.elements[0]
+            .properties
+            .iter()
+            .map(|p| p.name.as_ref())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["x", "y", "z", "rgb", "normal_0", "normal_1", "normal_2", "label"]
+        );
+        assert_eq!(
+            ply.header.obj_infos,
+            vec![
+                "pcd_version 0.7",
+                "pcd_width 2",
+                "pcd_height 2",
+                "pcd_viewpoint 1 2 3 1 0 0 0"
+            ]
+        );
+        assert_eq!(ply.payload["vertex"][3]["label"], Property::UShort(8));
+    }
+    #[test]
+    fn round_trip_ok() {
+        let ply = sample();
+        for data in [PcdData::Ascii, PcdData::Binary, PcdData::BinaryCompressed] {
+            let mut out = Vec::new();
+            let n = write_pcd(&mut out, &ply, data).unwrap();
+            assert_eq!(n, out.len());
+            let text = String::from_utf8_lossy(&out);
+            assert!(text.contains("COUNT 1 1 1 1 3 1\nWIDTH 2\nHEIGHT 2\n"));
+            let read = read_pcd(&mut out.as_slice()).unwrap();
+            assert_eq!(read.header.elements, ply.header.elements);
+            assert_eq!(read.header.obj_infos, ply.header.obj_infos);
+            let (a, b) = (&read.payload["vertex"], &ply.payload["vertex"]);
+            for (x, y) in a.iter().zip(b.iter()) {
+                for (k, v) in y.iter() {
+                    match (v, &x[k]) {
+                        (Property::Float(p), Property::Float(q)) if p.is_nan() => {
+                            assert!(q.is_nan())
+                        }
+                        (p, q) => assert_eq!(p, q),
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn padding_dropped_ok() {
+        let source = "FIELDS x _ y\nSIZE 4 1 4\nTYPE F U F\nCOUNT 1 4 1\n\
+            WIDTH 1\nHEIGHT 1\nPOINTS 1\nDATA ascii\n1 0 0 0 0 2\n";
+        let ply = read_pcd(&mut source.as_bytes()).unwrap();
+        assert_eq!(ply.header.elements[0].properties.len(), 2);
+        assert_eq!(ply.payload["vertex"][0]["y"], Property::Float(2.0));
+
+        // only the exact name `_` marks padding
+        let source = "FIELDS x _id\nSIZE 4 4\nTYPE F U\nCOUNT 1 1\n\
+            WIDTH 1\nHEIGHT 1\nPOINTS 1\nDATA ascii\n1 5\n";
+        let ply = read_pcd(&mut source.as_bytes()).unwrap();
+        assert_eq!(ply.payload["vertex"][0]["_id"], Property::UInt(5));
+    }
+    #[test]
+    fn huge_sizes_err() {
+        let header = "FIELDS x\nSIZE 4\nTYPE F\nCOUNT 1\nWIDTH 1\nHEIGHT 1\n";
+        let source = format!("{}POINTS 4294967295\nDATA ascii\n1\n", header);
+        let e = read_pcd(&mut source.as_bytes()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+
+        let mut source = format!("{}POINTS 1\nDATA binary_compressed\n", header).into_bytes();
+        source.extend_from_slice(&u32::MAX.to_le_bytes());
+        source.extend_from_slice(&u32::MAX.to_le_bytes());
+        source.extend_from_slice(&[0, 1, 2]);
+        let e = read_pcd(&mut source.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+
+        let mut source =
+            format!("{}POINTS 4294967295\nDATA binary_compressed\n", header).into_bytes();
+        source.extend_from_slice(&2u32.to_le_bytes());
+        source.extend_from_slice(&u32::MAX.to_le_bytes());
+        source.extend_from_slice(&[0, 1]);
+        let e = read_pcd(&mut source.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+    #[test]
+    fn lzf_ok() {
+        let mut data: Vec<u8> = (0..2000).map(|i| (i % 7) as u8).collect();
+        data.extend((0..500u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8));
+        let compressed = lzf_compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(lzf_decompress(&compressed, data.len()).unwrap(), data);
+        assert!(lzf_decompress(&compressed, data.len() + 1).is_err());
+        assert!(lzf_decompress(&[0xe0, 0], 9).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+//! STL import and export, in ascii and binary form.
+//!
+//! STL stores independent triangles with a facet normal.
+//! When writing, polygons are triangulated with ear clipping and the facet normals are computed.
+//! When reading, corners closer than a given tolerance are welded into one vertex,
+//! so faces index shared vertices again. Facet normals are dropped,
+//! use `Ply::compute_face_normals()` to restore them.
+//!
+//! Binary STL is read and written with the binary element functions of `Parser` and `Writer`,
+//! using one little endian element per facet, see `facet_element_def()`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::convert::stl::{self, StlFormat};
+//! # use ply_rs::parser::Parser;
+//! # use ply_rs::ply::DefaultElement;
+//! let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+//! let mut f = std::fs::File::open(path).unwrap();
+//! let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+//!
+//! let mut out = Vec::new();
+//! stl::write_stl(&mut out, &ply, StlFormat::Binary).unwrap();
+//!
+//! let cube = stl::read_stl(&mut out.as_slice(), 1e-6).unwrap();
+//! assert_eq!(cube.payload["vertex"].len(), 8);
+//! assert_eq!(cube.payload["face"].len(), 12);
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::{mesh_error, parse_error};
+use crate::mesh::{polygon_normal, triangulate_polygon, Mesh, PointCloud, TriangulationMethod};
+use crate::parser::Parser;
+use crate::ply::{
+    DefaultElement, ElementDef, Encoding, Ply, Property, PropertyAccess, PropertyDef, PropertyType,
+    ScalarType,
+};
+use crate::writer::Writer;
+
+/// Encoding of an STL file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StlFormat {
+    /// Human readable `solid ... endsolid` text.
+    Ascii,
+    /// 80 byte header, facet count and 50 bytes per facet.
+    Binary,
+}
+
+const HEADER_SIZE: usize = 80;
+const FACET_SIZE: usize = 50;
+const FACET_PROPERTIES: [&str; 12] = [
+    "nx", "ny", "nz", "x0", "y0", "z0", "x1", "y1", "z1", "x2", "y2", "z2",
+];
+
+/// Layout of a binary STL facet: normal, three corners and a two byte attribute.
+pub fn facet_element_def() -> ElementDef {
+    let mut e = ElementDef::new("facet");
+    for n in FACET_PROPERTIES {
+        e.properties
+            .push(PropertyDef::new(n, PropertyType::Scalar(ScalarType::Float)));
+    }
+    e.properties.push(PropertyDef::new(
+        "attribute",
+        PropertyType::Scalar(ScalarType::UShort),
+    ));
+    e
+}
+
+/// Triangles of `mesh` with their unit normals.
+fn facets(mesh: &Mesh) -> Vec<[[f32; 3]; 4]> {
+    let positions = &mesh.vertices.positions;
+    let mut facets = Vec::with_capacity(mesh.faces.len());
+    for f in &mesh.faces {
+        for t in triangulate_polygon(f, positions, TriangulationMethod::EarClipping) {
+            let n = polygon_normal(&t, positions);
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            let n = if len > 0.0 {
+                [
+                    (n[0] / len) as f32,
+                    (n[1] / len) as f32,
+                    (n[2] / len) as f32,
+                ]
+            } else {
+                [0.0; 3]
+            };
+            facets.push([
+                n,
+                positions[t[0] as usize],
+                positions[t[1] as usize],
+                positions[t[2] as usize],
+            ]);
+        }
+    }
+    facets
+}
+
+/// Writes the faces of `mesh` as STL, returns the number of bytes written.
+///
+/// Polygons are triangulated, faces with less than three vertices are skipped.
+pub fn write_mesh<T: Write>(out: &mut T, mesh: &Mesh, format: StlFormat) -> io::Result<usize> {
+    mesh.check().map_err(mesh_error)?;
+    let facets = facets(mesh);
+    match format {
+        StlFormat::Ascii => write_ascii(out, &facets),
+        StlFormat::Binary => write_binary(out, &facets),
+    }
+}
+
+/// Writes the `vertex` and `face` elements of `ply` as STL, see `write_mesh()`.
+pub fn write_stl<T: Write, E: PropertyAccess>(
+    out: &mut T,
+    ply: &Ply<E>,
+    format: StlFormat,
+) -> io::Result<usize> {
+    let mesh = Mesh::from_ply(ply).map_err(mesh_error)?;
+    write_mesh(out, &mesh, format)
+}
+
+fn write_ascii<T: Write>(out: &mut T, facets: &[[[f32; 3]; 4]]) -> io::Result<usize> {
+    let mut s = String::from("solid ply\n");
+    for f in facets {
+        s.push_str(&format!(
+            "  facet normal {} {} {}\n    outer loop\n",
+            f[0][0], f[0][1], f[0][2]
+        ));
+        for v in &f[1..] {
+            s.push_str(&format!("      vertex {} {} {}\n", v[0], v[1], v[2]));
+        }
+        s.push_str("    endloop\n  endfacet\n");
+    }
+    s.push_str("endsolid ply\n");
+    out.write_all(s.as_bytes())?;
+    Ok(s.len())
+}
+
+fn write_binary<T: Write>(out: &mut T, facets: &[[[f32; 3]; 4]]) -> io::Result<usize> {
+    let mut header = [0u8; HEADER_SIZE];
+    let title = b"binary STL written by ply-rs";
+    header[..title.len()].copy_from_slice(title);
+    out.write_all(&header)?;
+    out.write_u32::<LittleEndian>(facets.len() as u32)?;
+    let mut written = HEADER_SIZE + 4;
+    let def = facet_element_def();
+    let w = Writer::<DefaultElement>::new();
+    let mut e = DefaultElement::new();
+    e.insert("attribute".to_string(), Property::UShort(0));
+    for f in facets {
+        for (k, n) in FACET_PROPERTIES.iter().enumerate() {
+            e.insert(n.to_string(), Property::Float(f[k / 3][k % 3]));
+        }
+        written += w.write_little_endian_element(out, &e, &def)?;
+    }
+    Ok(written)
+}
+
+/// Reads an ascii or binary STL file and welds corners closer than `tolerance`.
+///
+/// With a tolerance of zero, only corners with identical coordinates are welded.
+pub fn read_mesh<T: Read>(source: &mut T, tolerance: f32) -> io::Result<Mesh> {
+    let mut bytes = Vec::new();
+    source.read_to_end(&mut bytes)?;
+    let triangles = if is_binary(&bytes) {
+        read_binary(&bytes)?
+    } else {
+        read_ascii(&bytes)?
+    };
+    Ok(weld(&triangles, tolerance))
+}
+
+/// Reads an STL file into a `Ply` with a `vertex` and a `face` element, see `read_mesh()`.
+pub fn read_stl<T: Read>(source: &mut T, tolerance: f32) -> io::Result<Ply<DefaultElement>> {
+    read_mesh(source, tolerance)?
+        .to_ply(Encoding::Ascii)
+        .map_err(mesh_error)
+}
+
+/// Binary files may start with "solid" too, the size decides.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < HEADER_SIZE + 4 {
+        return false;
+    }
+    let count = (&bytes[HEADER_SIZE..]).read_u32::<LittleEndian>().unwrap() as usize;
+    let binary_size = HEADER_SIZE + 4 + count * FACET_SIZE;
+    binary_size == bytes.len() || !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+fn read_binary(bytes: &[u8]) -> io::Result<Vec<Vec<[f32; 3]>>> {
+    let mut reader = &bytes[HEADER_SIZE..];
+    let count = reader.read_u32::<LittleEndian>()? as usize;
+    if reader.len() < count * FACET_SIZE {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Binary STL declares {} facets, but contains only {} bytes of facet data.",
+                count,
+                reader.len()
+            ),
+        ));
+    }
+    let def = facet_element_def();
+    let p = Parser::<DefaultElement>::new();
+    let mut triangles = Vec::with_capacity(count);
+    for _ in 0..count {
+        let e = p.read_little_endian_element(&mut reader, &def)?;
+        let corner = |k: usize| {
+            let mut v = [0.0; 3];
+            for (i, c) in v.iter_mut().enumerate() {
+                *c = e.get_float(FACET_PROPERTIES[3 + 3 * k + i]).unwrap();
+            }
+            v
+        };
+        triangles.push(vec![corner(0), corner(1), corner(2)]);
+    }
+    Ok(triangles)
+}
+
+fn read_ascii(bytes: &[u8]) -> io::Result<Vec<Vec<[f32; 3]>>> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut triangles = Vec::new();
+    let mut corners: Option<Vec<[f32; 3]>> = None;
+    for (i, line) in text.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            Some("outer") => corners = Some(Vec::new()),
+            Some("vertex") => {
+                let c = corners
+                    .as_mut()
+                    .ok_or_else(|| parse_error(i + 1, line, "`vertex` outside of a loop."))?;
+                if tokens.len() != 4 {
+                    return Err(parse_error(i + 1, line, "Expected three coordinates."));
+                }
+                let mut v = [0.0; 3];
+                for (d, t) in v.iter_mut().zip(tokens[1..].iter()) {
+                    *d = t.parse().map_err(|_| {
+                        parse_error(i + 1, line, &format!("Invalid number `{}`.", t))
+                    })?;
+                }
+                c.push(v);
+            }
+            Some("endloop") => {
+                let c = corners
+                    .take()
+                    .ok_or_else(|| parse_error(i + 1, line, "`endloop` without `outer loop`."))?;
+                triangles.push(c);
+            }
+            _ => (),
+        }
+    }
+    Ok(triangles)
+}
+
+/// Merges corners closer than `tolerance` into shared vertices.
+///
+/// Positions are hashed into a grid with cells of size `tolerance`,
+/// so only the neighbouring cells need to be searched.
+fn weld(polygons: &[Vec<[f32; 3]>], tolerance: f32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let cell = |v: &[f32; 3]| -> [i64; 3] {
+        if tolerance > 0.0 {
+            [
+                (v[0] / tolerance).floor() as i64,
+                (v[1] / tolerance).floor() as i64,
+                (v[2] / tolerance).floor() as i64,
+            ]
+        } else {
+            [
+                v[0].to_bits() as i64,
+                v[1].to_bits() as i64,
+                v[2].to_bits() as i64,
+            ]
+        }
+    };
+    let close = |a: &[f32; 3], b: &[f32; 3]| {
+        let d: f32 = (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum();
+        d <= tolerance * tolerance
+    };
+    let mut faces = Vec::with_capacity(polygons.len());
+    for polygon in polygons {
+        let mut face = Vec::with_capacity(polygon.len());
+        for v in polygon {
+            let c = cell(v);
+            let mut found = None;
+            let range = if tolerance > 0.0 { -1..=1 } else { 0..=0 };
+            'search: for dx in range.clone() {
+                for dy in range.clone() {
+                    for dz in range.clone() {
+                        let key = [c[0] + dx, c[1] + dy, c[2] + dz];
+                        if let Some(candidates) = grid.get(&key) {
+                            if let Some(i) = candidates
+                                .iter()
+                                .find(|i| close(&positions[**i as usize], v))
+                            {
+                                found = Some(*i);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+            let index = found.unwrap_or_else(|| {
+                positions.push(*v);
+                let i = (positions.len() - 1) as u32;
+                grid.entry(c).or_default().push(i);
+                i
+            });
+            face.push(index);
+        }
+        faces.push(face);
+    }
+    let mut vertices = PointCloud::new();
+    vertices.positions = positions;
+    Mesh { vertices, faces }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube() -> Mesh {
+        let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+        Mesh::from_ply(&ply).unwrap()
+    }
+
+    #[test]
+    fn round_trip_ok() {
+        let cube = cube();
+        for format in [StlFormat::Ascii, StlFormat::Binary] {
+            let mut out = Vec::new();
+            let n = write_mesh(&mut out, &cube, format).unwrap();
+            assert_eq!(n, out.len());
+            let read = read_mesh(&mut out.as_slice(), 0.0).unwrap();
+            assert_eq!(read.vertices.len(), 8);
+            assert_eq!(read.faces.len(), 12);
+            assert!((read.surface_area().unwrap() - 6.0).abs() < 1e-6);
+            assert!((read.volume().unwrap() - cube.volume().unwrap()).abs() < 1e-6);
+        }
+    }
+    #[test]
+    fn binary_size_ok() {
+        let mut out = Vec::new();
+        write_mesh(&mut out, &cube(), StlFormat::Binary).unwrap();
+        assert_eq!(out.len(), 84 + 12 * 50);
+    }
+    #[test]
+    fn weld_tolerance_ok() {
+        let source = "solid t\n\
+            facet normal 0 0 1\nouter loop\n\
+            vertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\n\
+            endloop\nendfacet\n\
+            facet normal 0 0 1\nouter loop\n\
+            vertex 1.00001 0 0\nvertex 1 1 0\nvertex 0 1 0\n\
+            endloop\nendfacet\n\
+            endsolid t\n";
+        let exact = read_mesh(&mut source.as_bytes(), 0.0).unwrap();
+        assert_eq!(exact.vertices.len(), 5);
+        let welded = read_mesh(&mut source.as_bytes(), 1e-3).unwrap();
+        assert_eq!(welded.vertices.len(), 4);
+        assert_eq!(welded.faces, vec![vec![0, 1, 2], vec![1, 3, 2]]);
+    }
+    #[test]
+    fn truncated_binary_err() {
+        let mut out = Vec::new();
+        write_mesh(&mut out, &cube(), StlFormat::Binary).unwrap();
+        out.truncate(out.len() - 10);
+        assert!(read_mesh(&mut out.as_slice(), 0.0).is_err());
+    }
+}
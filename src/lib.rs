@@ -19,15 +19,20 @@
 //!
 //! - Read an entire PLY file with `read_ply(reader)`, see the Parser module.
 //! - Write an entire PLY with `ẁrite_ply(target, ply)`, se the Writer module.
-//! - Don't care about data types: `DefaultElement` is nothing more than a [linked HashMap](https://github.com/contain-rs/linked-hash-map) where you access elements with String keys.
+//! - Don't care about data types: `CompactElement` keeps the values of a row in a `Vec` and looks names up in a shared `ElementDef`, it is the recommended choice for dynamic files.
+//!   `DefaultElement` is nothing more than a [linked HashMap](https://github.com/contain-rs/linked-hash-map) where you access elements with String keys.
 //!
 //! Performance can be achieved by using the finer granular methods and your own structs:
 //!
 //! - `Writer` and `Parser` provide you with methods down to the line/element level for nice things like streaming architectures.
 //! - `Ply`, `Writer`, and `Parser` use generics for the element-type. If HashMaps are too slow for you, define your own structs and implement the `PropertyAccess` trait. Data will then be written directly to your target format.
 
+pub mod compression;
+pub mod convert;
+pub mod mesh;
 pub mod parser;
 pub mod ply;
+pub mod splat;
 pub mod writer;
 
 mod util;
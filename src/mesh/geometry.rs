@@ -0,0 +1,175 @@
+//! Normals, surface area, volume and bounding boxes.
+
+use super::triangulate::polygon_normal;
+use super::{set_float_columns, Mesh, MeshError, PointCloud};
+use crate::ply::{DefaultElement, Ply};
+
+fn normalized(n: [f64; 3]) -> [f32; 3] {
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len > 0.0 {
+        [
+            (n[0] / len) as f32,
+            (n[1] / len) as f32,
+            (n[2] / len) as f32,
+        ]
+    } else {
+        [0.0; 3]
+    }
+}
+
+impl PointCloud {
+    /// Axis aligned bounding box as `(min, max)`, `None` if there are no vertices.
+    pub fn bounding_box(&self) -> Option<([f32; 3], [f32; 3])> {
+        let first = *self.positions.first()?;
+        Some(
+            self.positions
+                .iter()
+                .fold((first, first), |(mut min, mut max), p| {
+                    for i in 0..3 {
+                        min[i] = min[i].min(p[i]);
+                        max[i] = max[i].max(p[i]);
+                    }
+                    (min, max)
+                }),
+        )
+    }
+}
+
+impl Mesh {
+    /// Axis aligned bounding box of all vertices, see `PointCloud::bounding_box()`.
+    pub fn bounding_box(&self) -> Option<([f32; 3], [f32; 3])> {
+        self.vertices.bounding_box()
+    }
+
+    /// Unit normal of every face, following the right hand rule.
+    ///
+    /// Polygons don't need to be planar, degenerate faces get a zero normal.
+    pub fn face_normals(&self) -> Result<Vec<[f32; 3]>, MeshError> {
+        self.check()?;
+        Ok(self
+            .faces
+            .iter()
+            .map(|f| normalized(polygon_normal(f, &self.vertices.positions)))
+            .collect())
+    }
+
+    /// Unit normal of every vertex, averaged over all adjacent faces and weighted by their area.
+    ///
+    /// Vertices not referenced by any face get a zero normal.
+    pub fn vertex_normals(&self) -> Result<Vec<[f32; 3]>, MeshError> {
+        self.check()?;
+        let mut sums = vec![[0.0f64; 3]; self.vertices.len()];
+        for f in &self.faces {
+            // length of the Newell normal is proportional to the area
+            let n = polygon_normal(f, &self.vertices.positions);
+            for v in f {
+                let s = &mut sums[*v as usize];
+                for i in 0..3 {
+                    s[i] += n[i];
+                }
+            }
+        }
+        Ok(sums.into_iter().map(normalized).collect())
+    }
+
+    /// Sum of the areas of all faces.
+    pub fn surface_area(&self) -> Result<f64, MeshError> {
+        self.check()?;
+        Ok(self
+            .faces
+            .iter()
+            .map(|f| {
+                let n = polygon_normal(f, &self.vertices.positions);
+                (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt() / 2.0
+            })
+            .sum())
+    }
+
+    /// Enclosed volume, computed with the divergence theorem.
+    ///
+    /// The result is only meaningful for closed meshes.
+    /// It is positive if the faces are oriented outwards and negative otherwise.
+    pub fn volume(&self) -> Result<f64, MeshError> {
+        self.check()?;
+        let p = |i: u32| {
+            let p = self.vertices.positions[i as usize];
+            [p[0] as f64, p[1] as f64, p[2] as f64]
+        };
+        let mut volume = 0.0;
+        for f in self.faces.iter().filter(|f| f.len() >= 3) {
+            let a = p(f[0]);
+            for k in 1..f.len() - 1 {
+                let (b, c) = (p(f[k]), p(f[k + 1]));
+                volume += a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                    + a[2] * (b[0] * c[1] - b[1] * c[0]);
+            }
+        }
+        Ok(volume / 6.0)
+    }
+}
+
+impl Ply<DefaultElement> {
+    /// Computes area weighted vertex normals and stores them in the `vertex` element as `nx ny nz`.
+    pub fn compute_vertex_normals(&mut self) -> Result<(), MeshError> {
+        let normals = Mesh::from_ply(self)?.vertex_normals()?;
+        let def = self
+            .header
+            .elements
+            .iter_mut()
+            .find(|e| e.name == "vertex")
+            .unwrap();
+        let elements = self.payload.entry("vertex".to_string()).or_default();
+        set_float_columns(def, elements, ["nx", "ny", "nz"], &normals);
+        Ok(())
+    }
+
+    /// Computes face normals and stores them in the `face` element as `nx ny nz`.
+    pub fn compute_face_normals(&mut self) -> Result<(), MeshError> {
+        let normals = Mesh::from_ply(self)?.face_normals()?;
+        let def = match self.header.elements.iter_mut().find(|e| e.name == "face") {
+            Some(def) => def,
+            None => return Err(MeshError::new("No `face` element found.")),
+        };
+        let elements = self.payload.entry("face".to_string()).or_default();
+        set_float_columns(def, elements, ["nx", "ny", "nz"], &normals);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::ply::Property;
+
+    fn cube() -> Ply<DefaultElement> {
+        let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        Parser::<DefaultElement>::new().read_ply(&mut f).unwrap()
+    }
+
+    #[test]
+    fn cube_measures_ok() {
+        let mesh = Mesh::from_ply(&cube()).unwrap();
+        assert_eq!(mesh.bounding_box(), Some(([0.0; 3], [1.0; 3])));
+        assert!((mesh.surface_area().unwrap() - 6.0).abs() < 1e-9);
+        assert!((mesh.volume().unwrap().abs() - 1.0).abs() < 1e-9);
+    }
+    #[test]
+    fn cube_normals_ok() {
+        let mut ply = cube();
+        ply.compute_face_normals().unwrap();
+        ply.compute_vertex_normals().unwrap();
+        assert!(ply.make_consistent().is_ok());
+        let mesh = Mesh::from_ply(&ply).unwrap();
+        let normals = mesh.vertices.normals.unwrap();
+        // every corner normal points along the diagonal
+        let d = 1.0 / 3.0f32.sqrt();
+        for n in normals {
+            for c in n.iter() {
+                assert!((c.abs() - d).abs() < 1e-6);
+            }
+        }
+        assert_eq!(ply.payload["face"][0]["nx"], Property::Float(-1.0));
+    }
+}
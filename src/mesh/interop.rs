@@ -0,0 +1,329 @@
+//! Adapters for math types, enabled by the `mint` feature.
+//!
+//! Everything is generic over types that convert from and into `mint` types,
+//! so it works with `glam`, `nalgebra` and any other crate supporting `mint`.
+//! The `glam` and `nalgebra` features enable these conversions in the respective crates
+//! and add the type aliases `GlamVertex` and `NalgebraVertex`.
+
+use super::{color_scale, columns, read_colors, set_float_columns, to_f32, MeshError};
+use crate::ply::{DefaultElement, ElementDef, Property, PropertyAccess};
+
+/// Vertex element with a position `x y z`, a normal `nx ny nz` and a color `red green blue alpha`.
+///
+/// Colors are normalized to `[0, 1]` when read, see `PointCloud::colors`,
+/// and scaled back to the range of the requested type when written.
+/// Properties not present in a file keep their initial value:
+/// zero for position and normal, opaque black for the color.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::parser::Parser;
+/// # use ply_rs::mesh::Vertex;
+/// type V = Vertex<mint::Point3<f32>, mint::Vector3<f32>, mint::Vector4<f32>>;
+///
+/// let path = "example_plys/house_ok_ascii.ply";
+/// let mut f = std::fs::File::open(path).unwrap();
+/// let ply = Parser::<V>::new().read_ply(&mut f).unwrap();
+/// let v = &ply.payload["vertex"][0];
+/// assert_eq!(v.normal.z, 1.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vertex<P, N, C> {
+    /// `x y z`
+    pub position: P,
+    /// `nx ny nz`
+    pub normal: N,
+    /// `red green blue alpha`, normalized to `[0, 1]`.
+    pub color: C,
+}
+
+/// `Vertex` using `glam` types.
+#[cfg(feature = "glam")]
+pub type GlamVertex = Vertex<glam::Vec3, glam::Vec3, glam::Vec4>;
+
+/// `Vertex` using `nalgebra` types.
+#[cfg(feature = "nalgebra")]
+pub type NalgebraVertex =
+    Vertex<nalgebra::Point3<f32>, nalgebra::Vector3<f32>, nalgebra::Vector4<f32>>;
+
+fn color_channel(name: &str) -> Option<usize> {
+    match name.strip_prefix("diffuse_").unwrap_or(name) {
+        "red" => Some(0),
+        "green" => Some(1),
+        "blue" => Some(2),
+        "alpha" => Some(3),
+        _ => None,
+    }
+}
+
+impl<P, N, C> Vertex<P, N, C>
+where
+    P: From<mint::Point3<f32>> + Into<mint::Point3<f32>> + Copy,
+    N: From<mint::Vector3<f32>> + Into<mint::Vector3<f32>> + Copy,
+    C: From<mint::Vector4<f32>> + Into<mint::Vector4<f32>> + Copy,
+{
+    /// Value of a known property as float, colors are normalized.
+    fn component(&self, property_name: &str) -> Option<f32> {
+        let p: mint::Point3<f32> = self.position.into();
+        let n: mint::Vector3<f32> = self.normal.into();
+        let c: [f32; 4] = self.color.into().into();
+        match property_name {
+            "x" => Some(p.x),
+            "y" => Some(p.y),
+            "z" => Some(p.z),
+            "nx" => Some(n.x),
+            "ny" => Some(n.y),
+            "nz" => Some(n.z),
+            _ => color_channel(property_name).map(|i| c[i]),
+        }
+    }
+}
+
+impl<P, N, C> PropertyAccess for Vertex<P, N, C>
+where
+    P: From<mint::Point3<f32>> + Into<mint::Point3<f32>> + Copy,
+    N: From<mint::Vector3<f32>> + Into<mint::Vector3<f32>> + Copy,
+    C: From<mint::Vector4<f32>> + Into<mint::Vector4<f32>> + Copy,
+{
+    fn new() -> Self {
+        Vertex {
+            position: P::from([0.0; 3].into()),
+            normal: N::from([0.0; 3].into()),
+            color: C::from([0.0, 0.0, 0.0, 1.0].into()),
+        }
+    }
+    fn set_property(&mut self, property_name: &str, property: Property) {
+        let v = match property.as_f64() {
+            Some(v) => v,
+            None => return,
+        };
+        let mut p: mint::Point3<f32> = self.position.into();
+        let mut n: mint::Vector3<f32> = self.normal.into();
+        match property_name {
+            "x" => p.x = v as f32,
+            "y" => p.y = v as f32,
+            "z" => p.z = v as f32,
+            "nx" => n.x = v as f32,
+            "ny" => n.y = v as f32,
+            "nz" => n.z = v as f32,
+            _ => {
+                if let Some(i) = color_channel(property_name) {
+                    let mut c: [f32; 4] = self.color.into().into();
+                    c[i] = (v / color_scale(&property.property_type())) as f32;
+                    self.color = C::from(c.into());
+                }
+                return;
+            }
+        }
+        self.position = P::from(p);
+        self.normal = N::from(n);
+    }
+    fn get_uchar(&self, property_name: &str) -> Option<u8> {
+        color_channel(property_name)?;
+        self.component(property_name)
+            .map(|c| (c.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8)
+    }
+    fn get_ushort(&self, property_name: &str) -> Option<u16> {
+        color_channel(property_name)?;
+        self.component(property_name)
+            .map(|c| (c.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16)
+    }
+    fn get_float(&self, property_name: &str) -> Option<f32> {
+        self.component(property_name)
+    }
+    fn get_double(&self, property_name: &str) -> Option<f64> {
+        self.component(property_name).map(|v| v as f64)
+    }
+}
+
+fn missing(element_def: &ElementDef, names: &[&str]) -> MeshError {
+    MeshError::new(&format!(
+        "Element `{}` has no properties `{}`.",
+        element_def.name,
+        names.join(" ")
+    ))
+}
+
+/// Reads three scalar properties of every element as points, e.g. `["x", "y", "z"]`.
+pub fn read_points<E: PropertyAccess, V: From<mint::Point3<f32>>>(
+    elements: &[E],
+    element_def: &ElementDef,
+    names: [&str; 3],
+) -> Result<Vec<V>, MeshError> {
+    let c = columns(elements, element_def, names)?.ok_or_else(|| missing(element_def, &names))?;
+    Ok(to_f32(c).into_iter().map(|p| V::from(p.into())).collect())
+}
+
+/// Reads three scalar properties of every element as vectors, e.g. `["nx", "ny", "nz"]`.
+pub fn read_vectors<E: PropertyAccess, V: From<mint::Vector3<f32>>>(
+    elements: &[E],
+    element_def: &ElementDef,
+    names: [&str; 3],
+) -> Result<Vec<V>, MeshError> {
+    let c = columns(elements, element_def, names)?.ok_or_else(|| missing(element_def, &names))?;
+    Ok(to_f32(c).into_iter().map(|v| V::from(v.into())).collect())
+}
+
+/// Reads the colors of every element as normalized `red green blue alpha` vectors.
+pub fn read_color_vectors<E: PropertyAccess, V: From<mint::Vector4<f32>>>(
+    elements: &[E],
+    element_def: &ElementDef,
+) -> Result<Vec<V>, MeshError> {
+    let c = read_colors(elements, element_def)?
+        .ok_or_else(|| missing(element_def, &["red", "green", "blue"]))?;
+    Ok(c.into_iter().map(|v| V::from(v.into())).collect())
+}
+
+fn check_len<T>(elements: &[DefaultElement], values: &[T]) -> Result<(), MeshError> {
+    if elements.len() != values.len() {
+        return Err(MeshError::new(&format!(
+            "Expected {} values, got {}.",
+            elements.len(),
+            values.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Stores `values` as float properties `names`, one value per element.
+///
+/// Existing properties with these names are replaced in `element_def` and in every element.
+pub fn write_points<V: Into<mint::Point3<f32>> + Copy>(
+    element_def: &mut ElementDef,
+    elements: &mut [DefaultElement],
+    names: [&str; 3],
+    values: &[V],
+) -> Result<(), MeshError> {
+    check_len(elements, values)?;
+    let values: Vec<[f32; 3]> = values.iter().map(|v| (*v).into().into()).collect();
+    set_float_columns(element_def, elements, names, &values);
+    Ok(())
+}
+
+/// Stores `values` as float properties `names`, see `write_points()`.
+pub fn write_vectors<V: Into<mint::Vector3<f32>> + Copy>(
+    element_def: &mut ElementDef,
+    elements: &mut [DefaultElement],
+    names: [&str; 3],
+    values: &[V],
+) -> Result<(), MeshError> {
+    check_len(elements, values)?;
+    let values: Vec<[f32; 3]> = values.iter().map(|v| (*v).into().into()).collect();
+    set_float_columns(element_def, elements, names, &values);
+    Ok(())
+}
+
+/// Stores normalized colors as float properties `red green blue alpha`, see `write_points()`.
+pub fn write_color_vectors<V: Into<mint::Vector4<f32>> + Copy>(
+    element_def: &mut ElementDef,
+    elements: &mut [DefaultElement],
+    values: &[V],
+) -> Result<(), MeshError> {
+    check_len(elements, values)?;
+    let values: Vec<[f32; 4]> = values.iter().map(|v| (*v).into().into()).collect();
+    set_float_columns(
+        element_def,
+        elements,
+        ["red", "green", "blue", "alpha"],
+        &values,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::ply::{Encoding, Ply, PropertyDef, PropertyType, ScalarType};
+    use crate::writer::Writer;
+
+    type MintVertex = Vertex<mint::Point3<f32>, mint::Vector3<f32>, mint::Vector4<f32>>;
+
+    fn house() -> Ply<DefaultElement> {
+        let path = "example_plys/house_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        Parser::<DefaultElement>::new().read_ply(&mut f).unwrap()
+    }
+
+    #[test]
+    fn vertex_round_trip_ok() {
+        let mut ply = Ply::<MintVertex>::new();
+        let mut def = ElementDef::new("vertex");
+        for (n, t) in [
+            ("x", ScalarType::Float),
+            ("y", ScalarType::Float),
+            ("z", ScalarType::Float),
+            ("red", ScalarType::UChar),
+            ("green", ScalarType::UChar),
+            ("blue", ScalarType::UChar),
+        ] {
+            def.properties
+                .push(PropertyDef::new(n, PropertyType::Scalar(t)));
+        }
+        ply.header.elements.push(def);
+        ply.header.encoding = Encoding::BinaryLittleEndian;
+        let mut v = MintVertex::new();
+        v.position = [1.0, 2.0, 3.0].into();
+        v.color = [1.0, 0.0, 0.2, 1.0].into();
+        ply.payload.insert("vertex".to_string(), vec![v]);
+
+        let mut buf = Vec::new();
+        Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+        let read = Parser::<MintVertex>::new()
+            .read_ply(&mut buf.as_slice())
+            .unwrap();
+        let r = read.payload["vertex"][0];
+        assert_eq!(r.position, v.position);
+        assert_eq!(r.color.x, 1.0);
+        assert_eq!(r.color.z, 51.0 / 255.0);
+    }
+    #[test]
+    fn columns_round_trip_ok() {
+        let mut ply = house();
+        let def = &ply.header.elements[0];
+        let elements = &ply.payload["vertex"];
+        let points: Vec<mint::Point3<f32>> = read_points(elements, def, ["x", "y", "z"]).unwrap();
+        assert_eq!(points.len(), 5);
+        assert!(read_color_vectors::<_, mint::Vector4<f32>>(elements, def).is_err());
+
+        let moved: Vec<mint::Point3<f32>> = points
+            .iter()
+            .map(|p| [p.x + 1.0, p.y, p.z].into())
+            .collect();
+        let def = &mut ply.header.elements[0];
+        let elements = ply.payload.get_mut("vertex").unwrap();
+        write_points(def, elements, ["x", "y", "z"], &moved).unwrap();
+        assert!(write_points(def, elements, ["x", "y", "z"], &moved[1..]).is_err());
+        let read: Vec<mint::Point3<f32>> = read_points(elements, def, ["x", "y", "z"]).unwrap();
+        assert_eq!(read, moved);
+        assert!(ply.make_consistent().is_ok());
+    }
+    #[cfg(feature = "glam")]
+    #[test]
+    fn glam_ok() {
+        let path = "example_plys/house_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = Parser::<GlamVertex>::new().read_ply(&mut f).unwrap();
+        assert_eq!(ply.payload["vertex"][0].normal, glam::Vec3::Z);
+        let h = house();
+        let normals: Vec<glam::Vec3> = read_vectors(
+            &h.payload["vertex"],
+            &h.header.elements[0],
+            ["nx", "ny", "nz"],
+        )
+        .unwrap();
+        assert_eq!(normals[0], glam::Vec3::Z);
+    }
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_ok() {
+        let h = house();
+        let points: Vec<nalgebra::Point3<f32>> =
+            read_points(&h.payload["vertex"], &h.header.elements[0], ["x", "y", "z"]).unwrap();
+        let path = "example_plys/house_ok_ascii.ply";
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = Parser::<NalgebraVertex>::new().read_ply(&mut f).unwrap();
+        assert_eq!(ply.payload["vertex"][4].position, points[4]);
+    }
+}
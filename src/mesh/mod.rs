@@ -0,0 +1,650 @@
+//! Typed views on the common `vertex` and `face` elements.
+//!
+//! PLY files don't prescribe property names or types,
+//! but most applications follow the same conventions:
+//!
+//! - `vertex` with `x y z`, optional normals `nx ny nz`, colors `red green blue [alpha]`
+//!   and texture coordinates `s t`, `u v` or `texture_u texture_v`.
+//! - `face` with a list property `vertex_indices` or `vertex_index`.
+//!
+//! `PointCloud` and `Mesh` collect these properties into plain arrays,
+//! regardless of the stored types, and write them back to a `Ply`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::parser::Parser;
+//! # use ply_rs::ply::DefaultElement;
+//! # use ply_rs::mesh::Mesh;
+//! let path = "example_plys/house_ok_ascii.ply";
+//! let mut f = std::fs::File::open(path).unwrap();
+//! let ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+//!
+//! let mesh = Mesh::from_ply(&ply).unwrap();
+//! assert_eq!(mesh.vertices.positions.len(), 5);
+//! assert_eq!(mesh.faces[0], vec![0, 1, 2]);
+//! assert!(mesh.vertices.normals.is_some());
+//! ```
+
+use std::error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::ply::{
+    DefaultElement, ElementDef, Encoding, Ply, Property, PropertyAccess, PropertyDef, PropertyType,
+    ScalarType,
+};
+
+mod geometry;
+
+mod triangulate;
+pub use self::triangulate::*;
+
+#[cfg(feature = "mint")]
+mod interop;
+#[cfg(feature = "mint")]
+pub use self::interop::*;
+
+/// Contains a description, why a `Ply` couldn't be interpreted as a mesh.
+#[derive(Debug)]
+pub struct MeshError {
+    /// Describes in natural language, why the conversion failed.
+    description: String,
+}
+impl MeshError {
+    /// Create a new error object with a given description of the problem.
+    pub fn new(description: &str) -> Self {
+        MeshError {
+            description: description.to_string(),
+        }
+    }
+}
+
+impl Display for MeshError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str(&format!("MeshError: {}", self.description))
+    }
+}
+
+impl error::Error for MeshError {
+    fn description(&self) -> &str {
+        &self.description
+    }
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
+}
+
+/// Property names used for texture coordinates, in order of preference.
+pub const TEX_COORD_NAMES: [[&str; 2]; 3] = [["s", "t"], ["u", "v"], ["texture_u", "texture_v"]];
+
+/// Property names used for face indices, in order of preference.
+pub const FACE_INDEX_NAMES: [&str; 2] = ["vertex_indices", "vertex_index"];
+
+/// Vertex attributes of a `vertex` element.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PointCloud {
+    /// `x y z`
+    pub positions: Vec<[f32; 3]>,
+    /// `nx ny nz`
+    pub normals: Option<Vec<[f32; 3]>>,
+    /// `red green blue alpha` normalized to `[0, 1]`.
+    ///
+    /// Integer colors are divided by the maximum of their type, float colors are taken as they are.
+    /// A missing `alpha` property is reported as `1.0`.
+    pub colors: Option<Vec<[f32; 4]>>,
+    /// Texture coordinates, see `TEX_COORD_NAMES`.
+    pub tex_coords: Option<Vec<[f32; 2]>>,
+}
+
+/// A polygon mesh: vertex attributes and faces indexing into them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    /// Vertex attributes.
+    pub vertices: PointCloud,
+    /// Polygons as lists of vertex indices.
+    pub faces: Vec<Vec<u32>>,
+}
+
+fn find_property<'a>(element_def: &'a ElementDef, names: &[&str]) -> Option<&'a PropertyDef> {
+    names
+        .iter()
+        .filter_map(|n| element_def.properties.iter().find(|p| p.name == *n))
+        .next()
+}
+
+fn find_element<'a>(element_defs: &'a [ElementDef], name: &str) -> Option<&'a ElementDef> {
+    element_defs.iter().find(|e| e.name == name)
+}
+
+/// Position of `def` in `element_def`, for `Property::read_bound()`.
+fn property_index(element_def: &ElementDef, def: &PropertyDef) -> usize {
+    element_def
+        .properties
+        .iter()
+        .position(|p| p.name == def.name)
+        .unwrap_or(element_def.properties.len())
+}
+
+/// Values of `def`, a property of `element_def`.
+fn scalar_column<E: PropertyAccess>(
+    elements: &[E],
+    element_def: &ElementDef,
+    def: &PropertyDef,
+) -> Result<Vec<f64>, MeshError> {
+    if let PropertyType::List(_, _) = def.data_type {
+        return Err(MeshError::new(&format!(
+            "Expected scalar property `{}`, found a list.",
+            def.name
+        )));
+    }
+    let index = property_index(element_def, def);
+    let schema = E::bind_schema(element_def);
+    elements
+        .iter()
+        .map(|e| {
+            Property::read_bound(e, element_def, schema.as_ref(), index)
+                .and_then(|p| p.as_f64())
+                .ok_or_else(|| {
+                    MeshError::new(&format!("Missing value for property `{}`.", def.name))
+                })
+        })
+        .collect()
+}
+
+fn columns<E: PropertyAccess, const N: usize>(
+    elements: &[E],
+    element_def: &ElementDef,
+    names: [&str; N],
+) -> Result<Option<Vec<[f64; N]>>, MeshError> {
+    let mut cols = Vec::with_capacity(N);
+    for n in names.iter() {
+        match find_property(element_def, &[n]) {
+            Some(def) => cols.push(scalar_column(elements, element_def, def)?),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(
+        (0..elements.len())
+            .map(|i| {
+                let mut v = [0.0; N];
+                for (k, c) in cols.iter().enumerate() {
+                    v[k] = c[i];
+                }
+                v
+            })
+            .collect(),
+    ))
+}
+
+fn to_f32<const N: usize>(v: Vec<[f64; N]>) -> Vec<[f32; N]> {
+    v.into_iter()
+        .map(|a| {
+            let mut r = [0.0; N];
+            for (d, s) in r.iter_mut().zip(a.iter()) {
+                *d = *s as f32;
+            }
+            r
+        })
+        .collect()
+}
+
+/// Factor to map a color channel of the given type onto `[0, 1]`.
+fn color_scale(data_type: &PropertyType) -> f64 {
+    match *data_type {
+        PropertyType::Scalar(ScalarType::Char) => i8::MAX as f64,
+        PropertyType::Scalar(ScalarType::UChar) => u8::MAX as f64,
+        PropertyType::Scalar(ScalarType::Short) => i16::MAX as f64,
+        PropertyType::Scalar(ScalarType::UShort) => u16::MAX as f64,
+        PropertyType::Scalar(ScalarType::Int) => i32::MAX as f64,
+        PropertyType::Scalar(ScalarType::UInt) => u32::MAX as f64,
+        _ => 1.0,
+    }
+}
+
+fn read_colors<E: PropertyAccess>(
+    elements: &[E],
+    element_def: &ElementDef,
+) -> Result<Option<Vec<[f32; 4]>>, MeshError> {
+    let names = ["red", "green", "blue", "alpha"];
+    let mut channels = Vec::with_capacity(4);
+    for (i, n) in names.iter().enumerate() {
+        let def = match find_property(element_def, &[n, &format!("diffuse_{}", n)]) {
+            Some(def) => def,
+            None if i == 3 => break,
+            None => return Ok(None),
+        };
+        let scale = color_scale(&def.data_type);
+        channels.push(
+            scalar_column(elements, element_def, def)?
+                .into_iter()
+                .map(|v| (v / scale) as f32)
+                .collect::<Vec<f32>>(),
+        );
+    }
+    Ok(Some(
+        (0..elements.len())
+            .map(|i| {
+                let alpha = channels.get(3).map(|a| a[i]).unwrap_or(1.0);
+                [channels[0][i], channels[1][i], channels[2][i], alpha]
+            })
+            .collect(),
+    ))
+}
+
+/// Stores `values` as float properties `names`, existing properties with these names are replaced.
+fn set_float_columns<const N: usize>(
+    element_def: &mut ElementDef,
+    elements: &mut [DefaultElement],
+    names: [&str; N],
+    values: &[[f32; N]],
+) {
+    for n in names {
+        let def = PropertyDef::new(n, PropertyType::Scalar(ScalarType::Float));
+        match element_def.properties.iter_mut().find(|p| p.name == n) {
+            Some(p) => *p = def,
+            None => element_def.properties.push(def),
+        }
+    }
+    for (e, v) in elements.iter_mut().zip(values.iter()) {
+        for (n, c) in names.iter().zip(v.iter()) {
+            e.insert(n.to_string(), Property::Float(*c));
+        }
+    }
+}
+
+impl PointCloud {
+    /// Creates an empty point cloud.
+    pub fn new() -> Self {
+        PointCloud::default()
+    }
+
+    /// Extracts the vertex attributes from the elements of `element_def`.
+    ///
+    /// Positions are required, all other attributes are optional.
+    pub fn from_elements<E: PropertyAccess>(
+        elements: &[E],
+        element_def: &ElementDef,
+    ) -> Result<Self, MeshError> {
+        let positions = match columns(elements, element_def, ["x", "y", "z"])? {
+            Some(p) => to_f32(p),
+            None => {
+                return Err(MeshError::new(&format!(
+                    "Element `{}` has no `x`, `y` and `z` properties.",
+                    element_def.name
+                )))
+            }
+        };
+        let normals = columns(elements, element_def, ["nx", "ny", "nz"])?.map(to_f32);
+        let colors = read_colors(elements, element_def)?;
+        let mut tex_coords = None;
+        for names in TEX_COORD_NAMES.iter() {
+            if let Some(t) = columns(elements, element_def, *names)? {
+                tex_coords = Some(to_f32(t));
+                break;
+            }
+        }
+        Ok(PointCloud {
+            positions,
+            normals,
+            colors,
+            tex_coords,
+        })
+    }
+
+    /// Extracts the vertex attributes from the `vertex` element of `ply`.
+    pub fn from_ply<E: PropertyAccess>(ply: &Ply<E>) -> Result<Self, MeshError> {
+        let element_def = find_element(&ply.header.elements, "vertex")
+            .ok_or_else(|| MeshError::new("No `vertex` element found."))?;
+        let elements = ply.payload.get("vertex").map(|v| &v[..]).unwrap_or(&[]);
+        PointCloud::from_elements(elements, element_def)
+    }
+
+    /// Number of vertices.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns `true` if there are no vertices.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Checks that all present attributes have one entry per position.
+    pub fn check(&self) -> Result<(), MeshError> {
+        let n = self.positions.len();
+        let lens = [
+            ("normals", self.normals.as_ref().map(|v| v.len())),
+            ("colors", self.colors.as_ref().map(|v| v.len())),
+            ("tex_coords", self.tex_coords.as_ref().map(|v| v.len())),
+        ];
+        for (name, len) in lens.iter() {
+            if let Some(len) = *len {
+                if len != n {
+                    return Err(MeshError::new(&format!(
+                        "Found {} {} for {} positions.",
+                        len, name, n
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the definition of the `vertex` element as written by `to_elements()`.
+    ///
+    /// Positions, normals and texture coordinates (`s t`) are written as `float`, colors as `uchar`.
+    /// `alpha` is only written if some vertex isn't fully opaque.
+    pub fn element_def(&self) -> ElementDef {
+        let mut e = ElementDef::new("vertex");
+        e.count = self.positions.len();
+        let float = PropertyType::Scalar(ScalarType::Float);
+        let uchar = PropertyType::Scalar(ScalarType::UChar);
+        for n in ["x", "y", "z"] {
+            e.properties.push(PropertyDef::new(n, float));
+        }
+        if self.normals.is_some() {
+            for n in ["nx", "ny", "nz"] {
+                e.properties.push(PropertyDef::new(n, float));
+            }
+        }
+        if let Some(ref colors) = self.colors {
+            for n in ["red", "green", "blue"] {
+                e.properties.push(PropertyDef::new(n, uchar));
+            }
+            if colors.iter().any(|c| c[3] != 1.0) {
+                e.properties.push(PropertyDef::new("alpha", uchar));
+            }
+        }
+        if self.tex_coords.is_some() {
+            for n in TEX_COORD_NAMES[0].iter() {
+                e.properties.push(PropertyDef::new(n, float));
+            }
+        }
+        e
+    }
+
+    /// Converts the vertex attributes into elements matching `element_def()`.
+    pub fn to_elements(&self) -> Vec<DefaultElement> {
+        let write_alpha = self
+            .colors
+            .as_ref()
+            .map(|c| c.iter().any(|c| c[3] != 1.0))
+            .unwrap_or(false);
+        let to_uchar = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        (0..self.positions.len())
+            .map(|i| {
+                let mut e = DefaultElement::new();
+                let p = self.positions[i];
+                e.insert("x".to_string(), Property::Float(p[0]));
+                e.insert("y".to_string(), Property::Float(p[1]));
+                e.insert("z".to_string(), Property::Float(p[2]));
+                if let Some(ref normals) = self.normals {
+                    let n = normals[i];
+                    e.insert("nx".to_string(), Property::Float(n[0]));
+                    e.insert("ny".to_string(), Property::Float(n[1]));
+                    e.insert("nz".to_string(), Property::Float(n[2]));
+                }
+                if let Some(ref colors) = self.colors {
+                    let c = colors[i];
+                    e.insert("red".to_string(), Property::UChar(to_uchar(c[0])));
+                    e.insert("green".to_string(), Property::UChar(to_uchar(c[1])));
+                    e.insert("blue".to_string(), Property::UChar(to_uchar(c[2])));
+                    if write_alpha {
+                        e.insert("alpha".to_string(), Property::UChar(to_uchar(c[3])));
+                    }
+                }
+                if let Some(ref tex_coords) = self.tex_coords {
+                    let t = tex_coords[i];
+                    e.insert("s".to_string(), Property::Float(t[0]));
+                    e.insert("t".to_string(), Property::Float(t[1]));
+                }
+                e
+            })
+            .collect()
+    }
+
+    /// Creates a `Ply` with a single `vertex` element using the given encoding.
+    pub fn to_ply(&self, encoding: Encoding) -> Result<Ply<DefaultElement>, MeshError> {
+        self.check()?;
+        let mut ply = Ply::<DefaultElement>::new();
+        ply.header.encoding = encoding;
+        ply.header.elements.push(self.element_def());
+        ply.payload.insert("vertex".to_string(), self.to_elements());
+        Ok(ply)
+    }
+}
+
+impl Mesh {
+    /// Creates an empty mesh.
+    pub fn new() -> Self {
+        Mesh::default()
+    }
+
+    /// Extracts the vertex attributes and faces of `ply`.
+    ///
+    /// The `face` element is optional, face indices may be stored in any integer list type.
+    pub fn from_ply<E: PropertyAccess>(ply: &Ply<E>) -> Result<Self, MeshError> {
+        let vertices = PointCloud::from_ply(ply)?;
+        let faces = match find_element(&ply.header.elements, "face") {
+            Some(element_def) => {
+                let elements = ply.payload.get("face").map(|v| &v[..]).unwrap_or(&[]);
+                read_faces(elements, element_def)?
+            }
+            None => Vec::new(),
+        };
+        Ok(Mesh { vertices, faces })
+    }
+
+    /// Checks that vertex attributes are complete and all face indices point to a vertex.
+    pub fn check(&self) -> Result<(), MeshError> {
+        self.vertices.check()?;
+        let n = self.vertices.len();
+        for (i, f) in self.faces.iter().enumerate() {
+            if let Some(v) = f.iter().find(|v| **v as usize >= n) {
+                return Err(MeshError::new(&format!(
+                    "Face {} references vertex {}, but there are only {} vertices.",
+                    i, v, n
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the definition of the `face` element as written by `to_ply()`.
+    ///
+    /// Indices are stored as `vertex_indices` with `uint` entries.
+    /// The index type is the smallest unsigned type fitting the longest face.
+    pub fn face_element_def(&self) -> ElementDef {
+        let max_len = self.faces.iter().map(|f| f.len()).max().unwrap_or(0);
+        let index_type = if max_len <= u8::MAX as usize {
+            ScalarType::UChar
+        } else if max_len <= u16::MAX as usize {
+            ScalarType::UShort
+        } else {
+            ScalarType::UInt
+        };
+        let mut e = ElementDef::new("face");
+        e.count = self.faces.len();
+        e.properties.push(PropertyDef::new(
+            FACE_INDEX_NAMES[0],
+            PropertyType::List(index_type, ScalarType::UInt),
+        ));
+        e
+    }
+
+    /// Creates a `Ply` with a `vertex` and a `face` element using the given encoding.
+    pub fn to_ply(&self, encoding: Encoding) -> Result<Ply<DefaultElement>, MeshError> {
+        self.check()?;
+        let mut ply = self.vertices.to_ply(encoding)?;
+        ply.header.elements.push(self.face_element_def());
+        let faces = self
+            .faces
+            .iter()
+            .map(|f| {
+                let mut e = DefaultElement::new();
+                e.insert(
+                    FACE_INDEX_NAMES[0].to_string(),
+                    Property::ListUInt(f.clone()),
+                );
+                e
+            })
+            .collect();
+        ply.payload.insert("face".to_string(), faces);
+        Ok(ply)
+    }
+}
+
+fn read_faces<E: PropertyAccess>(
+    elements: &[E],
+    element_def: &ElementDef,
+) -> Result<Vec<Vec<u32>>, MeshError> {
+    let def = find_property(element_def, &FACE_INDEX_NAMES).ok_or_else(|| {
+        MeshError::new("Element `face` has no `vertex_indices` or `vertex_index` property.")
+    })?;
+    let content_type = match def.data_type {
+        PropertyType::List(_, t) => t,
+        PropertyType::Scalar(_) => {
+            return Err(MeshError::new(&format!(
+                "Expected list property `{}`, found a scalar.",
+                def.name
+            )))
+        }
+    };
+    if content_type == ScalarType::Float || content_type == ScalarType::Double {
+        return Err(MeshError::new(&format!(
+            "Face indices `{}` must be integers.",
+            def.name
+        )));
+    }
+    let index = property_index(element_def, def);
+    let schema = E::bind_schema(element_def);
+    elements
+        .iter()
+        .map(|e| {
+            let list = Property::read_bound(e, element_def, schema.as_ref(), index)
+                .and_then(|p| p.list_as_f64())
+                .ok_or_else(|| {
+                    MeshError::new(&format!("Missing value for property `{}`.", def.name))
+                })?;
+            list.into_iter()
+                .map(|v| {
+                    if v < 0.0 {
+                        Err(MeshError::new(&format!("Negative face index {}.", v)))
+                    } else {
+                        Ok(v as u32)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colored_ply(color_type: ScalarType) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut e = ElementDef::new("vertex");
+        for n in ["x", "y", "z"] {
+            e.properties.push(PropertyDef::new(
+                n,
+                PropertyType::Scalar(ScalarType::Double),
+            ));
+        }
+        for n in ["red", "green", "blue"] {
+            e.properties
+                .push(PropertyDef::new(n, PropertyType::Scalar(color_type)));
+        }
+        for n in ["u", "v"] {
+            e.properties
+                .push(PropertyDef::new(n, PropertyType::Scalar(ScalarType::Float)));
+        }
+        ply.header.elements.push(e);
+        let mut v = DefaultElement::new();
+        v.insert("x".to_string(), Property::Double(1.0));
+        v.insert("y".to_string(), Property::Double(2.0));
+        v.insert("z".to_string(), Property::Double(3.0));
+        let (r, g, b) = match color_type {
+            ScalarType::UChar => (
+                Property::UChar(255),
+                Property::UChar(0),
+                Property::UChar(51),
+            ),
+            _ => (
+                Property::Float(1.0),
+                Property::Float(0.0),
+                Property::Float(0.2),
+            ),
+        };
+        v.insert("red".to_string(), r);
+        v.insert("green".to_string(), g);
+        v.insert("blue".to_string(), b);
+        v.insert("u".to_string(), Property::Float(0.25));
+        v.insert("v".to_string(), Property::Float(0.75));
+        ply.payload.insert("vertex".to_string(), vec![v]);
+        ply
+    }
+
+    #[test]
+    fn colors_normalized_ok() {
+        for t in [ScalarType::UChar, ScalarType::Float] {
+            let cloud = PointCloud::from_ply(&colored_ply(t)).unwrap();
+            assert_eq!(cloud.positions, vec![[1.0, 2.0, 3.0]]);
+            assert_eq!(cloud.colors, Some(vec![[1.0, 0.0, 0.2, 1.0]]));
+            assert_eq!(cloud.tex_coords, Some(vec![[0.25, 0.75]]));
+            assert_eq!(cloud.normals, None);
+        }
+    }
+    #[test]
+    fn round_trip_ok() {
+        use crate::parser::Parser;
+        use crate::writer::Writer;
+        let mesh = Mesh {
+            vertices: PointCloud {
+                positions: vec![
+                    [0.0, 0.0, 0.0],
+                    [1.0, 0.0, 0.0],
+                    [1.0, 1.0, 0.5],
+                    [0.0, 1.0, 0.0],
+                ],
+                normals: Some(vec![[0.0, 0.0, 1.0]; 4]),
+                colors: Some(vec![[1.0, 0.0, 0.0, 1.0]; 4]),
+                tex_coords: Some(vec![[0.0, 1.0]; 4]),
+            },
+            faces: vec![vec![0, 1, 2, 3], vec![0, 2, 3]],
+        };
+        for encoding in [
+            Encoding::Ascii,
+            Encoding::BinaryBigEndian,
+            Encoding::BinaryLittleEndian,
+        ] {
+            let mut ply = mesh.to_ply(encoding).unwrap();
+            let mut buf = Vec::<u8>::new();
+            Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+            let read = Parser::<DefaultElement>::new()
+                .read_ply(&mut buf.as_slice())
+                .unwrap();
+            assert_eq!(read.header.encoding, encoding);
+            assert_eq!(Mesh::from_ply(&read).unwrap(), mesh);
+        }
+    }
+    #[test]
+    fn missing_positions_err() {
+        let mut ply = Ply::<DefaultElement>::new();
+        ply.header.elements.push(ElementDef::new("vertex"));
+        assert!(PointCloud::from_ply(&ply).is_err());
+    }
+    #[test]
+    fn face_index_out_of_range_err() {
+        let mesh = Mesh {
+            vertices: PointCloud {
+                positions: vec![[0.0; 3]; 2],
+                ..PointCloud::default()
+            },
+            faces: vec![vec![0, 1, 2]],
+        };
+        assert!(mesh.to_ply(Encoding::Ascii).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! Splits polygon faces into triangles.
+
+use super::{find_property, Mesh, MeshError, FACE_INDEX_NAMES};
+use crate::ply::{DefaultElement, Ply, Property, PropertyType, ScalarType};
+
+/// Algorithm used to split a polygon into triangles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriangulationMethod {
+    /// Connects the first vertex with all other edges.
+    ///
+    /// Fast and exact for convex polygons, but produces overlapping triangles for concave ones.
+    Fan,
+    /// Repeatedly cuts off convex corners that don't contain any other vertex.
+    ///
+    /// Handles concave polygons. The polygon is projected onto the plane of its normal,
+    /// if no ear can be found (e.g. for self intersecting polygons), it falls back to `Fan`.
+    EarClipping,
+}
+
+/// Defines what happens to the other properties of a face (e.g. colors) when it is split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FacePropertyPolicy {
+    /// Every triangle gets a copy of all properties of the face it was cut from.
+    Duplicate,
+    /// Only the vertex indices are kept, all other face properties are removed from payload and header.
+    Drop,
+}
+
+/// Newell's method: robust normal of a (possibly non-planar) polygon, its length is twice the area.
+pub(crate) fn polygon_normal(face: &[u32], positions: &[[f32; 3]]) -> [f64; 3] {
+    let mut n = [0.0f64; 3];
+    for (i, a) in face.iter().enumerate() {
+        let b = face[(i + 1) % face.len()];
+        let a = positions[*a as usize];
+        let b = positions[b as usize];
+        let (a, b) = (
+            [a[0] as f64, a[1] as f64, a[2] as f64],
+            [b[0] as f64, b[1] as f64, b[2] as f64],
+        );
+        n[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        n[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        n[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    n
+}
+
+fn fan(face: &[u32]) -> Vec<[u32; 3]> {
+    (1..face.len().saturating_sub(1))
+        .map(|i| [face[0], face[i], face[i + 1]])
+        .collect()
+}
+
+fn cross_2d(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn in_triangle(p: [f64; 2], a: [f64; 2], b: [f64; 2], c: [f64; 2], sign: f64) -> bool {
+    cross_2d(a, b, p) * sign >= 0.0
+        && cross_2d(b, c, p) * sign >= 0.0
+        && cross_2d(c, a, p) * sign >= 0.0
+}
+
+fn ear_clipping(face: &[u32], positions: &[[f32; 3]]) -> Option<Vec<[u32; 3]>> {
+    // project onto the coordinate plane most parallel to the polygon
+    let n = polygon_normal(face, positions);
+    let axis = (0..3)
+        .max_by(|a, b| n[*a].abs().total_cmp(&n[*b].abs()))
+        .unwrap();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let points: Vec<[f64; 2]> = face
+        .iter()
+        .map(|i| {
+            let p = positions[*i as usize];
+            [p[u] as f64, p[v] as f64]
+        })
+        .collect();
+    let sign = if n[axis] < 0.0 { -1.0 } else { 1.0 };
+
+    let mut remaining: Vec<usize> = (0..face.len()).collect();
+    let mut triangles = Vec::with_capacity(face.len() - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            );
+            if cross_2d(points[a], points[b], points[c]) * sign <= 0.0 {
+                return false;
+            }
+            !remaining.iter().any(|&k| {
+                k != a
+                    && k != b
+                    && k != c
+                    && points[k] != points[a]
+                    && points[k] != points[b]
+                    && points[k] != points[c]
+                    && in_triangle(points[k], points[a], points[b], points[c], sign)
+            })
+        })?;
+        let (a, b, c) = (
+            remaining[(ear + m - 1) % m],
+            remaining[ear],
+            remaining[(ear + 1) % m],
+        );
+        triangles.push([face[a], face[b], face[c]]);
+        remaining.remove(ear);
+    }
+    triangles.push([face[remaining[0]], face[remaining[1]], face[remaining[2]]]);
+    Some(triangles)
+}
+
+/// Splits a single polygon into triangles with the same winding order.
+///
+/// Faces with less than three vertices result in no triangles.
+pub fn triangulate_polygon(
+    face: &[u32],
+    positions: &[[f32; 3]],
+    method: TriangulationMethod,
+) -> Vec<[u32; 3]> {
+    if face.len() < 3 {
+        return Vec::new();
+    }
+    match method {
+        TriangulationMethod::Fan => fan(face),
+        TriangulationMethod::EarClipping => {
+            if face.len() == 3 {
+                return fan(face);
+            }
+            ear_clipping(face, positions).unwrap_or_else(|| fan(face))
+        }
+    }
+}
+
+impl Mesh {
+    /// Replaces all faces by triangles.
+    ///
+    /// Returns for every new face the index of the face it was cut from.
+    pub fn triangulate(&mut self, method: TriangulationMethod) -> Result<Vec<usize>, MeshError> {
+        self.check()?;
+        let mut faces = Vec::with_capacity(self.faces.len());
+        let mut sources = Vec::with_capacity(self.faces.len());
+        for (i, f) in self.faces.iter().enumerate() {
+            for t in triangulate_polygon(f, &self.vertices.positions, method) {
+                faces.push(t.to_vec());
+                sources.push(i);
+            }
+        }
+        self.faces = faces;
+        Ok(sources)
+    }
+
+    /// Returns `true` if every face is a triangle.
+    pub fn is_triangulated(&self) -> bool {
+        self.faces.iter().all(|f| f.len() == 3)
+    }
+}
+
+fn index_list(content_type: ScalarType, t: &[u32; 3]) -> Property {
+    match content_type {
+        ScalarType::Char => Property::ListChar(t.iter().map(|v| *v as i8).collect()),
+        ScalarType::UChar => Property::ListUChar(t.iter().map(|v| *v as u8).collect()),
+        ScalarType::Short => Property::ListShort(t.iter().map(|v| *v as i16).collect()),
+        ScalarType::UShort => Property::ListUShort(t.iter().map(|v| *v as u16).collect()),
+        ScalarType::Int => Property::ListInt(t.iter().map(|v| *v as i32).collect()),
+        ScalarType::UInt => Property::ListUInt(t.to_vec()),
+        ScalarType::Float => Property::ListFloat(t.iter().map(|v| *v as f32).collect()),
+        ScalarType::Double => Property::ListDouble(t.iter().map(|v| *v as f64).collect()),
+    }
+}
+
+impl Ply<DefaultElement> {
+    /// Rewrites the `face` element such that it only contains triangles.
+    ///
+    /// The vertex index property keeps its name and type.
+    /// `policy` decides what happens to all other face properties.
+    /// The `count` of the face element is updated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::parser::Parser;
+    /// # use ply_rs::ply::DefaultElement;
+    /// # use ply_rs::mesh::{FacePropertyPolicy, TriangulationMethod};
+    /// let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+    /// let mut f = std::fs::File::open(path).unwrap();
+    /// let mut ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+    ///
+    /// ply.triangulate(TriangulationMethod::EarClipping, FacePropertyPolicy::Duplicate).unwrap();
+    /// assert_eq!(ply.payload["face"].len(), 12);
+    /// ```
+    pub fn triangulate(
+        &mut self,
+        method: TriangulationMethod,
+        policy: FacePropertyPolicy,
+    ) -> Result<(), MeshError> {
+        let mut mesh = Mesh::from_ply(self)?;
+        let face_def = match self.header.elements.iter_mut().find(|e| e.name == "face") {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+        let index_def = find_property(face_def, &FACE_INDEX_NAMES).unwrap().clone();
+        let content_type = match index_def.data_type {
+            PropertyType::List(_, t) => t,
+            PropertyType::Scalar(_) => unreachable!("checked by Mesh::from_ply"),
+        };
+        let sources = mesh.triangulate(method)?;
+
+        let old = self.payload.remove("face").unwrap_or_default();
+        let mut new = Vec::with_capacity(sources.len());
+        for (t, s) in mesh.faces.iter().zip(sources.iter()) {
+            let mut e = match policy {
+                FacePropertyPolicy::Duplicate => old[*s].clone(),
+                FacePropertyPolicy::Drop => DefaultElement::new(),
+            };
+            e.insert(
+                index_def.name.clone(),
+                index_list(content_type, &[t[0], t[1], t[2]]),
+            );
+            new.push(e);
+        }
+        if policy == FacePropertyPolicy::Drop {
+            face_def.properties.retain(|p| p.name == index_def.name);
+        }
+        face_def.count = new.len();
+        self.payload.insert("face".to_string(), new);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ElementDef, PropertyDef};
+
+    #[test]
+    fn fan_ok() {
+        assert_eq!(
+            triangulate_polygon(&[0, 1, 2, 3], &[], TriangulationMethod::Fan),
+            vec![[0, 1, 2], [0, 2, 3]]
+        );
+        assert!(triangulate_polygon(&[0, 1], &[], TriangulationMethod::Fan).is_empty());
+    }
+    #[test]
+    fn ear_clipping_concave_ok() {
+        // an arrow head pointing up, vertex 3 is reflex
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 2.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ];
+        let tris = triangulate_polygon(&[0, 3, 2, 1], &positions, TriangulationMethod::EarClipping);
+        assert_eq!(tris.len(), 2);
+        // the fan from vertex 0 would produce the triangle (0, 3, 2) outside the polygon
+        let area: f64 = tris
+            .iter()
+            .map(|t| polygon_normal(t, &positions)[2].abs() / 2.0)
+            .sum();
+        assert!((area - 1.0).abs() < 1e-9, "area: {}", area);
+    }
+    #[test]
+    fn triangulate_drop_ok() {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut vertex = ElementDef::new("vertex");
+        for n in ["x", "y", "z"] {
+            vertex
+                .properties
+                .push(PropertyDef::new(n, PropertyType::Scalar(ScalarType::Float)));
+        }
+        let mut face = ElementDef::new("face");
+        face.properties.push(PropertyDef::new(
+            "vertex_index",
+            PropertyType::List(ScalarType::UChar, ScalarType::Int),
+        ));
+        face.properties.push(PropertyDef::new(
+            "red",
+            PropertyType::Scalar(ScalarType::UChar),
+        ));
+        ply.header.elements.push(vertex);
+        ply.header.elements.push(face);
+        let vertices = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+            .iter()
+            .map(|p| {
+                let mut e = DefaultElement::new();
+                e.insert("x".to_string(), Property::Float(p[0]));
+                e.insert("y".to_string(), Property::Float(p[1]));
+                e.insert("z".to_string(), Property::Float(0.0));
+                e
+            })
+            .collect();
+        let mut f = DefaultElement::new();
+        f.insert(
+            "vertex_index".to_string(),
+            Property::ListInt(vec![0, 1, 2, 3]),
+        );
+        f.insert("red".to_string(), Property::UChar(7));
+        ply.payload.insert("vertex".to_string(), vertices);
+        ply.payload.insert("face".to_string(), vec![f]);
+
+        let mut dup = ply.clone();
+        dup.triangulate(TriangulationMethod::Fan, FacePropertyPolicy::Duplicate)
+            .unwrap();
+        assert_eq!(dup.payload["face"].len(), 2);
+        assert_eq!(dup.payload["face"][1]["red"], Property::UChar(7));
+        assert_eq!(
+            dup.payload["face"][1]["vertex_index"],
+            Property::ListInt(vec![0, 2, 3])
+        );
+
+        ply.triangulate(TriangulationMethod::Fan, FacePropertyPolicy::Drop)
+            .unwrap();
+        assert!(!ply.payload["face"][0].contains_key("red"));
+        assert_eq!(ply.header.elements[1].properties.len(), 1);
+        assert_eq!(ply.header.elements[1].count, 2);
+        assert!(ply.make_consistent().is_ok());
+    }
+}
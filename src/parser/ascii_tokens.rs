@@ -0,0 +1,478 @@
+//! Splits ascii data into tokens and parses numbers from them without allocating.
+//!
+//! Plain decimals are parsed while scanning them, eight digits at a time.
+//! Other floats are parsed by `fast_float2` and the rest by `str::parse()`.
+
+use std::str::FromStr;
+
+/// Whitespace separated tokens borrowed from a line, like `split_ascii_whitespace()` on bytes.
+pub(crate) struct AsciiTokens<'a> {
+    bytes: &'a [u8],
+    /// Start of the rest of the line.
+    pos: usize,
+}
+
+impl<'a> AsciiTokens<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        AsciiTokens { bytes, pos: 0 }
+    }
+    /// Upper bound of the remaining tokens, every token but the last is followed by a separator.
+    pub fn max_remaining(&self) -> usize {
+        (self.bytes.len() - self.pos).div_ceil(2)
+    }
+    /// Parses the next token with `D::parse_prefix()` while scanning it, returns the token if that doesn't apply.
+    pub fn next_number<D: AsciiNumber>(&mut self) -> Option<std::result::Result<D, &'a [u8]>> {
+        while self.bytes.get(self.pos)?.is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        match D::parse_prefix(self.bytes, self.pos) {
+            Some((v, end)) => {
+                self.pos = end;
+                Some(Ok(v))
+            }
+            None => self.next().map(Err),
+        }
+    }
+}
+
+impl<'a> Iterator for AsciiTokens<'a> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let rest = &self.bytes[self.pos..];
+        let start = match rest.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(start) => start,
+            None => {
+                self.pos = self.bytes.len();
+                return None;
+            }
+        };
+        let token = &rest[start..];
+        let end = token
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .unwrap_or(token.len());
+        self.pos += start + end;
+        Some(&token[..end])
+    }
+}
+
+/// Position of the first `\n` in `bytes`, eight bytes at a time.
+pub(crate) fn find_newline(bytes: &[u8]) -> Option<usize> {
+    let mut chunks = bytes.chunks_exact(8);
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        let chunk = u64::from_le_bytes(chunk.try_into().unwrap()) ^ 0x0a0a_0a0a_0a0a_0a0a;
+        // high bit of the zero bytes, exact up to the first one
+        let zeros = chunk.wrapping_sub(0x0101_0101_0101_0101) & !chunk & 0x8080_8080_8080_8080;
+        if zeros != 0 {
+            return Some(i * 8 + zeros.trailing_zeros() as usize / 8);
+        }
+    }
+    let rest = chunks.remainder();
+    rest.iter()
+        .position(|b| *b == b'\n')
+        .map(|n| bytes.len() - rest.len() + n)
+}
+
+/// Numbers with a fast path for their common spellings.
+///
+/// `parse_fast()` returns `None` for anything unusual, callers then fall back to `str::parse()`,
+/// so results and errors are the same as with `str::parse()` alone.
+pub(crate) trait AsciiNumber: FromStr {
+    fn parse_fast(token: &[u8]) -> Option<Self>;
+    /// Parses the plain decimal spelling at `bytes[start..]` up to whitespace or the end, returns it and where it ends.
+    ///
+    /// Scans the token only once, `None` for anything else, e.g. exponents or more than 18 digits.
+    fn parse_prefix(bytes: &[u8], start: usize) -> Option<(Self, usize)>;
+    /// False for `nan` and infinities, including values too large for the type.
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty, $signed:expr);*) => {$(
+        impl AsciiNumber for $t {
+            fn parse_fast(token: &[u8]) -> Option<Self> {
+                let (negative, digits) = match token.split_first() {
+                    Some((b'-', rest)) if $signed => (true, rest),
+                    Some((b'+', rest)) => (false, rest),
+                    _ => (false, token),
+                };
+                // 18 digits always fit into an i64
+                if digits.is_empty() || digits.len() > 18 {
+                    return None;
+                }
+                let mut value: i64 = 0;
+                for &b in digits {
+                    let d = b.wrapping_sub(b'0');
+                    if d > 9 {
+                        return None;
+                    }
+                    value = value * 10 + i64::from(d);
+                }
+                <$t>::try_from(if negative { -value } else { value }).ok()
+            }
+            fn parse_prefix(bytes: &[u8], start: usize) -> Option<(Self, usize)> {
+                let (negative, start) = match bytes.get(start) {
+                    Some(b'-') if $signed => (true, start + 1),
+                    Some(b'+') => (false, start + 1),
+                    _ => (false, start),
+                };
+                let mut value: u64 = 0;
+                let end = scan_digits(bytes, start, &mut value);
+                // 18 digits always fit into an i64
+                if end == start
+                    || end - start > 18
+                    || bytes.get(end).is_some_and(|b| !b.is_ascii_whitespace())
+                {
+                    return None;
+                }
+                let value = value as i64;
+                let v = <$t>::try_from(if negative { -value } else { value }).ok()?;
+                Some((v, end))
+            }
+        }
+    )*};
+}
+impl_integer!(i8, true; u8, false; i16, true; u16, false; i32, true; u32, false; usize, false);
+
+/// Scans `[+-]digits[.digits]` at `bytes[start..]` up to whitespace or the end, returns sign, digits as integer,
+/// number of fraction digits and end. At most 19 digits, so the integer is exact.
+fn scan_decimal(bytes: &[u8], start: usize) -> Option<(bool, u64, usize, usize)> {
+    let (negative, start) = match bytes.get(start) {
+        Some(b'-') => (true, start + 1),
+        Some(b'+') => (false, start + 1),
+        _ => (false, start),
+    };
+    let mut mantissa: u64 = 0;
+    let mut end = scan_digits(bytes, start, &mut mantissa);
+    let mut digits = end - start;
+    let mut fraction = 0;
+    if bytes.get(end) == Some(&b'.') {
+        let fraction_end = scan_digits(bytes, end + 1, &mut mantissa);
+        fraction = fraction_end - end - 1;
+        digits += fraction;
+        end = fraction_end;
+    }
+    if digits == 0 || digits > 19 || bytes.get(end).is_some_and(|b| !b.is_ascii_whitespace()) {
+        return None;
+    }
+    Some((negative, mantissa, fraction, end))
+}
+
+/// Appends the digits from `start` on to `mantissa`, returns where they end.
+///
+/// Takes eight bytes at a time, at the end of `bytes` its last eight bytes shifted.
+/// Wraps beyond 19 digits, callers reject those.
+#[inline(always)]
+fn scan_digits(bytes: &[u8], start: usize, mantissa: &mut u64) -> usize {
+    let mut end = start;
+    while let Some(chunk) = bytes.get(end..end + 8) {
+        let n = chunk_digits(u64::from_le_bytes(chunk.try_into().unwrap()), mantissa);
+        end += n;
+        if n < 8 {
+            return end;
+        }
+    }
+    if let Some(last) = bytes.len().checked_sub(8).map(|i| &bytes[i..]) {
+        // zeros shifted in behind the end are no digits
+        let chunk = u64::from_le_bytes(last.try_into().unwrap())
+            .checked_shr(8 * (end + 8 - bytes.len()) as u32)
+            .unwrap_or(0);
+        return end + chunk_digits(chunk, mantissa);
+    }
+    while let Some(d) = bytes
+        .get(end)
+        .map(|b| b.wrapping_sub(b'0'))
+        .filter(|d| *d <= 9)
+    {
+        *mantissa = mantissa.wrapping_mul(10).wrapping_add(u64::from(d));
+        end += 1;
+    }
+    end
+}
+
+/// Appends the leading digits of eight bytes to `mantissa`, returns how many there are.
+#[inline(always)]
+fn chunk_digits(chunk: u64, mantissa: &mut u64) -> usize {
+    let values = chunk.wrapping_sub(0x3030_3030_3030_3030);
+    // high bit of every byte that is below `0`, above `9` or not ascii, exact up to the first one
+    let non_digits =
+        (chunk | values | chunk.wrapping_add(0x4646_4646_4646_4646)) & 0x8080_8080_8080_8080;
+    let n = non_digits.trailing_zeros() as usize / 8;
+    if n > 0 {
+        // the first byte is the most significant digit, shifting in zeros in front of the n digits
+        *mantissa = mantissa
+            .wrapping_mul(POW10_U64[n])
+            .wrapping_add(eight_digits(values << (64 - 8 * n)));
+    }
+    n
+}
+
+/// Value of eight digits `0..=9`, one per byte with the first in the lowest byte.
+#[inline(always)]
+fn eight_digits(v: u64) -> u64 {
+    let v = v.wrapping_mul(10).wrapping_add(v >> 8);
+    let v1 = (v & 0x0000_00FF_0000_00FF).wrapping_mul(0x000F_4240_0000_0064);
+    let v2 = ((v >> 16) & 0x0000_00FF_0000_00FF).wrapping_mul(0x0000_2710_0000_0001);
+    u64::from(((v1.wrapping_add(v2)) >> 32) as u32)
+}
+
+const POW10_U64: [u64; 9] = [
+    1,
+    10,
+    100,
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+];
+
+/// Powers of ten that are exact as `f64`.
+const POW10_F64: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// Powers of ten that are exact as `f32`.
+const POW10_F32: [f32; 11] = [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10];
+
+/// `mantissa / 10^fraction` correctly rounded if both are exact as `f64`, Clinger's fast path.
+fn decimal_f64(mantissa: u64, fraction: usize) -> Option<f64> {
+    if mantissa > 1 << 53 || fraction >= POW10_F64.len() {
+        return None;
+    }
+    Some(mantissa as f64 / POW10_F64[fraction])
+}
+
+/// Like `decimal_f64()`, rounding through `f64` unless that result lies exactly between two `f32`,
+/// the only case where rounding twice differs.
+fn decimal_f32(mantissa: u64, fraction: usize) -> Option<f32> {
+    if mantissa <= 1 << 24 && fraction < POW10_F32.len() {
+        return Some(mantissa as f32 / POW10_F32[fraction]);
+    }
+    let v = decimal_f64(mantissa, fraction)?;
+    // the 29 mantissa bits `f32` drops are exactly one half
+    if v.to_bits() & 0x1fff_ffff == 0x1000_0000 {
+        return None;
+    }
+    Some(v as f32)
+}
+
+macro_rules! impl_float {
+    ($($t:ty, $decimal:ident);*) => {$(
+        impl AsciiNumber for $t {
+            fn parse_fast(token: &[u8]) -> Option<Self> {
+                // `nan`, `inf` and their MSVC spellings take the slow path, which applies `FloatSyntax`
+                let unsigned = match token.split_first() {
+                    Some((b'-' | b'+', rest)) => rest,
+                    _ => token,
+                };
+                match unsigned.first() {
+                    Some(b) if b.is_ascii_digit() || *b == b'.' => (),
+                    _ => return None,
+                }
+                fast_float2::parse(token).ok()
+            }
+            fn parse_prefix(bytes: &[u8], start: usize) -> Option<(Self, usize)> {
+                let (negative, mantissa, fraction, end) = scan_decimal(bytes, start)?;
+                let v = $decimal(mantissa, fraction)?;
+                Some((if negative { -v } else { v }, end))
+            }
+            fn is_finite(&self) -> bool {
+                <$t>::is_finite(*self)
+            }
+        }
+    )*};
+}
+impl_float!(f32, decimal_f32; f64, decimal_f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(line: &str) -> Vec<&[u8]> {
+        AsciiTokens::new(line.as_bytes()).collect()
+    }
+
+    #[test]
+    fn tokens_ok() {
+        for line in ["", " \t\r\n", "1", " 1 -2\t3.5 \r\n", "a\x0cb  c\n"] {
+            let expected: Vec<&[u8]> = line.split_ascii_whitespace().map(str::as_bytes).collect();
+            assert_eq!(tokens(line), expected, "{:?}", line);
+            assert!(AsciiTokens::new(line.as_bytes()).max_remaining() >= expected.len());
+        }
+    }
+    #[test]
+    fn parse_fast_same_as_std() {
+        let inputs = [
+            "0",
+            "-0",
+            "+7",
+            "-128",
+            "127",
+            "128",
+            "255",
+            "256",
+            "-1",
+            "007",
+            "",
+            "-",
+            "+",
+            "1a",
+            "99999999999999999999",
+            "4294967295",
+            "-2147483648",
+            "1.5",
+            ".5",
+            "5.",
+            "-.5e3",
+            "1E5",
+            "nan",
+            "inf",
+            "1.#INF",
+            "0x10",
+            "1e",
+            "1e+",
+            "..5",
+            "1.5.5",
+            "1e5x",
+            "-.",
+            "+-1",
+            "1_0",
+            "3.4028236e38",
+            "1e-50",
+        ];
+        fn check<T: AsciiNumber + PartialEq + std::fmt::Debug>(s: &str) {
+            if let Some(v) = T::parse_fast(s.as_bytes()) {
+                assert_eq!(Some(v), s.parse::<T>().ok(), "{:?}", s);
+            }
+        }
+        for s in inputs {
+            check::<i8>(s);
+            check::<u8>(s);
+            check::<i16>(s);
+            check::<u16>(s);
+            check::<i32>(s);
+            check::<u32>(s);
+            check::<usize>(s);
+            check::<f32>(s);
+            check::<f64>(s);
+        }
+        assert_eq!(u8::parse_fast(b"255"), Some(255));
+        assert_eq!(i32::parse_fast(b"-2147483648"), Some(i32::MIN));
+        assert_eq!(u32::parse_fast(b"-0"), None);
+        assert_eq!(f32::parse_fast(b"-.5e3"), Some(-500.0));
+        assert_eq!(f64::parse_fast(b"nan"), None);
+    }
+
+    /// Reads every token of `line` with `next_number()` and compares to `str::parse()`.
+    fn check_line<T: AsciiNumber + std::fmt::Debug>(line: &str) {
+        let mut tokens = AsciiTokens::new(line.as_bytes());
+        for expected in line.split_ascii_whitespace() {
+            match tokens.next_number::<T>() {
+                // `Debug` tells `-0.0` from `0.0`
+                Some(Ok(v)) => assert_eq!(
+                    format!("{:?}", v),
+                    format!("{:?}", expected.parse::<T>().ok().unwrap()),
+                    "{:?} in {:?}",
+                    expected,
+                    line
+                ),
+                Some(Err(token)) => assert_eq!(token, expected.as_bytes(), "{:?}", line),
+                None => panic!("{:?} missing in {:?}", expected, line),
+            }
+        }
+        assert!(tokens.next_number::<T>().is_none(), "{:?}", line);
+    }
+    fn check_all(line: &str) {
+        check_line::<i8>(line);
+        check_line::<u8>(line);
+        check_line::<i16>(line);
+        check_line::<u16>(line);
+        check_line::<i32>(line);
+        check_line::<u32>(line);
+        check_line::<usize>(line);
+        check_line::<f32>(line);
+        check_line::<f64>(line);
+    }
+    #[test]
+    fn next_number_same_as_std() {
+        let inputs = [
+            "0",
+            "-0",
+            "+7",
+            "255",
+            "-129",
+            "5.",
+            ".5",
+            "+.5",
+            "-0.0",
+            ".",
+            "-.",
+            "1e5",
+            "1.5e-3",
+            "nan",
+            "-inf",
+            "1.#INF",
+            "0x10",
+            "1_0",
+            "12a",
+            "0.1",
+            "12.345678",
+            "-0.999999",
+            "123456789012345678",
+            "1234567890123456789",
+            "12345678901234567890",
+            "0.000000000000000000001",
+            "16777216",
+            // exactly between two `f32`
+            "16777217",
+            "16777219",
+            "33554434",
+            // rounded to a value between two `f32` as `f64`
+            "0.0001374772473354824",
+            "0.03861991874873638",
+            "9007199254740993",
+            "4294967295.5",
+            "3.4028235e38",
+            "340282350000000000000000000000000000000",
+        ];
+        for s in inputs {
+            check_all(s);
+            check_all(&format!("{}\n", s));
+            check_all(&format!(" \t{} 12345678 9\r\n", s));
+            check_all(&format!("1 {} {}", s, s));
+        }
+    }
+    #[test]
+    fn next_number_random_same_as_std() {
+        let mut state = 12345u64;
+        let mut next = |n: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        for _ in 0..20_000 {
+            let mut line = String::new();
+            for _ in 0..=next(4) {
+                let digits: String = (0..=next(20))
+                    .map(|_| char::from(b'0' + next(10) as u8))
+                    .collect();
+                let point = next(digits.len() as u64 + 2) as usize;
+                let sign = ["", "-", "+"][next(3) as usize];
+                line += sign;
+                match digits.split_at_checked(point) {
+                    Some((int, fraction)) => line += &format!("{}.{}", int, fraction),
+                    None => line += &digits,
+                }
+                line += [" ", "  ", "\t"][next(3) as usize];
+            }
+            check_all(line.trim_end());
+            check_all(&line);
+        }
+    }
+}
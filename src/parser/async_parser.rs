@@ -0,0 +1,392 @@
+//! Reads PLY files from tokio's `AsyncBufRead`.
+
+use super::element_state::ElementState;
+use super::grammar;
+use super::{list_count, missing_list_entries, missing_property, scan_ascii_token};
+use super::{parse_ascii_rethrow, AsciiMode, FloatSyntax, Parser};
+use crate::ply::{
+    ElementDef, Encoding, Header, HeaderLayout, HeaderLine, Payload, Ply, PropertyAccess,
+    PropertyType, ScalarType,
+};
+use crate::util::LocationTracker;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::io::Result;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+
+/// Reads a PLY file from an `AsyncBufRead`, available with the `tokio` feature.
+///
+/// Mirrors the element level API of `Parser`.
+/// Every header line and element is read asynchronously and then decoded by `Parser`,
+/// so results and errors are the same as for the synchronous API.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut data = "ply\nformat ascii 1.0\nelement point 2\nproperty int x\nend_header\n1\n2\n".as_bytes();
+/// let p = parser::AsyncParser::<ply::DefaultElement>::new();
+/// let header = p.read_header(&mut data).await.unwrap();
+///
+/// let mut count = 0;
+/// p.read_payload_with(&mut data, &header, |_element_def, _element| {
+///     count += 1;
+///     Ok(())
+/// }).await.unwrap();
+/// assert_eq!(count, 2);
+/// # });
+/// ```
+#[derive(Default)]
+pub struct AsyncParser<E: PropertyAccess> {
+    parser: Parser<E>,
+}
+
+/// Reads with the settings of `parser`.
+impl<E: PropertyAccess> From<Parser<E>> for AsyncParser<E> {
+    fn from(parser: Parser<E>) -> Self {
+        AsyncParser { parser }
+    }
+}
+
+impl<E: PropertyAccess> AsyncParser<E> {
+    /// Creates a new `AsyncParser<E>`, where `E` is the type to store the element data in.
+    pub fn new() -> Self {
+        AsyncParser {
+            parser: Parser::new(),
+        }
+    }
+
+    /// Selects the spellings accepted for ascii floating point values, see `Parser::with_float_syntax()`.
+    pub fn with_float_syntax(mut self, syntax: FloatSyntax) -> Self {
+        self.parser = self.parser.with_float_syntax(syntax);
+        self
+    }
+
+    /// Selects how ascii payloads are split into elements, see `Parser::with_ascii_mode()`.
+    pub fn with_ascii_mode(mut self, mode: AsciiMode) -> Self {
+        self.parser = self.parser.with_ascii_mode(mode);
+        self
+    }
+
+    /// Reads an entire PLY file, see `Parser::read_ply()`.
+    pub async fn read_ply<T: AsyncRead + Unpin>(&self, source: &mut T) -> Result<Ply<E>> {
+        let mut source = BufReader::new(source);
+        let text = self.read_header_text(&mut source).await?;
+        let header = self.parser.read_header(&mut text.as_bytes())?;
+        // payload lines are counted from the end of the header, as `Parser::read_ply()` does
+        let mut location = LocationTracker::new();
+        location.line_index = text.matches('\n').count() + 1;
+        let payload = self
+            .__read_payload(&mut source, &mut location, &header)
+            .await?;
+        let mut ply = Ply::new();
+        ply.header = header;
+        ply.payload = payload;
+        Ok(ply)
+    }
+
+    /// Reads header until and inclusive `end_header`, see `Parser::read_header()`.
+    pub async fn read_header<T: AsyncBufRead + Unpin>(&self, reader: &mut T) -> Result<Header> {
+        let text = self.read_header_text(reader).await?;
+        self.parser.read_header(&mut text.as_bytes())
+    }
+
+    /// Reads the header and the layout of its lines, see `Parser::read_header_with_layout()`.
+    pub async fn read_header_with_layout<T: AsyncBufRead + Unpin>(
+        &self,
+        reader: &mut T,
+    ) -> Result<(Header, HeaderLayout)> {
+        let text = self.read_header_text(reader).await?;
+        self.parser.read_header_with_layout(&mut text.as_bytes())
+    }
+
+    /// Collects lines up to `end_header`, or up to the first line `Parser` will reject.
+    async fn read_header_text<T: AsyncBufRead + Unpin>(&self, reader: &mut T) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            let start = text.len();
+            if reader.read_line(&mut text).await? == 0 {
+                break;
+            }
+            match grammar::line(&text[start..]) {
+                Ok(HeaderLine::EndHeader) | Err(_) => break,
+                Ok(_) => (),
+            }
+        }
+        Ok(text)
+    }
+
+    /// Reads the entire payload, see `Parser::read_payload()`.
+    pub async fn read_payload<T: AsyncBufRead + Unpin>(
+        &self,
+        reader: &mut T,
+        header: &Header,
+    ) -> Result<Payload<E>> {
+        let mut location = LocationTracker::new();
+        self.__read_payload(reader, &mut location, header).await
+    }
+
+    async fn __read_payload<T: AsyncBufRead + Unpin>(
+        &self,
+        reader: &mut T,
+        location: &mut LocationTracker,
+        header: &Header,
+    ) -> Result<Payload<E>> {
+        let mut payload = Payload::with_capacity(header.elements.len());
+        for element_def in &header.elements {
+            payload.insert(
+                element_def.name.clone(),
+                Vec::with_capacity(element_def.count),
+            );
+        }
+        self.__read_payload_with(reader, location, header, |element_def, element| {
+            payload.get_mut(&element_def.name).unwrap().push(element);
+            Ok(())
+        })
+        .await?;
+        Ok(payload)
+    }
+
+    /// Reads a single element, see `Parser::read_element()`.
+    ///
+    /// Every call sets up the schema and buffers for `element_def` anew, use `element_reader()` to read many elements.
+    pub async fn read_element<T: AsyncBufRead + Unpin>(
+        &self,
+        reader: &mut T,
+        element_def: &ElementDef,
+        header: &Header,
+    ) -> Result<E> {
+        self.element_reader(element_def, header).read(reader).await
+    }
+
+    /// Creates a reader for the elements of `element_def`, see `Parser::element_reader()`.
+    pub fn element_reader<'a>(
+        &'a self,
+        element_def: &'a ElementDef,
+        header: &'a Header,
+    ) -> AsyncElementReader<'a, E> {
+        AsyncElementReader {
+            parser: self,
+            element_def,
+            header,
+            state: ElementState::bind::<E>(element_def),
+            location: LocationTracker::new(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Hands elements one by one to `visitor`, see `Parser::read_payload_with()`.
+    ///
+    /// Only the current element is kept in memory.
+    pub async fn read_payload_with<
+        T: AsyncBufRead + Unpin,
+        F: FnMut(&ElementDef, E) -> Result<()>,
+    >(
+        &self,
+        reader: &mut T,
+        header: &Header,
+        visitor: F,
+    ) -> Result<()> {
+        let mut location = LocationTracker::new();
+        self.__read_payload_with(reader, &mut location, header, visitor)
+            .await
+    }
+
+    async fn __read_payload_with<
+        T: AsyncBufRead + Unpin,
+        F: FnMut(&ElementDef, E) -> Result<()>,
+    >(
+        &self,
+        reader: &mut T,
+        location: &mut LocationTracker,
+        header: &Header,
+        mut visitor: F,
+    ) -> Result<()> {
+        let mut buf = Vec::new();
+        for element_def in &header.elements {
+            let mut state = ElementState::bind::<E>(element_def);
+            for _ in 0..element_def.count {
+                let element = self
+                    .__read_element(reader, location, &mut buf, element_def, &mut state, header)
+                    .await?;
+                visitor(element_def, element)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn __read_element<T: AsyncBufRead + Unpin>(
+        &self,
+        reader: &mut T,
+        location: &mut LocationTracker,
+        buf: &mut Vec<u8>,
+        element_def: &ElementDef,
+        state: &mut ElementState,
+        header: &Header,
+    ) -> Result<E> {
+        let element = match header.encoding {
+            Encoding::Ascii if self.parser.ascii_mode == AsciiMode::Tokens => {
+                // positions continue within the line, no `next_line()`
+                return self
+                    .__read_ascii_token_element(reader, location, buf, element_def, state)
+                    .await;
+            }
+            Encoding::Ascii => {
+                buf.clear();
+                reader.read_until(b'\n', buf).await?;
+                match self.parser.__read_ascii_element(buf, element_def, state) {
+                    Ok(e) => e,
+                    Err(e) => {
+                        return parse_ascii_rethrow(
+                            location,
+                            &String::from_utf8_lossy(buf),
+                            e,
+                            "Couln't read element line.",
+                        )
+                    }
+                }
+            }
+            Encoding::BinaryBigEndian => {
+                read_binary_bytes::<T, BigEndian>(reader, element_def, buf).await?;
+                self.parser.__read_binary_element::<_, BigEndian>(
+                    &mut buf.as_slice(),
+                    element_def,
+                    state,
+                )?
+            }
+            Encoding::BinaryLittleEndian => {
+                read_binary_bytes::<T, LittleEndian>(reader, element_def, buf).await?;
+                self.parser.__read_binary_element::<_, LittleEndian>(
+                    &mut buf.as_slice(),
+                    element_def,
+                    state,
+                )?
+            }
+        };
+        location.next_line();
+        Ok(element)
+    }
+
+    /// Reads one element from a stream of tokens, see `AsciiMode::Tokens`.
+    async fn __read_ascii_token_element<T: AsyncBufRead + Unpin>(
+        &self,
+        reader: &mut T,
+        location: &mut LocationTracker,
+        tokens: &mut Vec<u8>,
+        element_def: &ElementDef,
+        state: &mut ElementState,
+    ) -> Result<E> {
+        let mut vals = state.new_element::<E>();
+        for (i, def) in element_def.properties.iter().enumerate() {
+            tokens.clear();
+            let position = match read_ascii_token(reader, location, tokens).await? {
+                Some(p) => p,
+                None => return missing_property(location, def),
+            };
+            if let PropertyType::List(..) = def.data_type {
+                let count = list_count(tokens);
+                for n in 0..count {
+                    if read_ascii_token(reader, location, tokens).await?.is_none() {
+                        return missing_list_entries(&position, tokens, def, count, n);
+                    }
+                }
+            }
+            self.parser
+                .__read_property_tokens(tokens, &position, i, def, &mut vals, state)?;
+        }
+        Ok(vals)
+    }
+}
+
+/// Reads the elements of one `ElementDef` one at a time, see `ElementReader`.
+pub struct AsyncElementReader<'a, E: PropertyAccess> {
+    parser: &'a AsyncParser<E>,
+    element_def: &'a ElementDef,
+    header: &'a Header,
+    state: ElementState,
+    location: LocationTracker,
+    buf: Vec<u8>,
+}
+
+impl<E: PropertyAccess> AsyncElementReader<'_, E> {
+    /// Reads the next element from `reader`.
+    pub async fn read<T: AsyncBufRead + Unpin>(&mut self, reader: &mut T) -> Result<E> {
+        self.parser
+            .__read_element(
+                reader,
+                &mut self.location,
+                &mut self.buf,
+                self.element_def,
+                &mut self.state,
+                self.header,
+            )
+            .await
+    }
+}
+
+/// Appends the next whitespace separated token to `tokens`, returns its position.
+async fn read_ascii_token<T: AsyncBufRead + Unpin>(
+    reader: &mut T,
+    location: &mut LocationTracker,
+    tokens: &mut Vec<u8>,
+) -> Result<Option<LocationTracker>> {
+    let mut position = None;
+    loop {
+        let buf = reader.fill_buf().await?;
+        if buf.is_empty() {
+            return Ok(position);
+        }
+        let (used, done) = scan_ascii_token(buf, location, tokens, &mut position);
+        reader.consume(used);
+        if done {
+            return Ok(position);
+        }
+    }
+}
+
+/// Reads the bytes of one binary element into `buf`.
+///
+/// Stops early at the end of the input or at an invalid list index type,
+/// decoding the incomplete bytes then fails like the synchronous parser does.
+async fn read_binary_bytes<T: AsyncRead + Unpin, B: ByteOrder>(
+    reader: &mut T,
+    element_def: &ElementDef,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    buf.clear();
+    for def in &element_def.properties {
+        match def.data_type {
+            PropertyType::Scalar(scalar_type) => {
+                if !fill(reader, buf, scalar_type.byte_size()).await? {
+                    return Ok(());
+                }
+            }
+            PropertyType::List(index_type, scalar_type) => {
+                let start = buf.len();
+                if !fill(reader, buf, index_type.byte_size()).await? {
+                    return Ok(());
+                }
+                let raw = &buf[start..];
+                let count = match index_type {
+                    ScalarType::Char => raw[0] as i8 as usize,
+                    ScalarType::UChar => raw[0] as usize,
+                    ScalarType::Short => B::read_i16(raw) as usize,
+                    ScalarType::UShort => B::read_u16(raw) as usize,
+                    ScalarType::Int => B::read_i32(raw) as usize,
+                    ScalarType::UInt => B::read_u32(raw) as usize,
+                    ScalarType::Float | ScalarType::Double => return Ok(()),
+                };
+                if !fill(reader, buf, count.saturating_mul(scalar_type.byte_size())).await? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Appends up to `n` bytes, returns whether all of them were available.
+async fn fill<T: AsyncRead + Unpin>(reader: &mut T, buf: &mut Vec<u8>, n: usize) -> Result<bool> {
+    let read = reader.take(n as u64).read_to_end(buf).await?;
+    Ok(read == n)
+}
@@ -0,0 +1,64 @@
+//! Reads the elements of one `ElementDef` one at a time.
+
+use super::element_state::ElementState;
+use super::Parser;
+use crate::ply::{ElementDef, Header, PropertyAccess};
+use crate::util::LocationTracker;
+use std::io::{BufRead, Result};
+
+/// Reads the elements of one `ElementDef` one at a time, created by `Parser::element_reader()`.
+///
+/// The schema of `E`, the shared `ElementDef` and the list buffers are set up once and reused for every element,
+/// whereas `Parser::read_element()` sets them up on every call.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// # let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+/// # let f = std::fs::File::open(path).unwrap();
+/// let mut f = std::io::BufReader::new(f);
+/// let p = parser::Parser::<ply::CompactElement>::new();
+/// let header = p.read_header(&mut f).unwrap();
+///
+/// let mut vertices = p.element_reader(&header.elements[0], &header);
+/// let first = vertices.read(&mut f).unwrap();
+/// let second = vertices.read(&mut f).unwrap();
+/// assert!(std::sync::Arc::ptr_eq(first.element_def(), second.element_def()));
+/// ```
+pub struct ElementReader<'a, E: PropertyAccess> {
+    parser: &'a Parser<E>,
+    element_def: &'a ElementDef,
+    header: &'a Header,
+    state: ElementState,
+    location: LocationTracker,
+    line: Vec<u8>,
+}
+
+impl<'a, E: PropertyAccess> ElementReader<'a, E> {
+    pub(crate) fn new(
+        parser: &'a Parser<E>,
+        element_def: &'a ElementDef,
+        header: &'a Header,
+    ) -> Self {
+        ElementReader {
+            parser,
+            element_def,
+            header,
+            state: ElementState::bind::<E>(element_def),
+            location: LocationTracker::new(),
+            line: Vec::new(),
+        }
+    }
+    /// Reads the next element from `reader`, lines in errors are counted from the first element read.
+    pub fn read<T: BufRead>(&mut self, reader: &mut T) -> Result<E> {
+        self.parser.__read_element(
+            reader,
+            &mut self.location,
+            &mut self.line,
+            self.element_def,
+            &mut self.state,
+            self.header,
+        )
+    }
+}
@@ -0,0 +1,45 @@
+//! State kept while reading all elements of one `ElementDef`.
+
+use crate::ply::{ElementDef, PropertyAccess, PropertySchema};
+use std::sync::Arc;
+
+/// The schema bound by the element type, the shared `ElementDef` and list buffers reused across elements.
+///
+/// Lists are read into the buffers and handed to the element as slices,
+/// so reading a list allocates only while the buffer grows.
+pub(crate) struct ElementState {
+    pub schema: Option<PropertySchema>,
+    pub lists: ListBuffers,
+    /// Only cloned for types that ask for it with `PropertyAccess::SHARES_ELEMENT_DEF`.
+    element_def: Option<Arc<ElementDef>>,
+}
+
+impl ElementState {
+    pub fn bind<E: PropertyAccess>(element_def: &ElementDef) -> Self {
+        ElementState {
+            schema: E::bind_schema(element_def),
+            lists: ListBuffers::default(),
+            element_def: E::SHARES_ELEMENT_DEF.then(|| Arc::new(element_def.clone())),
+        }
+    }
+    /// Creates an empty element, call it instead of `E::new()`.
+    pub fn new_element<E: PropertyAccess>(&self) -> E {
+        match self.element_def {
+            Some(ref element_def) => E::new_shared(element_def),
+            None => E::new(),
+        }
+    }
+}
+
+/// One buffer per `ScalarType`.
+#[derive(Default)]
+pub(crate) struct ListBuffers {
+    pub char: Vec<i8>,
+    pub uchar: Vec<u8>,
+    pub short: Vec<i16>,
+    pub ushort: Vec<u16>,
+    pub int: Vec<i32>,
+    pub uint: Vec<u32>,
+    pub float: Vec<f32>,
+    pub double: Vec<f64>,
+}
@@ -7,23 +7,40 @@ use std::result;
 
 use std::io::{BufRead, ErrorKind, Result};
 
+mod ascii_tokens;
+mod element_reader;
+mod element_state;
 mod ply_grammar;
 
+pub use self::element_reader::ElementReader;
+
+#[cfg(feature = "tokio")]
+mod async_parser;
+#[cfg(feature = "tokio")]
+pub use self::async_parser::{AsyncElementReader, AsyncParser};
+
+use self::ascii_tokens::{find_newline, AsciiNumber, AsciiTokens};
+use self::element_state::{ElementState, ListBuffers};
 use self::ply_grammar::grammar;
-use self::ply_grammar::Line;
+use crate::compression::Decompressor;
 use crate::util::LocationTracker;
 
+/// Errors name the column too if `location` tracks one, i.e. in `AsciiMode::Tokens`.
 fn parse_ascii_rethrow<T, E: Debug>(
     location: &LocationTracker,
     line_str: &str,
     e: E,
     message: &str,
 ) -> Result<T> {
+    let column = match location.column_index {
+        0 => String::new(),
+        c => format!(", column {}", c),
+    };
     Err(io::Error::new(
         ErrorKind::InvalidInput,
         format!(
-            "Line {}: {}\n\tString: '{}'\n\tError: {:?}",
-            location.line_index, message, line_str, e
+            "Line {}{}: {}\n\tString: '{}'\n\tError: {:?}",
+            location.line_index, column, message, line_str, e
         ),
     ))
 }
@@ -104,12 +121,43 @@ use std::marker::PhantomData;
 #[derive(Default)]
 pub struct Parser<E: PropertyAccess> {
     phantom: PhantomData<E>,
+    ascii_mode: AsciiMode,
+    float_syntax: FloatSyntax,
+}
+
+/// How an ascii payload is split into elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AsciiMode {
+    /// Every element is on a line of its own. Values after the last property are ignored.
+    #[default]
+    Lines,
+    /// Values form a single whitespace separated stream, the `ElementDef` alone decides where an element ends.
+    ///
+    /// Reads files with face lists wrapped across lines or several points on one line.
+    Tokens,
+}
+
+/// Spellings accepted for `float` and `double` values in ascii data.
+///
+/// Every level accepts the spellings of the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatSyntax {
+    /// Decimal and exponent notation, e.g. `-1`, `.5`, `5.`, `2.5E-3`. No `nan` or `inf`, nor values out of range like `1e39` for a `float`.
+    Finite,
+    /// Also `nan`, `inf` and `infinity` in any case and with an optional sign, as written by C's `printf`.
+    Standard,
+    /// Also MSVC's `1.#INF`, `-1.#IND`, `1.#QNAN`, `1.#SNAN` and `nan(ind)`, including trailing digits like `1.#INF00`.
+    #[default]
+    Lenient,
 }
 
 //use std::marker::PhantomData;
 //use std::io::{ Read, BufReader };
 use crate::ply::Ply;
-use crate::ply::{Encoding, Header, Payload};
+use crate::ply::{
+    Diff, DiffAccumulator, DiffOptions, Encoding, Fingerprint, FingerprintAccumulator,
+    FingerprintOptions, Header, Payload, Statistics, StatisticsAccumulator,
+};
 
 impl<E: PropertyAccess> Parser<E> {
     /// Creates a new `Parser<E>`, where `E` is the type to store the element data in.
@@ -118,31 +166,163 @@ impl<E: PropertyAccess> Parser<E> {
     pub fn new() -> Self {
         Parser {
             phantom: PhantomData,
+            ascii_mode: AsciiMode::Lines,
+            float_syntax: FloatSyntax::Lenient,
         }
     }
 
+    /// Selects the spellings accepted for ascii floating point values, see `FloatSyntax`.
+    pub fn with_float_syntax(mut self, syntax: FloatSyntax) -> Self {
+        self.float_syntax = syntax;
+        self
+    }
+
+    /// Selects how ascii payloads are split into elements, see `AsciiMode`.
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut data = "ply\nformat ascii 1.0\nelement point 3\nproperty int x\nend_header\n1 2\n3\n".as_bytes();
+    /// let p = parser::Parser::<ply::DefaultElement>::new().with_ascii_mode(parser::AsciiMode::Tokens);
+    /// let ply = p.read_ply(&mut data).unwrap();
+    /// assert_eq!(ply.payload["point"].len(), 3);
+    /// ```
+    pub fn with_ascii_mode(mut self, mode: AsciiMode) -> Self {
+        self.ascii_mode = mode;
+        self
+    }
+
     /// Expects the complete content of a PLY file.
     ///
     /// A PLY file starts with "ply\n". `read_ply` reads until all elements have been read as
     /// defined in the header of the PLY file.
+    ///
+    /// Gzip or zstd compressed input is decompressed on the fly, see `compression`.
     pub fn read_ply<T: Read>(&self, source: &mut T) -> Result<Ply<E>> {
-        let mut source = BufReader::new(source);
+        let mut source = Decompressor::new(BufReader::new(source))?;
         let mut location = LocationTracker::new();
-        let header = self.__read_header(&mut source, &mut location)?;
+        let header = self.__read_header(&mut source, &mut location, None)?;
         let payload = self.__read_payload(&mut source, &mut location, &header)?;
         let mut ply = Ply::new();
         ply.header = header;
         ply.payload = payload;
         Ok(ply)
     }
+
+    /// Reads a PLY file like `read_ply()`, but from a `BufRead` and without decompression.
+    ///
+    /// Only the header and the elements are consumed, data after the last element is left in `reader`.
+    pub fn read_ply_from_buf<T: BufRead>(&self, reader: &mut T) -> Result<Ply<E>> {
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(reader, &mut location, None)?;
+        let payload = self.__read_payload(reader, &mut location, &header)?;
+        let mut ply = Ply::new();
+        ply.header = header;
+        ply.payload = payload;
+        Ok(ply)
+    }
+
+    /// Reads an entire PLY file, but only keeps statistics of the payload.
+    ///
+    /// Elements are summarized one by one and then dropped, see `StatisticsAccumulator`.
+    pub fn read_statistics<T: Read>(&self, source: &mut T) -> Result<(Header, Statistics)> {
+        let mut source = Decompressor::new(BufReader::new(source))?;
+        let header = self.read_header(&mut source)?;
+        let mut acc = StatisticsAccumulator::new(&header);
+        self.read_payload_with(&mut source, &header, |element_def, element| {
+            acc.add(element_def, &element);
+            Ok(())
+        })?;
+        Ok((header, acc.finish()))
+    }
+
+    /// Reads an entire PLY file, but only keeps its fingerprint, see `Fingerprint`.
+    pub fn read_fingerprint<T: Read>(
+        &self,
+        source: &mut T,
+        options: &FingerprintOptions,
+    ) -> Result<(Header, Fingerprint)> {
+        let mut source = Decompressor::new(BufReader::new(source))?;
+        let header = self.read_header(&mut source)?;
+        let mut acc = FingerprintAccumulator::new(&header, options);
+        self.read_payload_with(&mut source, &header, |element_def, element| {
+            acc.add(element_def, &element);
+            Ok(())
+        })?;
+        Ok((header, acc.finish()))
+    }
+
+    /// Compares two PLY files of any encoding, see `Diff`.
+    ///
+    /// Both payloads are read in step, one element at a time, so neither is kept in memory.
+    /// This requires the element types declared in both files to appear in the same order.
+    pub fn read_diff<L: Read, R: Read>(
+        &self,
+        left: &mut L,
+        right: &mut R,
+        options: &DiffOptions,
+    ) -> Result<Diff> {
+        let mut left = Decompressor::new(BufReader::new(left))?;
+        let mut right = Decompressor::new(BufReader::new(right))?;
+        let left_header = self.read_header(&mut left)?;
+        let right_header = self.read_header(&mut right)?;
+        let mut acc = DiffAccumulator::new(&left_header, &right_header, options);
+        let mut next_right = 0;
+        for left_def in &left_header.elements {
+            let position = right_header
+                .elements
+                .iter()
+                .position(|r| r.name == left_def.name);
+            let right_def = match position {
+                Some(p) if p < next_right => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Element `{}` appears in a different order, files can't be compared in step.",
+                            left_def.name
+                        ),
+                    ))
+                }
+                Some(p) => {
+                    for skipped in &right_header.elements[next_right..p] {
+                        let mut skipped_reader = self.element_reader(skipped, &right_header);
+                        for _ in 0..skipped.count {
+                            skipped_reader.read(&mut right)?;
+                        }
+                    }
+                    next_right = p + 1;
+                    Some(&right_header.elements[p])
+                }
+                None => None,
+            };
+            let right_count = right_def.map_or(0, |r| r.count);
+            let mut left_reader = self.element_reader(left_def, &left_header);
+            let mut right_reader = right_def.map(|r| self.element_reader(r, &right_header));
+            for i in 0..left_def.count.max(right_count) {
+                let l = if i < left_def.count {
+                    Some(left_reader.read(&mut left)?)
+                } else {
+                    None
+                };
+                let r = match right_reader {
+                    Some(ref mut right_reader) if i < right_count => {
+                        Some(right_reader.read(&mut right)?)
+                    }
+                    _ => None,
+                };
+                if let (Some(l), Some(r)) = (l, r) {
+                    acc.add(&left_def.name, i, &l, &r);
+                }
+            }
+        }
+        Ok(acc.finish())
+    }
 }
 
 // use ply::{ Header, Encoding };
-use crate::ply::{Comment, ElementDef, ObjInfo, PropertyAccess, Version};
+use crate::ply::{Comment, ElementDef, HeaderLayout, HeaderLine, ObjInfo, PropertyAccess, Version};
 /*
 use util::LocationTracker;
 use super::Parser;
-use super::Line;
 use super::grammar;
 use super::{parse_ascii_error, parse_ascii_rethrow};
 use std::io;
@@ -160,9 +340,23 @@ impl<E: PropertyAccess> Parser<E> {
     /// This method reads all headere elemnts up to `end_header`.
     pub fn read_header<T: BufRead>(&self, reader: &mut T) -> Result<Header> {
         let mut line = LocationTracker::new();
-        self.__read_header(reader, &mut line)
+        self.__read_header(reader, &mut line, None)
     }
-    pub fn read_header_line(&self, line: &str) -> Result<Line> {
+    /// Reads the header like `read_header()` and also records the original text and order of its lines.
+    ///
+    /// Pass both to `Writer::write_header_with_layout()` to write the header back without reordering
+    /// comments or respelling types.
+    pub fn read_header_with_layout<T: BufRead>(
+        &self,
+        reader: &mut T,
+    ) -> Result<(Header, HeaderLayout)> {
+        let mut line = LocationTracker::new();
+        let mut layout = HeaderLayout::new();
+        let header = self.__read_header(reader, &mut line, Some(&mut layout))?;
+        Ok((header, layout))
+    }
+    /// Parses a single header line.
+    pub fn read_header_line(&self, line: &str) -> Result<HeaderLine> {
         match self.__read_header_line(line) {
             Ok(l) => Ok(l),
             Err(e) => Err(io::Error::new(
@@ -176,7 +370,7 @@ impl<E: PropertyAccess> Parser<E> {
     fn __read_header_line(
         &self,
         line_str: &str,
-    ) -> result::Result<Line, peg::error::ParseError<peg::str::LineCol>> {
+    ) -> result::Result<HeaderLine, peg::error::ParseError<peg::str::LineCol>> {
         grammar::line(line_str)
     }
 
@@ -184,12 +378,13 @@ impl<E: PropertyAccess> Parser<E> {
         &self,
         reader: &mut T,
         location: &mut LocationTracker,
+        mut layout: Option<&mut HeaderLayout>,
     ) -> Result<Header> {
         location.next_line();
         let mut line_str = String::new();
         reader.read_line(&mut line_str)?;
         match self.__read_header_line(&line_str) {
-            Ok(Line::MagicNumber) => (),
+            Ok(HeaderLine::MagicNumber) => (),
             Ok(l) => {
                 return parse_ascii_error(
                     location,
@@ -201,9 +396,12 @@ impl<E: PropertyAccess> Parser<E> {
                 return parse_ascii_rethrow(location, &line_str, e, "Expected magic number 'ply'.")
             }
         }
+        if let Some(ref mut layout) = layout {
+            layout.push(HeaderLine::MagicNumber, &line_str);
+        }
         match grammar::line(&line_str) {
             Err(e) => return Err(io::Error::new(ErrorKind::InvalidInput, e)),
-            Ok(l @ Line::MagicNumber) => l,
+            Ok(l @ HeaderLine::MagicNumber) => l,
             Ok(ob) => {
                 return Err(io::Error::new(
                     ErrorKind::InvalidInput,
@@ -224,20 +422,20 @@ impl<E: PropertyAccess> Parser<E> {
             line_str.clear();
             reader.read_line(&mut line_str)?;
             let line = self.__read_header_line(&line_str);
+            if let (Some(ref mut layout), Ok(l)) = (&mut layout, &line) {
+                layout.push(l.clone(), &line_str);
+            }
 
             match line {
                 Err(e) => {
                     return parse_ascii_rethrow(location, &line_str, e, "Couldn't parse line.")
                 }
-                Ok(Line::MagicNumber) => {
+                Ok(HeaderLine::MagicNumber) => {
                     return parse_ascii_error(location, &line_str, "Unexpected 'ply' found.")
                 }
-                Ok(Line::Format(ref t)) => {
-                    if header_form_ver.is_none() {
-                        header_form_ver = Some(*t);
-                    } else {
-                        let f = header_form_ver.unwrap();
-                        if f != *t {
+                Ok(HeaderLine::Format(encoding, version)) => {
+                    if let Some(f) = header_form_ver {
+                        if f != (encoding, version) {
                             return parse_ascii_error(
                                 location,
                                 &line_str,
@@ -246,16 +444,18 @@ impl<E: PropertyAccess> Parser<E> {
                                     \tEncoding: {:?}, Version: {:?}\n\
                                     previous definition:\n\
                                     \tEncoding: {:?}, Version: {:?}",
-                                    t.0, t.1, f.0, f.1
+                                    encoding, version, f.0, f.1
                                 ),
                             );
                         }
+                    } else {
+                        header_form_ver = Some((encoding, version));
                     }
                 }
-                Ok(Line::ObjInfo(ref o)) => header_obj_infos.push(o.clone()),
-                Ok(Line::Comment(ref c)) => header_comments.push(c.clone()),
-                Ok(Line::Element(ref e)) => header_elements.push(e.clone()),
-                Ok(Line::Property(p)) => {
+                Ok(HeaderLine::ObjInfo(ref o)) => header_obj_infos.push(o.clone()),
+                Ok(HeaderLine::Comment(ref c)) => header_comments.push(c.clone()),
+                Ok(HeaderLine::Element(ref e)) => header_elements.push(e.clone()),
+                Ok(HeaderLine::Property(p)) => {
                     if header_elements.is_empty() {
                         return parse_ascii_error(
                             location,
@@ -270,7 +470,7 @@ impl<E: PropertyAccess> Parser<E> {
                         header_elements.push(e);
                     }
                 }
-                Ok(Line::EndHeader) => {
+                Ok(HeaderLine::EndHeader) => {
                     location.next_line();
                     break 'readlines;
                 }
@@ -327,6 +527,111 @@ impl<E: PropertyAccess> Parser<E> {
         }
     }
 
+    /// Reads a single element. Encoding is chosen according to `header`.
+    ///
+    /// Together with `read_header()` this allows to stream a file element by element.
+    /// Make sure to read the elements in the order as they are defined in the header.
+    ///
+    /// Every call sets up the schema and buffers for `element_def` anew, use `element_reader()` to read many elements.
+    pub fn read_element<T: BufRead>(
+        &self,
+        reader: &mut T,
+        element_def: &ElementDef,
+        header: &Header,
+    ) -> Result<E> {
+        self.element_reader(element_def, header).read(reader)
+    }
+
+    /// Creates a reader for the elements of `element_def`, which keeps its buffers across elements.
+    pub fn element_reader<'a>(
+        &'a self,
+        element_def: &'a ElementDef,
+        header: &'a Header,
+    ) -> ElementReader<'a, E> {
+        ElementReader::new(self, element_def, header)
+    }
+
+    /// Reads the entire payload, but instead of collecting the elements, hands them one by one to `visitor`.
+    ///
+    /// Use this to process files larger than memory.
+    /// Reading stops at the first error returned by `visitor`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// # let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+    /// # let f = std::fs::File::open(path).unwrap();
+    /// let mut f = std::io::BufReader::new(f);
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let header = p.read_header(&mut f).unwrap();
+    ///
+    /// let mut count = 0;
+    /// p.read_payload_with(&mut f, &header, |_element_def, _element| {
+    ///     count += 1;
+    ///     Ok(())
+    /// }).unwrap();
+    /// assert_eq!(count, 14);
+    /// ```
+    pub fn read_payload_with<T: BufRead, F: FnMut(&ElementDef, E) -> Result<()>>(
+        &self,
+        reader: &mut T,
+        header: &Header,
+        mut visitor: F,
+    ) -> Result<()> {
+        let mut location = LocationTracker::new();
+        let mut line_str = Vec::new();
+        for element_def in &header.elements {
+            let mut state = ElementState::bind::<E>(element_def);
+            for _ in 0..element_def.count {
+                let element = self.__read_element(
+                    reader,
+                    &mut location,
+                    &mut line_str,
+                    element_def,
+                    &mut state,
+                    header,
+                )?;
+                visitor(element_def, element)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn __read_element<T: BufRead>(
+        &self,
+        reader: &mut T,
+        location: &mut LocationTracker,
+        line_str: &mut Vec<u8>,
+        element_def: &ElementDef,
+        state: &mut ElementState,
+        header: &Header,
+    ) -> Result<E> {
+        let element = match header.encoding {
+            Encoding::Ascii if self.ascii_mode == AsciiMode::Tokens => {
+                // positions continue within the line, no `next_line()`
+                return self.__read_ascii_token_element(
+                    reader,
+                    location,
+                    line_str,
+                    element_def,
+                    state,
+                );
+            }
+            Encoding::Ascii => {
+                self.__read_ascii_line(reader, location, line_str, element_def, state)?
+            }
+            Encoding::BinaryBigEndian => {
+                self.__read_binary_element::<T, BigEndian>(reader, element_def, state)?
+            }
+            Encoding::BinaryLittleEndian => {
+                self.__read_binary_element::<T, LittleEndian>(reader, element_def, state)?
+            }
+        };
+        location.next_line();
+        Ok(element)
+    }
+
     /// internal dispatcher based on the encoding
     fn __read_payload<T: BufRead>(
         &self,
@@ -353,10 +658,10 @@ impl<E: PropertyAccess> Parser<E> {
 // ////////////////////////////////////////////////////////////////
 // # Ascii
 // ////////////////////////////////////////////////////////////////
-use std::slice::Iter;
+use std::borrow::Cow;
 use std::str::FromStr;
 
-use crate::ply::{Property, PropertyType, ScalarType};
+use crate::ply::{PropertyDef, PropertyKey, PropertyType, ScalarType};
 use std::error;
 use std::marker;
 
@@ -369,93 +674,217 @@ impl<E: PropertyAccess> Parser<E> {
         element_def: &ElementDef,
     ) -> Result<Vec<E>> {
         let mut elems = Vec::<E>::with_capacity(element_def.count);
-        let mut line_str = String::with_capacity(16);
+        let mut line_str = Vec::with_capacity(16);
+        let mut state = ElementState::bind::<E>(element_def);
+        if self.ascii_mode == AsciiMode::Tokens {
+            for _ in 0..element_def.count {
+                elems.push(self.__read_ascii_token_element(
+                    reader,
+                    location,
+                    &mut line_str,
+                    element_def,
+                    &mut state,
+                )?);
+            }
+            return Ok(elems);
+        }
         for _ in 0..element_def.count {
-            line_str.clear();
-            reader.read_line(&mut line_str)?;
-            let element = match self.read_ascii_element(&line_str, element_def) {
-                Ok(e) => e,
-                Err(e) => return parse_ascii_rethrow(location, &line_str, e, "Couln't read element line.")
-            };
+            let element =
+                self.__read_ascii_line(reader, location, &mut line_str, element_def, &mut state)?;
             elems.push(element);
             location.next_line();
         }
         Ok(elems)
     }
 
+    /// Reads the element on the next line, in place if the buffer of `reader` holds the whole line.
+    fn __read_ascii_line<T: BufRead>(
+        &self,
+        reader: &mut T,
+        location: &LocationTracker,
+        line_str: &mut Vec<u8>,
+        element_def: &ElementDef,
+        state: &mut ElementState,
+    ) -> Result<E> {
+        let rethrow = |line: &[u8], e| {
+            parse_ascii_rethrow(
+                location,
+                &String::from_utf8_lossy(line),
+                e,
+                "Couln't read element line.",
+            )
+        };
+        let buf = reader.fill_buf()?;
+        if let Some(end) = find_newline(buf) {
+            let line = &buf[..=end];
+            let element = self
+                .__read_ascii_element(line, element_def, state)
+                .or_else(|e| rethrow(line, e));
+            reader.consume(end + 1);
+            return element;
+        }
+        line_str.clear();
+        reader.read_until(b'\n', line_str)?;
+        self.__read_ascii_element(line_str, element_def, state)
+            .or_else(|e| rethrow(line_str, e))
+    }
+
     /// Read a single element. Assume it is encoded in ascii.
     ///
     /// Make sure all elements are parsed in the order they are defined in the header.
+    /// Every call sets up the schema and buffers for `element_def` anew, see `element_reader()`.
     pub fn read_ascii_element(&self, line: &str, element_def: &ElementDef) -> Result<E> {
-        let elems = crate::parser::Parser::<E>::__fast_data_line_split(line);
+        let mut state = ElementState::bind::<E>(element_def);
+        self.__read_ascii_element(line.as_bytes(), element_def, &mut state)
+    }
 
-        let mut elem_it: Iter<&str> = elems.iter();
-        let mut vals = E::new();
-        for def in &element_def.properties {
-            let new_p: Property = self.__read_ascii_property(&mut elem_it, &def.data_type)?;
-            vals.set_property(&def.name, new_p);
+    /// Tokenizes the line in place, lists are read into the buffers of `state`.
+    pub(crate) fn __read_ascii_element(
+        &self,
+        line: &[u8],
+        element_def: &ElementDef,
+        state: &mut ElementState,
+    ) -> Result<E> {
+        let mut tokens = AsciiTokens::new(line);
+        let mut vals = state.new_element::<E>();
+        for (i, def) in element_def.properties.iter().enumerate() {
+            let key = PropertyKey::new(state.schema.as_ref(), i, def);
+            self.__read_ascii_property(
+                &mut tokens,
+                &def.data_type,
+                key,
+                &mut vals,
+                &mut state.lists,
+            )?;
+        }
+        Ok(vals)
+    }
+
+    /// Reads one element from a stream of whitespace separated tokens, which may span lines.
+    ///
+    /// Errors report the line and column of the property's first token.
+    fn __read_ascii_token_element<T: BufRead>(
+        &self,
+        reader: &mut T,
+        location: &mut LocationTracker,
+        tokens: &mut Vec<u8>,
+        element_def: &ElementDef,
+        state: &mut ElementState,
+    ) -> Result<E> {
+        let mut vals = state.new_element::<E>();
+        for (i, def) in element_def.properties.iter().enumerate() {
+            tokens.clear();
+            let position = match read_ascii_token(reader, location, tokens)? {
+                Some(p) => p,
+                None => return missing_property(location, def),
+            };
+            if let PropertyType::List(..) = def.data_type {
+                let count = list_count(tokens);
+                for n in 0..count {
+                    if read_ascii_token(reader, location, tokens)?.is_none() {
+                        return missing_list_entries(&position, tokens, def, count, n);
+                    }
+                }
+            }
+            self.__read_property_tokens(tokens, &position, i, def, &mut vals, state)?;
         }
         Ok(vals)
     }
 
-    fn __fast_data_line_split(line: &str) -> Vec<&str> {
-        line.split_ascii_whitespace().collect()
+    /// Parses the tokens of one property collected in `AsciiMode::Tokens`.
+    pub(crate) fn __read_property_tokens(
+        &self,
+        tokens: &[u8],
+        position: &LocationTracker,
+        index: usize,
+        def: &PropertyDef,
+        element: &mut E,
+        state: &mut ElementState,
+    ) -> Result<()> {
+        let key = PropertyKey::new(state.schema.as_ref(), index, def);
+        match self.__read_ascii_property(
+            &mut AsciiTokens::new(tokens),
+            &def.data_type,
+            key,
+            element,
+            &mut state.lists,
+        ) {
+            Ok(()) => Ok(()),
+            Err(e) => parse_ascii_rethrow(
+                position,
+                &String::from_utf8_lossy(tokens),
+                e,
+                &property_message(def),
+            ),
+        }
     }
 
     fn __read_ascii_property(
         &self,
-        elem_iter: &mut Iter<&str>,
+        elem_iter: &mut AsciiTokens,
         data_type: &PropertyType,
-    ) -> Result<Property> {
-        let s: &str = match elem_iter.next() {
-            None => {
-                return Err(io::Error::new(
-                    ErrorKind::InvalidInput,
-                    format!(
-                        "Expected element of type '{:?}', but found nothing.",
-                        data_type
-                    ),
-                ))
-            }
-            Some(x) => x,
-        };
-
-        let result = match *data_type {
+        key: PropertyKey,
+        element: &mut E,
+        lists: &mut ListBuffers,
+    ) -> Result<()> {
+        match *data_type {
             PropertyType::Scalar(ref scalar_type) => match *scalar_type {
-                ScalarType::Char => Property::Char(self.parse(s)?),
-                ScalarType::UChar => Property::UChar(self.parse(s)?),
-                ScalarType::Short => Property::Short(self.parse(s)?),
-                ScalarType::UShort => Property::UShort(self.parse(s)?),
-                ScalarType::Int => Property::Int(self.parse(s)?),
-                ScalarType::UInt => Property::UInt(self.parse(s)?),
-                ScalarType::Float => Property::Float(self.parse(s)?),
-                ScalarType::Double => Property::Double(self.parse(s)?),
+                ScalarType::Char => key.set_char(element, self.next_number(elem_iter, data_type)?),
+                ScalarType::UChar => {
+                    key.set_uchar(element, self.next_number(elem_iter, data_type)?)
+                }
+                ScalarType::Short => {
+                    key.set_short(element, self.next_number(elem_iter, data_type)?)
+                }
+                ScalarType::UShort => {
+                    key.set_ushort(element, self.next_number(elem_iter, data_type)?)
+                }
+                ScalarType::Int => key.set_int(element, self.next_number(elem_iter, data_type)?),
+                ScalarType::UInt => key.set_uint(element, self.next_number(elem_iter, data_type)?),
+                ScalarType::Float => key.set_float(element, self.next_float(elem_iter, data_type)?),
+                ScalarType::Double => {
+                    key.set_double(element, self.next_float(elem_iter, data_type)?)
+                }
             },
             PropertyType::List(_, ref scalar_type) => {
-                let count: usize = self.parse(s)?;
+                let count: usize = self.next_number(elem_iter, data_type)?;
                 match *scalar_type {
-                    ScalarType::Char => Property::ListChar(self.__read_ascii_list(elem_iter, count)?),
+                    ScalarType::Char => {
+                        self.__read_ascii_list(elem_iter, count, &mut lists.char)?;
+                        key.set_list_i8_from(element, &lists.char);
+                    }
                     ScalarType::UChar => {
-                        Property::ListUChar(self.__read_ascii_list(elem_iter, count)?)
+                        self.__read_ascii_list(elem_iter, count, &mut lists.uchar)?;
+                        key.set_list_u8_from(element, &lists.uchar);
                     }
                     ScalarType::Short => {
-                        Property::ListShort(self.__read_ascii_list(elem_iter, count)?)
+                        self.__read_ascii_list(elem_iter, count, &mut lists.short)?;
+                        key.set_list_i16_from(element, &lists.short);
                     }
                     ScalarType::UShort => {
-                        Property::ListUShort(self.__read_ascii_list(elem_iter, count)?)
+                        self.__read_ascii_list(elem_iter, count, &mut lists.ushort)?;
+                        key.set_list_u16_from(element, &lists.ushort);
+                    }
+                    ScalarType::Int => {
+                        self.__read_ascii_list(elem_iter, count, &mut lists.int)?;
+                        key.set_list_i32_from(element, &lists.int);
+                    }
+                    ScalarType::UInt => {
+                        self.__read_ascii_list(elem_iter, count, &mut lists.uint)?;
+                        key.set_list_u32_from(element, &lists.uint);
                     }
-                    ScalarType::Int => Property::ListInt(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::UInt => Property::ListUInt(self.__read_ascii_list(elem_iter, count)?),
                     ScalarType::Float => {
-                        Property::ListFloat(self.__read_ascii_list(elem_iter, count)?)
+                        self.__read_ascii_float_list(elem_iter, count, &mut lists.float)?;
+                        key.set_list_f32_from(element, &lists.float);
                     }
                     ScalarType::Double => {
-                        Property::ListDouble(self.__read_ascii_list(elem_iter, count)?)
+                        self.__read_ascii_float_list(elem_iter, count, &mut lists.double)?;
+                        key.set_list_f64_from(element, &lists.double);
                     }
                 }
             }
-        };
-        Ok(result)
+        }
+        Ok(())
     }
 
     fn parse<D: FromStr>(&self, s: &str) -> Result<D>
@@ -472,30 +901,306 @@ impl<E: PropertyAccess> Parser<E> {
         }
     }
 
-    fn __read_ascii_list<D: FromStr>(
+    /// Parses an integer, `parse()` reports the errors.
+    fn parse_number<D: AsciiNumber>(&self, s: &[u8]) -> Result<D>
+    where
+        <D as FromStr>::Err: error::Error + Send + Sync + 'static,
+    {
+        match D::parse_fast(s) {
+            Some(v) => Ok(v),
+            None => self.parse(&String::from_utf8_lossy(s)),
+        }
+    }
+
+    /// Parses a float, unusual spellings are handled according to `FloatSyntax`.
+    fn parse_float<D: AsciiNumber>(&self, s: &[u8]) -> Result<D>
+    where
+        <D as FromStr>::Err: error::Error + Send + Sync + 'static,
+    {
+        let v = match D::parse_fast(s) {
+            Some(v) => v,
+            None => self.parse(&self.float_token(&String::from_utf8_lossy(s))?)?,
+        };
+        self.check_finite(v, s)
+    }
+
+    /// Parses the next token as an integer, while scanning it if possible.
+    fn next_number<D: AsciiNumber>(
         &self,
-        elem_iter: &mut Iter<&str>,
+        tokens: &mut AsciiTokens,
+        data_type: &PropertyType,
+    ) -> Result<D>
+    where
+        <D as FromStr>::Err: error::Error + Send + Sync + 'static,
+    {
+        match tokens.next_number() {
+            Some(Ok(v)) => Ok(v),
+            Some(Err(s)) => self.parse_number(s),
+            None => Err(missing_value(data_type)),
+        }
+    }
+
+    /// Parses the next token as a float, while scanning it if possible.
+    fn next_float<D: AsciiNumber>(
+        &self,
+        tokens: &mut AsciiTokens,
+        data_type: &PropertyType,
+    ) -> Result<D>
+    where
+        <D as FromStr>::Err: error::Error + Send + Sync + 'static,
+    {
+        match tokens.next_number() {
+            Some(Ok(v)) => Ok(v),
+            Some(Err(s)) => self.parse_float(s),
+            None => Err(missing_value(data_type)),
+        }
+    }
+
+    /// Rejects `nan` and infinities for `FloatSyntax::Finite`, e.g. `1e39` as a `float`.
+    fn check_finite<D: AsciiNumber>(&self, v: D, s: &[u8]) -> Result<D> {
+        if self.float_syntax == FloatSyntax::Finite && !v.is_finite() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Expected a finite number, found '{}'.",
+                    String::from_utf8_lossy(s)
+                ),
+            ));
+        }
+        Ok(v)
+    }
+
+    fn __read_ascii_list<D: AsciiNumber>(
+        &self,
+        elem_iter: &mut AsciiTokens,
+        count: usize,
+        list: &mut Vec<D>,
+    ) -> Result<()>
+    where
+        <D as FromStr>::Err: error::Error + marker::Send + marker::Sync + 'static,
+    {
+        list.clear();
+        list.reserve(count.min(elem_iter.max_remaining()));
+        for i in 0..count {
+            let v = match elem_iter.next_number() {
+                Some(Ok(v)) => v,
+                Some(Err(s)) => match D::parse_fast(s) {
+                    Some(v) => v,
+                    None => String::from_utf8_lossy(s).parse().map_err(|err| {
+                        io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Couldn't parse element at index {}: {:?}", i, err),
+                        )
+                    })?,
+                },
+                None => break,
+            };
+            list.push(v);
+        }
+        Ok(())
+    }
+
+    fn __read_ascii_float_list<D: AsciiNumber>(
+        &self,
+        elem_iter: &mut AsciiTokens,
         count: usize,
-    ) -> Result<Vec<D>>
+        list: &mut Vec<D>,
+    ) -> Result<()>
     where
         <D as FromStr>::Err: error::Error + marker::Send + marker::Sync + 'static,
     {
-        let list: Result<Vec<D>> = elem_iter
-            .take(count)
-            .enumerate()
-            .map(|(i, s)| {
-                s.parse().map_err(|err| {
-                    io::Error::new(
+        list.clear();
+        list.reserve(count.min(elem_iter.max_remaining()));
+        for i in 0..count {
+            let s = match elem_iter.next_number() {
+                Some(Ok(v)) => {
+                    list.push(v);
+                    continue;
+                }
+                Some(Err(s)) => s,
+                None => break,
+            };
+            let v = match D::parse_fast(s) {
+                Some(v) => v,
+                None => {
+                    let s = String::from_utf8_lossy(s);
+                    self.float_token(&s)?.parse().map_err(|err| {
+                        io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Couldn't parse element at index {}: {:?}", i, err),
+                        )
+                    })?
+                }
+            };
+            list.push(self.check_finite(v, s)?);
+        }
+        Ok(())
+    }
+
+    /// Rewrites a float token into a spelling `str::parse()` accepts, according to `FloatSyntax`.
+    fn float_token<'a>(&self, s: &'a str) -> Result<Cow<'a, str>> {
+        match self.float_syntax {
+            FloatSyntax::Finite => {
+                let finite = s
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'+' | b'e' | b'E'));
+                if !finite {
+                    return Err(io::Error::new(
                         ErrorKind::InvalidInput,
-                        format!("Couldn't parse element at index {}: {:?}", i, err),
-                    )
-                })
-            })
-            .collect();
-        list
+                        format!("Expected a finite number, found '{}'.", s),
+                    ));
+                }
+                Ok(Cow::Borrowed(s))
+            }
+            FloatSyntax::Standard => Ok(Cow::Borrowed(s)),
+            FloatSyntax::Lenient => {
+                let (sign, rest) = match s.as_bytes().first() {
+                    Some(b'-') | Some(b'+') => s.split_at(1),
+                    _ => ("", s),
+                };
+                let special = if let Some(kind) = rest.strip_prefix("1.#") {
+                    let kind = kind.trim_end_matches(|c: char| c.is_ascii_digit());
+                    match kind.to_ascii_uppercase().as_str() {
+                        "INF" => "inf",
+                        "IND" | "QNAN" | "SNAN" => "nan",
+                        _ => return Ok(Cow::Borrowed(s)),
+                    }
+                } else if rest
+                    .get(..4)
+                    .is_some_and(|p| p.eq_ignore_ascii_case("nan("))
+                    && rest.ends_with(')')
+                {
+                    "nan"
+                } else {
+                    return Ok(Cow::Borrowed(s));
+                };
+                Ok(Cow::Owned(format!("{}{}", sign, special)))
+            }
+        }
+    }
+}
+
+fn missing_value(data_type: &PropertyType) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "Expected element of type '{:?}', but found nothing.",
+            data_type
+        ),
+    )
+}
+
+fn property_message(def: &PropertyDef) -> String {
+    format!("Couldn't read property '{}'.", def.name)
+}
+
+/// Error for a property that starts after the end of the input at `location`.
+pub(crate) fn missing_property<T>(location: &LocationTracker, def: &PropertyDef) -> Result<T> {
+    let position = LocationTracker {
+        column_index: location.column_index + 1,
+        ..*location
+    };
+    parse_ascii_rethrow(
+        &position,
+        "",
+        format!(
+            "Expected element of type '{:?}', but found end of input.",
+            def.data_type
+        ),
+        &property_message(def),
+    )
+}
+
+/// Error for a list property with only `found` of its `count` entries.
+pub(crate) fn missing_list_entries<T>(
+    position: &LocationTracker,
+    tokens: &[u8],
+    def: &PropertyDef,
+    count: usize,
+    found: usize,
+) -> Result<T> {
+    parse_ascii_rethrow(
+        position,
+        &String::from_utf8_lossy(tokens),
+        format!(
+            "Expected {} list entries, but found end of input after {}.",
+            count, found
+        ),
+        &property_message(def),
+    )
+}
+
+/// Number of list entries announced by the first token, zero if invalid.
+///
+/// An invalid count is reported by `__read_ascii_property()`.
+pub(crate) fn list_count(tokens: &[u8]) -> usize {
+    std::str::from_utf8(tokens)
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(0)
+}
+
+/// Appends the next whitespace separated token to `tokens`, returns its position, the column counted from 1.
+///
+/// Only whitespace before the token and the token itself are consumed,
+/// so the reader can be handed on after any element.
+fn read_ascii_token<T: BufRead>(
+    reader: &mut T,
+    location: &mut LocationTracker,
+    tokens: &mut Vec<u8>,
+) -> Result<Option<LocationTracker>> {
+    let mut position = None;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(position);
+        }
+        let (used, done) = scan_ascii_token(buf, location, tokens, &mut position);
+        reader.consume(used);
+        if done {
+            return Ok(position);
+        }
     }
 }
 
+/// Scans `buf` for the rest of a token started at `position`, or for a new one if `None`.
+///
+/// Returns the number of bytes used and whether the token ended within `buf`.
+pub(crate) fn scan_ascii_token(
+    buf: &[u8],
+    location: &mut LocationTracker,
+    tokens: &mut Vec<u8>,
+    position: &mut Option<LocationTracker>,
+) -> (usize, bool) {
+    let mut used = 0;
+    for &b in buf {
+        if b.is_ascii_whitespace() {
+            if position.is_some() {
+                return (used, true);
+            }
+            if b == b'\n' {
+                location.next_line();
+            } else {
+                location.column_index += 1;
+            }
+        } else {
+            if position.is_none() {
+                *position = Some(LocationTracker {
+                    column_index: location.column_index + 1,
+                    ..*location
+                });
+                if !tokens.is_empty() {
+                    tokens.push(b' ');
+                }
+            }
+            tokens.push(b);
+            location.column_index += 1;
+        }
+        used += 1;
+    }
+    (used, false)
+}
+
 // //////////////////////////////////////
 // # Binary
 // //////////////////////////////////////
@@ -564,23 +1269,43 @@ impl<E: PropertyAccess> Parser<E> {
         element_def: &ElementDef,
     ) -> Result<Vec<E>> {
         let mut elems = Vec::<E>::with_capacity(element_def.count);
+        let mut state = ElementState::bind::<E>(element_def);
         for _ in 0..element_def.count {
-            let element = self.read_binary_element::<T, B>(reader, element_def)?;
+            let element = self.__read_binary_element::<T, B>(reader, element_def, &mut state)?;
             elems.push(element);
             location.next_line();
         }
         Ok(elems)
     }
 
+    /// Reads a single binary element with byte order `B`.
+    ///
+    /// Every call sets up the schema and buffers for `element_def` anew, see `element_reader()`.
     pub fn read_binary_element<T: Read, B: ByteOrder>(
         &self,
         reader: &mut T,
         element_def: &ElementDef,
     ) -> Result<E> {
-        let mut raw_element = E::new();
-        for def in &element_def.properties {
-            let property = self.__read_binary_property::<T, B>(reader, &def.data_type)?;
-            raw_element.set_property(&def.name, property);
+        let mut state = ElementState::bind::<E>(element_def);
+        self.__read_binary_element::<T, B>(reader, element_def, &mut state)
+    }
+
+    pub(crate) fn __read_binary_element<T: Read, B: ByteOrder>(
+        &self,
+        reader: &mut T,
+        element_def: &ElementDef,
+        state: &mut ElementState,
+    ) -> Result<E> {
+        let mut raw_element = state.new_element::<E>();
+        for (i, def) in element_def.properties.iter().enumerate() {
+            let key = PropertyKey::new(state.schema.as_ref(), i, def);
+            self.__read_binary_property::<T, B>(
+                reader,
+                &def.data_type,
+                key,
+                &mut raw_element,
+                &mut state.lists,
+            )?;
         }
         Ok(raw_element)
     }
@@ -589,17 +1314,20 @@ impl<E: PropertyAccess> Parser<E> {
         &self,
         reader: &mut T,
         data_type: &PropertyType,
-    ) -> Result<Property> {
-        let result = match *data_type {
+        key: PropertyKey,
+        element: &mut E,
+        lists: &mut ListBuffers,
+    ) -> Result<()> {
+        match *data_type {
             PropertyType::Scalar(scalar_type) => match scalar_type {
-                ScalarType::Char => Property::Char(reader.read_i8()?),
-                ScalarType::UChar => Property::UChar(reader.read_u8()?),
-                ScalarType::Short => Property::Short(reader.read_i16::<B>()?),
-                ScalarType::UShort => Property::UShort(reader.read_u16::<B>()?),
-                ScalarType::Int => Property::Int(reader.read_i32::<B>()?),
-                ScalarType::UInt => Property::UInt(reader.read_u32::<B>()?),
-                ScalarType::Float => Property::Float(reader.read_f32::<B>()?),
-                ScalarType::Double => Property::Double(reader.read_f64::<B>()?),
+                ScalarType::Char => key.set_char(element, reader.read_i8()?),
+                ScalarType::UChar => key.set_uchar(element, reader.read_u8()?),
+                ScalarType::Short => key.set_short(element, reader.read_i16::<B>()?),
+                ScalarType::UShort => key.set_ushort(element, reader.read_u16::<B>()?),
+                ScalarType::Int => key.set_int(element, reader.read_i32::<B>()?),
+                ScalarType::UInt => key.set_uint(element, reader.read_u32::<B>()?),
+                ScalarType::Float => key.set_float(element, reader.read_f32::<B>()?),
+                ScalarType::Double => key.set_double(element, reader.read_f64::<B>()?),
             },
             PropertyType::List(ref index_type, ref property_type) => {
                 let count: usize =
@@ -621,57 +1349,64 @@ impl<E: PropertyAccess> Parser<E> {
                     };
                 match *property_type {
                     ScalarType::Char => {
-                        Property::ListChar(self.__read_binary_list(reader, T::read_i8, count)?)
+                        self.__read_binary_list(reader, T::read_i8, count, &mut lists.char)?;
+                        key.set_list_i8_from(element, &lists.char);
                     }
                     ScalarType::UChar => {
-                        Property::ListUChar(self.__read_binary_list(reader, T::read_u8, count)?)
+                        self.__read_binary_list(reader, T::read_u8, count, &mut lists.uchar)?;
+                        key.set_list_u8_from(element, &lists.uchar);
+                    }
+                    ScalarType::Short => {
+                        self.__read_binary_list(reader, T::read_i16::<B>, count, &mut lists.short)?;
+                        key.set_list_i16_from(element, &lists.short);
+                    }
+                    ScalarType::UShort => {
+                        self.__read_binary_list(
+                            reader,
+                            T::read_u16::<B>,
+                            count,
+                            &mut lists.ushort,
+                        )?;
+                        key.set_list_u16_from(element, &lists.ushort);
                     }
-                    ScalarType::Short => Property::ListShort(self.__read_binary_list(
-                        reader,
-                        T::read_i16::<B>,
-                        count,
-                    )?),
-                    ScalarType::UShort => Property::ListUShort(self.__read_binary_list(
-                        reader,
-                        T::read_u16::<B>,
-                        count,
-                    )?),
                     ScalarType::Int => {
-                        Property::ListInt(self.__read_binary_list(reader, T::read_i32::<B>, count)?)
+                        self.__read_binary_list(reader, T::read_i32::<B>, count, &mut lists.int)?;
+                        key.set_list_i32_from(element, &lists.int);
+                    }
+                    ScalarType::UInt => {
+                        self.__read_binary_list(reader, T::read_u32::<B>, count, &mut lists.uint)?;
+                        key.set_list_u32_from(element, &lists.uint);
+                    }
+                    ScalarType::Float => {
+                        self.__read_binary_list(reader, T::read_f32::<B>, count, &mut lists.float)?;
+                        key.set_list_f32_from(element, &lists.float);
+                    }
+                    ScalarType::Double => {
+                        self.__read_binary_list(
+                            reader,
+                            T::read_f64::<B>,
+                            count,
+                            &mut lists.double,
+                        )?;
+                        key.set_list_f64_from(element, &lists.double);
                     }
-                    ScalarType::UInt => Property::ListUInt(self.__read_binary_list(
-                        reader,
-                        T::read_u32::<B>,
-                        count,
-                    )?),
-                    ScalarType::Float => Property::ListFloat(self.__read_binary_list(
-                        reader,
-                        T::read_f32::<B>,
-                        count,
-                    )?),
-                    ScalarType::Double => Property::ListDouble(self.__read_binary_list(
-                        reader,
-                        T::read_f64::<B>,
-                        count,
-                    )?),
                 }
             }
-        };
-        Ok(result)
+        }
+        Ok(())
     }
 
-    fn __read_binary_list<T: Read, D: FromStr>(
+    /// Reads `count` entries into `list`, which is cleared first.
+    fn __read_binary_list<T: Read, D>(
         &self,
         reader: &mut T,
         read_from: fn(&mut T) -> Result<D>,
         count: usize,
-    ) -> Result<Vec<D>>
-    where
-        <D as FromStr>::Err: error::Error + marker::Send + marker::Sync + 'static,
-    {
-        (0..count)
-        .map(|i| {
-            read_from(reader).map_err(|e| {
+        list: &mut Vec<D>,
+    ) -> Result<()> {
+        list.clear();
+        for i in 0..count {
+            let v = read_from(reader).map_err(|e| {
                 io::Error::new(
                     ErrorKind::InvalidInput,
                     format!(
@@ -679,19 +1414,22 @@ impl<E: PropertyAccess> Parser<E> {
                         i, e
                     ),
                 )
-            })
-        })
-        .collect()
+            })?;
+            list.push(v);
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::grammar as g;
-    use super::Line;
-    use crate::parser::Parser;
+    use super::AsciiTokens;
+    use super::ListBuffers;
+    use crate::parser::{FloatSyntax, Parser};
     use crate::ply::{
-        DefaultElement, ElementDef, Encoding, PropertyDef, PropertyType, ScalarType, Version,
+        DefaultElement, ElementDef, Encoding, HeaderLine, Property, PropertyDef, PropertyKey,
+        PropertyType, ScalarType, Version,
     };
     macro_rules! assert_ok {
         ($e:expr) => {
@@ -871,10 +1609,10 @@ mod tests {
     }
     #[test]
     fn line_ok() {
-        assert_ok!(g::line("ply "), Line::MagicNumber);
+        assert_ok!(g::line("ply "), HeaderLine::MagicNumber);
         assert_ok!(
             g::line("format ascii 1.0 "),
-            Line::Format((Encoding::Ascii, Version { major: 1, minor: 0 }))
+            HeaderLine::Format(Encoding::Ascii, Version { major: 1, minor: 0 })
         );
         assert_ok!(g::line("comment a very nice comment "));
         assert_ok!(g::line("element vertex 8 "));
@@ -885,9 +1623,9 @@ mod tests {
     }
     #[test]
     fn line_breaks_ok() {
-        assert_ok!(g::line("ply \n"), Line::MagicNumber); // Unix, Mac OS X
-        assert_ok!(g::line("ply \r"), Line::MagicNumber); // Mac pre OS X
-        assert_ok!(g::line("ply \r\n"), Line::MagicNumber); // Windows
+        assert_ok!(g::line("ply \n"), HeaderLine::MagicNumber); // Unix, Mac OS X
+        assert_ok!(g::line("ply \r"), HeaderLine::MagicNumber); // Mac pre OS X
+        assert_ok!(g::line("ply \r\n"), HeaderLine::MagicNumber); // Windows
     }
     #[test]
     fn data_line_ok() {
@@ -897,11 +1635,73 @@ mod tests {
         );
         assert_ok!(g::data_line("034 8e3 8e-3"), vec!["034", "8e3", "8e-3"]);
         assert_ok!(g::data_line(""), Vec::<String>::new());
+        assert_ok!(
+            g::data_line(".5 5. 1E5 nan -NaN +inf Infinity 1.#INF00 -1.#IND nan(ind)"),
+            vec![
+                ".5", "5.", "1E5", "nan", "-NaN", "+inf", "Infinity", "1.#INF00", "-1.#IND",
+                "nan(ind)"
+            ]
+        );
+    }
+    fn read_property(
+        p: &Parser<DefaultElement>,
+        text: &str,
+        data_type: &PropertyType,
+    ) -> std::io::Result<Property> {
+        let mut element = DefaultElement::new();
+        let key = PropertyKey::Name("p");
+        p.__read_ascii_property(
+            &mut AsciiTokens::new(text.as_bytes()),
+            data_type,
+            key,
+            &mut element,
+            &mut ListBuffers::default(),
+        )?;
+        Ok(element.remove("p").unwrap())
+    }
+    #[test]
+    fn float_syntax_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let def = PropertyType::List(ScalarType::UChar, ScalarType::Float);
+        for line in [
+            ".5 5. 1E5 nan -NaN +inf Infinity 1.#INF00 -1.#IND nan(ind)",
+            "1.#QNAN -1.#INF",
+        ] {
+            // the reader accepts every token the grammar accepts
+            for t in g::data_line(line).unwrap() {
+                let v = read_property(&p, &format!("1 {}", t), &def);
+                assert!(v.is_ok(), "{}: {:?}", t, v);
+            }
+        }
+        let v = read_property(&p, "3 -1.#INF 1.#IND 5.", &def).unwrap();
+        match v {
+            Property::ListFloat(v) => {
+                assert_eq!(v[0], f32::NEG_INFINITY);
+                assert!(v[1].is_nan());
+                assert_eq!(v[2], 5.0);
+            }
+            _ => panic!("Unexpected property."),
+        }
+        let standard = Parser::<DefaultElement>::new().with_float_syntax(FloatSyntax::Standard);
+        let scalar = PropertyType::Scalar(ScalarType::Double);
+        assert!(read_property(&standard, "-inf", &scalar).is_ok());
+        assert!(read_property(&standard, "1.#INF", &scalar).is_err());
+        let finite = Parser::<DefaultElement>::new().with_float_syntax(FloatSyntax::Finite);
+        assert!(read_property(&finite, "-2.5e-3", &scalar).is_ok());
+        assert!(read_property(&finite, "nan", &scalar).is_err());
+        assert!(read_property(&finite, "2 1 inf", &def).is_err());
+        assert!(read_property(&finite, "1e308", &scalar).is_ok());
+        assert!(read_property(&finite, "1e309", &scalar).is_err());
+        assert!(read_property(&finite, "2 1 1e39", &def).is_err());
+        assert!(read_property(&standard, "1e39", &PropertyType::Scalar(ScalarType::Float)).is_ok());
     }
     #[test]
     fn data_line_err() {
         assert_err!(g::data_line("++3"));
         assert_err!(g::data_line("+-3"));
         assert_err!(g::data_line("five"));
+        assert_err!(g::data_line("1.#FOO"));
+        assert_err!(g::data_line("nanx"));
+        assert_err!(g::data_line("."));
     }
 }
@@ -1,17 +1,7 @@
 use crate::ply::{
-    Comment, ElementDef, Encoding, ObjInfo, PropertyDef, PropertyType, ScalarType, Version,
+    Comment, ElementDef, Encoding, HeaderLine, ObjInfo, PropertyDef, PropertyType, ScalarType,
+    Version,
 };
-#[derive(Debug, PartialEq, Clone)]
-pub enum Line {
-    MagicNumber,
-    Format((Encoding, Version)),
-    Comment(Comment),
-    ObjInfo(ObjInfo),
-    Element(ElementDef),
-    Property(PropertyDef),
-    EndHeader,
-}
-
 peg::parser! {pub grammar grammar() for str {
 
 /// Grammar for PLY header
@@ -104,20 +94,37 @@ pub rule property() -> PropertyDef
 pub rule end_header()
     = "end_header"
 
-pub rule line() -> Line
+pub rule line() -> HeaderLine
     = l:trimmed_line() space()? line_break()? { l }
 
-rule trimmed_line() -> Line
-    = magic_number() { Line::MagicNumber }
-    / end_header() { Line::EndHeader }
-    / v:format() { Line::Format(v) }
-    / v:obj_info() { Line::ObjInfo(v) }
-    / v:comment() { Line::Comment(v) }
-    / v:element() { Line::Element(v) }
-    / v:property() { Line::Property(v) }
+rule trimmed_line() -> HeaderLine
+    = magic_number() { HeaderLine::MagicNumber }
+    / end_header() { HeaderLine::EndHeader }
+    / v:format() { HeaderLine::Format(v.0, v.1) }
+    / v:obj_info() { HeaderLine::ObjInfo(v) }
+    / v:comment() { HeaderLine::Comment(v) }
+    / v:element() { HeaderLine::Element(v) }
+    / v:property() { HeaderLine::Property(v) }
 
+/// Numbers as the ascii reader accepts them with `FloatSyntax::Lenient`
 rule any_number() -> String
-    = s:$(['-'|'+']? ['0'..='9']+("."['0'..='9']+)?("e"['-'|'+']?['0'..='9']+)?) { s.to_string() }
+    = s:$(['-'|'+']? (msvc_special() / decimal() exponent()? / nan() / inf())) { s.to_string() }
+
+rule decimal()
+    = ['0'..='9']+ ("." ['0'..='9']*)?
+    / "." ['0'..='9']+
+
+rule exponent()
+    = ['e'|'E'] ['-'|'+']? ['0'..='9']+
+
+rule nan()
+    = ['n'|'N'] ['a'|'A'] ['n'|'N'] ("(" ['a'..='z'|'A'..='Z'|'0'..='9'|'_']* ")")?
+
+rule inf()
+    = ['i'|'I'] ['n'|'N'] ['f'|'F'] (['i'|'I'] ['n'|'N'] ['i'|'I'] ['t'|'T'] ['y'|'Y'])?
+
+rule msvc_special()
+    = "1.#" (['I'|'i'] ['N'|'n'] ['F'|'f'|'D'|'d'] / ['Q'|'q'|'S'|'s'] ['N'|'n'] ['A'|'a'] ['N'|'n']) ['0'..='9']*
 
 rule trimmed_data_line() -> Vec<String>
     = any_number() ** space()
@@ -0,0 +1,553 @@
+//! Comparison with float tolerances, for tests that survive an ascii round trip.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[macro_use] extern crate ply_rs;
+//! # use ply_rs::ply::{ApproxEq, DefaultElement, Property, ScalarType, Tolerance};
+//! # fn main() {
+//! let mut a = DefaultElement::new();
+//! a.insert("x".to_string(), Property::Float(0.1));
+//! let mut b = DefaultElement::new();
+//! b.insert("x".to_string(), Property::Float(0.1000001));
+//!
+//! assert!(a != b);
+//! assert!(a.approx_eq(&b, &Tolerance::new(1e-6)));
+//! assert_approx_eq!(a, b, Tolerance::new(0.0).with_scalar_type(ScalarType::Float, 1e-6));
+//! assert_approx_ne!(a, b, 1e-9);
+//! # }
+//! ```
+
+use super::{
+    DefaultElement, ElementDef, Header, Payload, Ply, Property, PropertyAccess, ScalarType,
+};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Decides when two values are close enough, and which parts of a `Ply` are compared.
+///
+/// The tolerance of a value is looked up in this order: per property, per `ScalarType`, `absolute`.
+/// Integers are compared with the same tolerances, a tolerance below `1` keeps them exact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tolerance {
+    /// Largest absolute difference of two equal values, unless overridden.
+    pub absolute: f64,
+    /// Overrides `absolute` for all properties of a scalar type, for lists the type of the entries.
+    pub per_scalar_type: Vec<(ScalarType, f64)>,
+    /// Overrides all other tolerances: element name (`None` for any element), property name and tolerance.
+    pub per_property: Vec<(Option<String>, String, f64)>,
+    /// Whether `NaN` is equal to `NaN`.
+    pub nan_equal: bool,
+    /// Whether headers are compared.
+    pub header: bool,
+    /// Whether payloads are compared.
+    pub payload: bool,
+}
+
+impl Tolerance {
+    /// Compares headers and payloads with the given absolute tolerance, `NaN` is equal to `NaN`.
+    pub fn new(absolute: f64) -> Self {
+        Tolerance {
+            absolute,
+            per_scalar_type: Vec::new(),
+            per_property: Vec::new(),
+            nan_equal: true,
+            header: true,
+            payload: true,
+        }
+    }
+
+    /// Uses `tolerance` for all values of `scalar_type`.
+    pub fn with_scalar_type(mut self, scalar_type: ScalarType, tolerance: f64) -> Self {
+        self.per_scalar_type.retain(|(s, _)| *s != scalar_type);
+        self.per_scalar_type.push((scalar_type, tolerance));
+        self
+    }
+
+    /// Uses `tolerance` for all values of `property` in elements named `element`.
+    pub fn with_property(mut self, element: &str, property: &str, tolerance: f64) -> Self {
+        self.per_property
+            .push((Some(element.to_string()), property.to_string(), tolerance));
+        self
+    }
+
+    /// Sets whether `NaN` is equal to `NaN`.
+    pub fn with_nan_equal(mut self, nan_equal: bool) -> Self {
+        self.nan_equal = nan_equal;
+        self
+    }
+
+    /// Only compares headers.
+    pub fn header_only(mut self) -> Self {
+        self.header = true;
+        self.payload = false;
+        self
+    }
+
+    /// Only compares payloads, element definitions are still used to read the elements.
+    pub fn payload_only(mut self) -> Self {
+        self.header = false;
+        self.payload = true;
+        self
+    }
+
+    /// Tolerance for a property of the given type.
+    pub fn tolerance_for(
+        &self,
+        element: Option<&str>,
+        property: &str,
+        scalar_type: ScalarType,
+    ) -> f64 {
+        let by_property = self
+            .per_property
+            .iter()
+            .rev()
+            .find(|(e, p, _)| p == property && e.as_deref().is_none_or(|e| Some(e) == element));
+        if let Some((_, _, t)) = by_property {
+            return *t;
+        }
+        self.per_scalar_type
+            .iter()
+            .find(|(s, _)| *s == scalar_type)
+            .map_or(self.absolute, |(_, t)| *t)
+    }
+
+    fn values_equal(&self, left: f64, right: f64, tolerance: f64) -> bool {
+        if left.is_nan() || right.is_nan() {
+            return self.nan_equal && left.is_nan() && right.is_nan();
+        }
+        left == right || (left - right).abs() <= tolerance
+    }
+
+    fn property_mismatch(
+        &self,
+        element: Option<&str>,
+        name: &str,
+        left: &Property,
+        right: &Property,
+    ) -> Option<String> {
+        let (l_type, r_type) = (left.property_type(), right.property_type());
+        if l_type != r_type {
+            return Some(format!("type {} vs {}", l_type, r_type));
+        }
+        let tolerance = self.tolerance_for(element, name, scalar_type_of(left));
+        if let (Some(l), Some(r)) = (left.as_f64(), right.as_f64()) {
+            if !self.values_equal(l, r, tolerance) {
+                return Some(format!("{} vs {}", l, r));
+            }
+            return None;
+        }
+        let l = left.list_as_f64().unwrap_or_default();
+        let r = right.list_as_f64().unwrap_or_default();
+        if l.len() != r.len() {
+            return Some(format!("list length {} vs {}", l.len(), r.len()));
+        }
+        for (i, (a, b)) in l.iter().zip(r.iter()).enumerate() {
+            if !self.values_equal(*a, *b, tolerance) {
+                return Some(format!("entry {}: {} vs {}", i, a, b));
+            }
+        }
+        None
+    }
+
+    /// Compares two sequences of elements described by `left_def` and `right_def`.
+    ///
+    /// This is the building block for element types other than `DefaultElement`.
+    pub fn elements_mismatch<L: PropertyAccess, R: PropertyAccess>(
+        &self,
+        left_def: &ElementDef,
+        left: &[L],
+        right_def: &ElementDef,
+        right: &[R],
+    ) -> Option<Mismatch> {
+        let element = Some(left_def.name.clone());
+        if left.len() != right.len() {
+            return Some(Mismatch {
+                element,
+                description: format!("{} vs {} elements", left.len(), right.len()),
+                ..Mismatch::default()
+            });
+        }
+        let l_schema = L::bind_schema(left_def);
+        let r_schema = R::bind_schema(right_def);
+        for (index, (l, r)) in left.iter().zip(right.iter()).enumerate() {
+            for (l_index, l_def) in left_def.properties.iter().enumerate() {
+                let mismatch = |description: String| Mismatch {
+                    element: element.clone(),
+                    index: Some(index),
+                    property: Some(l_def.name.clone()),
+                    description,
+                };
+                let r_index = match right_def
+                    .properties
+                    .iter()
+                    .position(|p| p.name == l_def.name)
+                {
+                    Some(r_index) => r_index,
+                    None => return Some(mismatch("missing on the right".to_string())),
+                };
+                let lp = Property::read_bound(l, left_def, l_schema.as_ref(), l_index);
+                let rp = Property::read_bound(r, right_def, r_schema.as_ref(), r_index);
+                let description = match (lp, rp) {
+                    (Some(lp), Some(rp)) => {
+                        self.property_mismatch(Some(&left_def.name), &l_def.name, &lp, &rp)
+                    }
+                    (None, None) => None,
+                    (None, Some(_)) => Some("missing on the left".to_string()),
+                    (Some(_), None) => Some("missing on the right".to_string()),
+                };
+                if let Some(d) = description {
+                    return Some(mismatch(d));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Default for Tolerance {
+    /// No tolerance, but `NaN` is equal to `NaN`.
+    fn default() -> Self {
+        Tolerance::new(0.0)
+    }
+}
+
+impl From<f64> for Tolerance {
+    fn from(absolute: f64) -> Self {
+        Tolerance::new(absolute)
+    }
+}
+
+fn scalar_type_of(p: &Property) -> ScalarType {
+    match p.property_type() {
+        super::PropertyType::Scalar(s) => s,
+        super::PropertyType::List(_, s) => s,
+    }
+}
+
+/// Location and description of the first difference found.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mismatch {
+    /// Name of the element type, if the difference is inside one.
+    pub element: Option<String>,
+    /// Position of the element, if the difference is inside one.
+    pub index: Option<usize>,
+    /// Name of the property, if the difference is inside one.
+    pub property: Option<String>,
+    /// What differs.
+    pub description: String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        if let Some(ref e) = self.element {
+            write!(f, "element `{}`", e)?;
+            if let Some(i) = self.index {
+                write!(f, "[{}]", i)?;
+            }
+            f.write_str(", ")?;
+        }
+        if let Some(ref p) = self.property {
+            write!(f, "property `{}`: ", p)?;
+        }
+        f.write_str(&self.description)
+    }
+}
+
+/// Equality within a `Tolerance`.
+pub trait ApproxEq<Rhs: ?Sized = Self> {
+    /// Returns the first difference beyond the tolerance, or `None` if there is none.
+    fn approx_mismatch(&self, other: &Rhs, tolerance: &Tolerance) -> Option<Mismatch>;
+
+    /// Returns `true` if all values are equal within the tolerance.
+    fn approx_eq(&self, other: &Rhs, tolerance: &Tolerance) -> bool {
+        self.approx_mismatch(other, tolerance).is_none()
+    }
+}
+
+impl ApproxEq for Property {
+    /// Uses the tolerance of the property's `ScalarType`.
+    fn approx_mismatch(&self, other: &Property, tolerance: &Tolerance) -> Option<Mismatch> {
+        let t = Tolerance {
+            per_property: Vec::new(),
+            ..tolerance.clone()
+        };
+        t.property_mismatch(None, "", self, other)
+            .map(|description| Mismatch {
+                description,
+                ..Mismatch::default()
+            })
+    }
+}
+
+impl ApproxEq for DefaultElement {
+    /// Compares all keys of both elements, per property tolerances match any element name.
+    fn approx_mismatch(&self, other: &DefaultElement, tolerance: &Tolerance) -> Option<Mismatch> {
+        for (k, l) in self {
+            let mismatch = |description: String| Mismatch {
+                property: Some(k.clone()),
+                description,
+                ..Mismatch::default()
+            };
+            match other.get(k) {
+                Some(r) => {
+                    if let Some(d) = tolerance.property_mismatch(None, k, l, r) {
+                        return Some(mismatch(d));
+                    }
+                }
+                None => return Some(mismatch("missing on the right".to_string())),
+            }
+        }
+        other
+            .keys()
+            .find(|k| !self.contains_key(*k))
+            .map(|k| Mismatch {
+                property: Some(k.clone()),
+                description: "missing on the left".to_string(),
+                ..Mismatch::default()
+            })
+    }
+}
+
+impl ApproxEq for [DefaultElement] {
+    fn approx_mismatch(&self, other: &[DefaultElement], tolerance: &Tolerance) -> Option<Mismatch> {
+        if self.len() != other.len() {
+            return Some(Mismatch {
+                description: format!("{} vs {} elements", self.len(), other.len()),
+                ..Mismatch::default()
+            });
+        }
+        self.iter()
+            .zip(other.iter())
+            .enumerate()
+            .find_map(|(i, (l, r))| {
+                l.approx_mismatch(r, tolerance).map(|m| Mismatch {
+                    index: Some(i),
+                    ..m
+                })
+            })
+    }
+}
+
+impl ApproxEq for Vec<DefaultElement> {
+    fn approx_mismatch(
+        &self,
+        other: &Vec<DefaultElement>,
+        tolerance: &Tolerance,
+    ) -> Option<Mismatch> {
+        self[..].approx_mismatch(&other[..], tolerance)
+    }
+}
+
+impl ApproxEq for Payload<DefaultElement> {
+    fn approx_mismatch(
+        &self,
+        other: &Payload<DefaultElement>,
+        tolerance: &Tolerance,
+    ) -> Option<Mismatch> {
+        for (k, l) in self {
+            let element = Some(k.clone());
+            let r = match other.get(k) {
+                Some(r) => r,
+                None => {
+                    return Some(Mismatch {
+                        element,
+                        description: "missing on the right".to_string(),
+                        ..Mismatch::default()
+                    })
+                }
+            };
+            let t = Tolerance {
+                per_property: tolerance
+                    .per_property
+                    .iter()
+                    .filter(|(e, _, _)| e.as_ref().is_none_or(|e| e == k))
+                    .map(|(_, p, t)| (None, p.clone(), *t))
+                    .collect(),
+                ..tolerance.clone()
+            };
+            if let Some(m) = l.approx_mismatch(r, &t) {
+                return Some(Mismatch { element, ..m });
+            }
+        }
+        other
+            .keys()
+            .find(|k| !self.contains_key(*k))
+            .map(|k| Mismatch {
+                element: Some(k.clone()),
+                description: "missing on the left".to_string(),
+                ..Mismatch::default()
+            })
+    }
+}
+
+fn header_mismatch(left: &Header, right: &Header) -> Option<Mismatch> {
+    let description = if left.encoding != right.encoding {
+        format!("encoding {} vs {}", left.encoding, right.encoding)
+    } else if left.version != right.version {
+        format!("version {:?} vs {:?}", left.version, right.version)
+    } else if left.comments != right.comments {
+        format!("comments {:?} vs {:?}", left.comments, right.comments)
+    } else if left.obj_infos != right.obj_infos {
+        format!("obj_info {:?} vs {:?}", left.obj_infos, right.obj_infos)
+    } else if left.elements != right.elements {
+        let l: Vec<String> = left.elements.iter().map(|e| format!("{:?}", e)).collect();
+        let r: Vec<String> = right.elements.iter().map(|e| format!("{:?}", e)).collect();
+        let (l, r) = l.iter().zip(r.iter()).find(|(l, r)| l != r).map_or_else(
+            || (format!("{} elements", l.len()), format!("{}", r.len())),
+            |(l, r)| (l.clone(), r.clone()),
+        );
+        format!("element definitions {} vs {}", l, r)
+    } else {
+        return None;
+    };
+    Some(Mismatch {
+        description: format!("header: {}", description),
+        ..Mismatch::default()
+    })
+}
+
+impl<E: PropertyAccess> ApproxEq for Ply<E> {
+    /// Compares the header, then the elements of every element type in the order of `self`.
+    fn approx_mismatch(&self, other: &Ply<E>, tolerance: &Tolerance) -> Option<Mismatch> {
+        if tolerance.header {
+            if let Some(m) = header_mismatch(&self.header, &other.header) {
+                return Some(m);
+            }
+        }
+        if !tolerance.payload {
+            return None;
+        }
+        for l_def in &self.header.elements {
+            let missing = |side: &str| Mismatch {
+                element: Some(l_def.name.clone()),
+                description: format!("missing on the {}", side),
+                ..Mismatch::default()
+            };
+            let r_def = match other.header.elements.iter().find(|e| e.name == l_def.name) {
+                Some(r_def) => r_def,
+                None => return Some(missing("right")),
+            };
+            let empty = Vec::new();
+            let l = self.payload.get(&l_def.name).unwrap_or(&empty);
+            let r = other.payload.get(&l_def.name).unwrap_or(&empty);
+            if let Some(m) = tolerance.elements_mismatch(l_def, l, r_def, r) {
+                return Some(m);
+            }
+        }
+        other
+            .header
+            .elements
+            .iter()
+            .find(|r| !self.header.elements.iter().any(|l| l.name == r.name))
+            .map(|r| Mismatch {
+                element: Some(r.name.clone()),
+                description: "missing on the left".to_string(),
+                ..Mismatch::default()
+            })
+    }
+}
+
+/// Asserts that two values are equal within a tolerance, see `ApproxEq`.
+///
+/// The tolerance is a `Tolerance` or an absolute `f64`.
+/// On failure, the first mismatch is printed with its element and property name.
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr, $tolerance:expr $(,)?) => {{
+        let tolerance: $crate::ply::Tolerance = ::std::convert::From::from($tolerance);
+        if let Some(m) = $crate::ply::ApproxEq::approx_mismatch(&$left, &$right, &tolerance) {
+            panic!("assertion failed: `left ≈ right`\n  first mismatch: {}", m);
+        }
+    }};
+}
+
+/// Asserts that two values differ beyond a tolerance, see `assert_approx_eq!`.
+#[macro_export]
+macro_rules! assert_approx_ne {
+    ($left:expr, $right:expr, $tolerance:expr $(,)?) => {{
+        let tolerance: $crate::ply::Tolerance = ::std::convert::From::from($tolerance);
+        if $crate::ply::ApproxEq::approx_eq(&$left, &$right, &tolerance) {
+            panic!("assertion failed: `left ≉ right`, the values are equal within the tolerance");
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn sample(x: f32, l: Vec<f64>) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut e = ElementDef::new("point");
+        e.properties.push(PropertyDef::new(
+            "x",
+            PropertyType::Scalar(ScalarType::Float),
+        ));
+        e.properties.push(PropertyDef::new(
+            "l",
+            PropertyType::List(ScalarType::UChar, ScalarType::Double),
+        ));
+        ply.header.elements.push(e);
+        let mut p = DefaultElement::new();
+        p.insert("x".to_string(), Property::Float(x));
+        p.insert("l".to_string(), Property::ListDouble(l));
+        ply.payload.insert("point".to_string(), vec![p]);
+        ply.make_consistent().unwrap();
+        ply
+    }
+
+    #[test]
+    fn property_ok() {
+        let t = Tolerance::new(0.01);
+        assert!(Property::Float(1.0).approx_eq(&Property::Float(1.005), &t));
+        assert!(!Property::Float(1.0).approx_eq(&Property::Float(1.05), &t));
+        assert!(!Property::Float(1.0).approx_eq(&Property::Double(1.0), &t));
+        assert!(Property::Float(f32::NAN).approx_eq(&Property::Float(f32::NAN), &t));
+        let t = t.with_nan_equal(false);
+        assert!(!Property::Float(f32::NAN).approx_eq(&Property::Float(f32::NAN), &t));
+        assert!(!Property::ListInt(vec![1]).approx_eq(&Property::ListInt(vec![1, 2]), &t));
+    }
+    #[test]
+    fn per_type_and_property_ok() {
+        let a = sample(1.0, vec![1.0, 2.0]);
+        let b = sample(1.1, vec![1.0, 2.001]);
+        let t = Tolerance::new(0.0).with_scalar_type(ScalarType::Double, 0.01);
+        let m = a.approx_mismatch(&b, &t).unwrap();
+        assert_eq!(m.element.as_deref(), Some("point"));
+        assert_eq!(m.index, Some(0));
+        assert_eq!(m.property.as_deref(), Some("x"));
+        assert!(m
+            .to_string()
+            .starts_with("element `point`[0], property `x`: 1 vs 1.1"));
+        let t = t.with_property("point", "x", 0.2);
+        assert!(a.approx_eq(&b, &t));
+        assert!(a.payload.approx_eq(&b.payload, &t));
+        assert!(!a.payload["point"].approx_eq(&b.payload["point"], &Tolerance::new(0.01)));
+    }
+    #[test]
+    fn header_and_payload_only_ok() {
+        let a = sample(1.0, vec![]);
+        let mut b = sample(2.0, vec![]);
+        b.header.comments.push("changed".to_string());
+        let m = a.approx_mismatch(&b, &Tolerance::new(0.0)).unwrap();
+        assert!(m.description.starts_with("header: comments"));
+        assert!(!a.approx_eq(&b, &Tolerance::new(10.0)));
+        assert!(a.approx_eq(&b, &Tolerance::new(10.0).payload_only()));
+        assert!(!a.approx_eq(&b, &Tolerance::new(10.0).header_only()));
+        b.header.comments.clear();
+        assert!(a.approx_eq(&b, &Tolerance::new(0.0).header_only()));
+    }
+    #[test]
+    fn macros_ok() {
+        let a = sample(1.0, vec![0.5]);
+        let b = sample(1.0000001, vec![0.5]);
+        assert_approx_eq!(a, b, 1e-6);
+        assert_approx_ne!(a, b, Tolerance::new(0.0));
+    }
+    #[test]
+    #[should_panic(expected = "element `point`[0], property `l`: entry 0: 0.5 vs 0.6")]
+    fn macro_message_ok() {
+        assert_approx_eq!(sample(1.0, vec![0.5]), sample(1.0, vec![0.6]), 0.01);
+    }
+}
@@ -0,0 +1,361 @@
+use super::{
+    DefaultElement, ElementDef, Property, PropertyAccess, PropertyDef, PropertySchema,
+    PropertyType, ScalarType,
+};
+use std::sync::Arc;
+
+/// Dynamic element that stores its values in a `Vec<Property>` and shares its `ElementDef`.
+///
+/// Values are stored in the order of the definition's properties, names are only kept once in the shared definition.
+/// All elements read for the same `ElementDef` share one `Arc`,
+/// which makes this the recommended choice for files whose layout isn't known at compile time.
+///
+/// Setting a property the definition doesn't know adds it to a copy of the definition owned by this element.
+///
+/// The parser and writer address values by index, see `PropertyAccess::bind_schema()`.
+/// Values of elements whose own `ElementDef` orders the properties differently than the header,
+/// e.g. converted from a `DefaultElement` without `from_default()`, are looked up by name instead.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// # use ply_rs::ply::{CompactElement, Property};
+/// # use std::sync::Arc;
+/// # let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+/// # let mut f = std::fs::File::open(path).unwrap();
+/// let p = parser::Parser::<CompactElement>::new();
+/// let ply = p.read_ply(&mut f).unwrap();
+///
+/// let vertices = &ply.payload["vertex"];
+/// assert_eq!(vertices[1].get("z"), Some(&Property::Float(1.0)));
+/// assert!(Arc::ptr_eq(vertices[0].element_def(), vertices[1].element_def()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactElement {
+    element_def: Arc<ElementDef>,
+    values: Vec<Property>,
+}
+
+macro_rules! get(
+    ($e:expr) => (match $e {None => return None, Some(x) => x})
+);
+
+/// Getters by name and by index for one variant each.
+macro_rules! getters {
+    ($($get:ident, $get_at:ident, $variant:ident -> $t:ty;)*) => {$(
+        fn $get(&self, key: &str) -> Option<$t> {
+            match *get!(self.get(key)) {
+                Property::$variant(x) => Some(x),
+                _ => None,
+            }
+        }
+        fn $get_at(&self, index: usize) -> Option<$t> {
+            match *get!(self.values.get(index)) {
+                Property::$variant(x) => Some(x),
+                _ => None,
+            }
+        }
+    )*};
+    ($(list $get:ident, $get_at:ident, $variant:ident -> $t:ty;)*) => {$(
+        fn $get(&self, key: &str) -> Option<&[$t]> {
+            match *get!(self.get(key)) {
+                Property::$variant(ref x) => Some(x),
+                _ => None,
+            }
+        }
+        fn $get_at(&self, index: usize) -> Option<&[$t]> {
+            match *get!(self.values.get(index)) {
+                Property::$variant(ref x) => Some(x),
+                _ => None,
+            }
+        }
+    )*};
+}
+
+impl CompactElement {
+    /// Creates an empty element sharing `element_def`.
+    pub fn with_element_def(element_def: Arc<ElementDef>) -> Self {
+        let values = Vec::with_capacity(element_def.properties.len());
+        CompactElement {
+            element_def,
+            values,
+        }
+    }
+    /// Converts `element`, taking the values in the order of `element_def`.
+    ///
+    /// Properties missing in `element` are set to zero, properties `element_def` doesn't know are appended.
+    pub fn from_default(element: DefaultElement, element_def: &Arc<ElementDef>) -> Self {
+        let mut compact = CompactElement::with_element_def(element_def.clone());
+        let mut element = element;
+        for def in &element_def.properties {
+            let value = element
+                .remove(&def.name)
+                .unwrap_or_else(|| zero(&def.data_type));
+            compact.set_property(&def.name, value);
+        }
+        for (name, value) in element {
+            compact.set_property(&name, value);
+        }
+        compact
+    }
+    /// The definition the values follow.
+    pub fn element_def(&self) -> &Arc<ElementDef> {
+        &self.element_def
+    }
+    /// Values in the order of `element_def().properties`.
+    pub fn values(&self) -> &[Property] {
+        &self.values
+    }
+    /// Value of the property called `name`.
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        let index = get!(self.index_of(name));
+        self.values.get(index)
+    }
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.element_def
+            .properties
+            .iter()
+            .position(|p| p.name == name)
+    }
+}
+
+/// Zero of the declared type, fills gaps left by setting properties out of order.
+fn zero(data_type: &PropertyType) -> Property {
+    match *data_type {
+        PropertyType::Scalar(ScalarType::Char) => Property::Char(0),
+        PropertyType::Scalar(ScalarType::UChar) => Property::UChar(0),
+        PropertyType::Scalar(ScalarType::Short) => Property::Short(0),
+        PropertyType::Scalar(ScalarType::UShort) => Property::UShort(0),
+        PropertyType::Scalar(ScalarType::Int) => Property::Int(0),
+        PropertyType::Scalar(ScalarType::UInt) => Property::UInt(0),
+        PropertyType::Scalar(ScalarType::Float) => Property::Float(0.0),
+        PropertyType::Scalar(ScalarType::Double) => Property::Double(0.0),
+        PropertyType::List(_, ScalarType::Char) => Property::ListChar(Vec::new()),
+        PropertyType::List(_, ScalarType::UChar) => Property::ListUChar(Vec::new()),
+        PropertyType::List(_, ScalarType::Short) => Property::ListShort(Vec::new()),
+        PropertyType::List(_, ScalarType::UShort) => Property::ListUShort(Vec::new()),
+        PropertyType::List(_, ScalarType::Int) => Property::ListInt(Vec::new()),
+        PropertyType::List(_, ScalarType::UInt) => Property::ListUInt(Vec::new()),
+        PropertyType::List(_, ScalarType::Float) => Property::ListFloat(Vec::new()),
+        PropertyType::List(_, ScalarType::Double) => Property::ListDouble(Vec::new()),
+    }
+}
+
+/// Keeps the names and order of the values.
+impl From<CompactElement> for DefaultElement {
+    fn from(element: CompactElement) -> Self {
+        let mut default = DefaultElement::new();
+        for (def, value) in element.element_def.properties.iter().zip(element.values) {
+            default.insert(def.name.clone(), value);
+        }
+        default
+    }
+}
+
+/// The element owns its definition, use `CompactElement::from_default()` to share one.
+impl From<DefaultElement> for CompactElement {
+    fn from(element: DefaultElement) -> Self {
+        let mut compact = CompactElement::new();
+        for (name, value) in element {
+            compact.set_property(&name, value);
+        }
+        compact
+    }
+}
+
+impl PropertyAccess for CompactElement {
+    fn new() -> Self {
+        CompactElement::with_element_def(Arc::new(ElementDef::new("")))
+    }
+    const SHARES_ELEMENT_DEF: bool = true;
+    fn new_shared(element_def: &Arc<ElementDef>) -> Self {
+        CompactElement::with_element_def(element_def.clone())
+    }
+    fn set_property(&mut self, property_name: &str, property: Property) {
+        // the parser sets the properties in order
+        let next = self.element_def.properties.get(self.values.len());
+        if next.is_some_and(|p| p.name == property_name) {
+            self.values.push(property);
+            return;
+        }
+        let index = match self.index_of(property_name) {
+            Some(index) => index,
+            None => {
+                let def = PropertyDef::new(property_name, property.property_type());
+                Arc::make_mut(&mut self.element_def).properties.push(def);
+                self.element_def.properties.len() - 1
+            }
+        };
+        while self.values.len() <= index {
+            let data_type = self.element_def.properties[self.values.len()].data_type;
+            self.values.push(zero(&data_type));
+        }
+        self.values[index] = property;
+    }
+    /// Properties are addressed by their position in the `ElementDef` the elements are read or written with.
+    fn bind_schema(element_def: &ElementDef) -> Option<PropertySchema> {
+        Some(PropertySchema {
+            indices: (0..element_def.properties.len()).map(Some).collect(),
+        })
+    }
+    /// True if the element's own definition has a property of the same name at `index`,
+    /// elements converted from a `DefaultElement` may follow another order than the header.
+    fn is_bound_at(&self, index: usize, def: &PropertyDef) -> bool {
+        self.element_def
+            .properties
+            .get(index)
+            .is_some_and(|p| p.name == def.name)
+    }
+    fn set_property_at(&mut self, index: usize, property: Property) {
+        if index == self.values.len() {
+            self.values.push(property);
+            return;
+        }
+        while self.values.len() < index {
+            let data_type = match self.element_def.properties.get(self.values.len()) {
+                Some(def) => def.data_type,
+                None => property.property_type(),
+            };
+            self.values.push(zero(&data_type));
+        }
+        match self.values.get_mut(index) {
+            Some(value) => *value = property,
+            None => self.values.push(property),
+        }
+    }
+    getters! {
+        get_char, get_char_at, Char -> i8;
+        get_uchar, get_uchar_at, UChar -> u8;
+        get_short, get_short_at, Short -> i16;
+        get_ushort, get_ushort_at, UShort -> u16;
+        get_int, get_int_at, Int -> i32;
+        get_uint, get_uint_at, UInt -> u32;
+        get_float, get_float_at, Float -> f32;
+        get_double, get_double_at, Double -> f64;
+    }
+    getters! {
+        list get_list_char, get_list_char_at, ListChar -> i8;
+        list get_list_uchar, get_list_uchar_at, ListUChar -> u8;
+        list get_list_short, get_list_short_at, ListShort -> i16;
+        list get_list_ushort, get_list_ushort_at, ListUShort -> u16;
+        list get_list_int, get_list_int_at, ListInt -> i32;
+        list get_list_uint, get_list_uint_at, ListUInt -> u32;
+        list get_list_float, get_list_float_at, ListFloat -> f32;
+        list get_list_double, get_list_double_at, ListDouble -> f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::sync::Arc;
+
+    fn point_def() -> Arc<ElementDef> {
+        let mut e = ElementDef::new("point");
+        e.properties.push(PropertyDef::new(
+            "x",
+            PropertyType::Scalar(ScalarType::Float),
+        ));
+        e.properties.push(PropertyDef::new(
+            "l",
+            PropertyType::List(ScalarType::UChar, ScalarType::Int),
+        ));
+        Arc::new(e)
+    }
+
+    #[test]
+    fn set_property_ok() {
+        let def = point_def();
+        let mut e = CompactElement::new_shared(&def);
+        e.set_property("l", Property::ListInt(vec![1, 2]));
+        e.set_property("c", Property::UChar(7));
+        e.set_property("x", Property::Float(0.5));
+        assert_eq!(
+            e.values(),
+            &[
+                Property::Float(0.5),
+                Property::ListInt(vec![1, 2]),
+                Property::UChar(7)
+            ]
+        );
+        assert_eq!(e.get_uchar("c"), Some(7));
+        assert_eq!(e.get_list_int("l"), Some(&[1, 2][..]));
+        assert_eq!(e.get_float("l"), None);
+        assert_eq!(e.get("y"), None);
+        // the unknown property was added to a copy
+        assert!(!Arc::ptr_eq(e.element_def(), &def));
+        assert_eq!(def.properties.len(), 2);
+    }
+    #[test]
+    fn index_access_ok() {
+        let def = point_def();
+        let schema = CompactElement::bind_schema(&def).unwrap();
+        assert_eq!(schema.indices, vec![Some(0), Some(1)]);
+        let mut e = CompactElement::new_shared(&def);
+        e.set_list_i32_from_at(1, &[4, 5]);
+        assert_eq!(e.values()[0], Property::Float(0.0));
+        e.set_float_at(0, 2.5);
+        assert_eq!(e.get_float_at(0), Some(2.5));
+        assert_eq!(e.get_list_int_at(1), Some(&[4, 5][..]));
+        assert_eq!(e.get_int_at(0), None);
+        assert_eq!(e.get_float_at(2), None);
+        assert_eq!(e.get_float("x"), Some(2.5));
+
+        let mut ply = Ply::<CompactElement>::new();
+        ply.header.encoding = Encoding::BinaryLittleEndian;
+        ply.header.elements.push((*def).clone());
+        ply.payload.insert("point".to_string(), vec![e.clone()]);
+        let mut buf = Vec::new();
+        crate::writer::Writer::new()
+            .write_ply(&mut buf, &mut ply)
+            .unwrap();
+        let read = crate::parser::Parser::<CompactElement>::new()
+            .read_ply(&mut buf.as_slice())
+            .unwrap();
+        assert_eq!(read.payload["point"][0].values(), e.values());
+    }
+    #[test]
+    fn write_reordered_ok() {
+        let mut d = DefaultElement::new();
+        d.insert("y".to_string(), Property::Int(2));
+        d.insert("x".to_string(), Property::Int(1));
+        let mut def = ElementDef::new("point");
+        for name in ["x", "y"] {
+            def.properties.push(PropertyDef::new(
+                name,
+                PropertyType::Scalar(ScalarType::Int),
+            ));
+        }
+        let mut ply = Ply::<CompactElement>::new();
+        ply.header.elements.push(def);
+        ply.payload
+            .insert("point".to_string(), vec![CompactElement::from(d)]);
+        let mut buf = Vec::new();
+        crate::writer::Writer::new()
+            .write_ply(&mut buf, &mut ply)
+            .unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.ends_with("end_header\n1 2 \n"), "{}", text);
+    }
+    #[test]
+    fn default_element_round_trip() {
+        let def = point_def();
+        let mut d = DefaultElement::new();
+        d.insert("l".to_string(), Property::ListInt(vec![3]));
+        d.insert("x".to_string(), Property::Float(1.5));
+        let e = CompactElement::from_default(d.clone(), &def);
+        assert!(Arc::ptr_eq(e.element_def(), &def));
+        assert_eq!(e.values()[0], Property::Float(1.5));
+
+        let back = DefaultElement::from(e);
+        assert_eq!(back.keys().collect::<Vec<_>>(), vec!["x", "l"]);
+        assert_eq!(back["l"], d["l"]);
+        assert_eq!(DefaultElement::from(CompactElement::from(d.clone())), d);
+
+        let mut partial = DefaultElement::new();
+        partial.insert("l".to_string(), Property::ListInt(vec![3]));
+        let e = CompactElement::from_default(partial, &def);
+        assert_eq!(e.get("x"), Some(&Property::Float(0.0)));
+    }
+}
@@ -9,8 +9,10 @@ use super::PropertyAccess;
 /// they define types very dinamically.
 /// To achieve this flexibility in rust, this alias to a HashMap is provided.
 ///
-/// If you need a more compact representation or faster access,
-/// you might want to define your own structures and implement the `PropertyAccess` trait.
+/// Every element stores its own keys, so reading allocates a key and a map entry for every value,
+/// and lists are copied into a `Vec` of their own.
+/// `CompactElement` shares the keys and is the recommended choice for large files.
+/// If you need faster access, you might want to define your own structures and implement the `PropertyAccess` trait.
 pub type DefaultElement = KeyMap<Property>;
 macro_rules! get(
     ($e:expr) => (match $e {None => return None, Some(x) => x})
@@ -19,8 +21,14 @@ impl PropertyAccess for DefaultElement {
     fn new() -> Self {
         DefaultElement::new()
     }
+    /// Allocates the key only if it isn't set yet, a new element owns a copy of every key.
     fn set_property(&mut self, key: &str, property: Property) {
-        self.insert(key.to_string(), property);
+        match self.get_mut(key) {
+            Some(value) => *value = property,
+            None => {
+                self.insert(key.to_string(), property);
+            }
+        }
     }
     fn get_char(&self, key: &str) -> Option<i8> {
         match *get!(self.get(key)) {
@@ -0,0 +1,576 @@
+//! Semantic comparison of two PLY files, independent of their encoding.
+
+use super::{
+    ElementDef, Header, Ply, Property, PropertyAccess, PropertySchema, PropertyType,
+    ValueStatistics,
+};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Controls when two values count as equal and how much detail a `Diff` keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffOptions {
+    /// Values closer than this are equal.
+    pub absolute_tolerance: f64,
+    /// Values closer than this fraction of the larger magnitude are equal.
+    pub relative_tolerance: f64,
+    /// Number of differing rows kept per element type.
+    pub max_rows: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            absolute_tolerance: 0.0,
+            relative_tolerance: 0.0,
+            max_rows: 10,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Returns `true` if `left` and `right` are equal within the tolerances.
+    ///
+    /// Two `NaN` values are equal.
+    pub fn is_equal(&self, left: f64, right: f64) -> bool {
+        if left.is_nan() || right.is_nan() {
+            return left.is_nan() && right.is_nan();
+        }
+        if left == right {
+            return true;
+        }
+        let d = (left - right).abs();
+        d <= self.absolute_tolerance || d <= self.relative_tolerance * left.abs().max(right.abs())
+    }
+}
+
+/// A difference between the headers of two files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderDifference {
+    /// The comments differ.
+    Comments(Vec<String>, Vec<String>),
+    /// The obj_info lines differ.
+    ObjInfos(Vec<String>, Vec<String>),
+    /// An element type exists only in the left file.
+    ElementOnlyLeft(String),
+    /// An element type exists only in the right file.
+    ElementOnlyRight(String),
+    /// The number of elements differs: element name, left count, right count.
+    Count(String, usize, usize),
+    /// A property exists only in the left file: element name, property name.
+    PropertyOnlyLeft(String, String),
+    /// A property exists only in the right file: element name, property name.
+    PropertyOnlyRight(String, String),
+    /// A property is declared with different types: element name, property name, left type, right type.
+    PropertyType(String, String, PropertyType, PropertyType),
+}
+
+impl Display for HeaderDifference {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            HeaderDifference::Comments(ref l, ref r) => write!(f, "comments: {:?} vs {:?}", l, r),
+            HeaderDifference::ObjInfos(ref l, ref r) => write!(f, "obj_info: {:?} vs {:?}", l, r),
+            HeaderDifference::ElementOnlyLeft(ref e) => write!(f, "element {}: only left", e),
+            HeaderDifference::ElementOnlyRight(ref e) => write!(f, "element {}: only right", e),
+            HeaderDifference::Count(ref e, l, r) => {
+                write!(f, "element {}: {} vs {} elements", e, l, r)
+            }
+            HeaderDifference::PropertyOnlyLeft(ref e, ref p) => {
+                write!(f, "property {}.{}: only left", e, p)
+            }
+            HeaderDifference::PropertyOnlyRight(ref e, ref p) => {
+                write!(f, "property {}.{}: only right", e, p)
+            }
+            HeaderDifference::PropertyType(ref e, ref p, ref l, ref r) => {
+                write!(f, "property {}.{}: {} vs {}", e, p, l, r)
+            }
+        }
+    }
+}
+
+/// Left and right value of a property in a differing row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueDifference {
+    /// Name of the property.
+    pub property: String,
+    /// Value in the left file, `None` if the element didn't provide it.
+    pub left: Option<Property>,
+    /// Value in the right file, `None` if the element didn't provide it.
+    pub right: Option<Property>,
+}
+
+/// A row, i.e. a single element, with at least one differing property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowDifference {
+    /// Position of the element within its element type.
+    pub index: usize,
+    /// Only the differing properties.
+    pub values: Vec<ValueDifference>,
+}
+
+/// Comparison of one property over all rows present in both files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyDiff {
+    /// Name of the property.
+    pub name: String,
+    /// Number of rows compared.
+    pub compared: u64,
+    /// Number of rows where the values differ beyond the tolerances.
+    pub differing: u64,
+    /// Absolute differences of all compared values, for lists of every entry.
+    ///
+    /// Lists of different length add an infinite difference, a single `NaN` value a `NaN` difference.
+    pub differences: ValueStatistics,
+}
+
+/// Comparison of the values of one element type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementDiff {
+    /// Name of the element.
+    pub name: String,
+    /// Number of rows compared, the smaller of both counts.
+    pub compared: u64,
+    /// Number of rows with at least one differing property.
+    pub differing: u64,
+    /// One entry per property declared in both files, in the order of the left file.
+    pub properties: Vec<PropertyDiff>,
+    /// The first differing rows, at most `DiffOptions::max_rows`.
+    pub rows: Vec<RowDifference>,
+}
+
+impl ElementDiff {
+    /// Looks up the comparison of a property by name.
+    pub fn property(&self, name: &str) -> Option<&PropertyDiff> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+/// Result of comparing two files, see `Parser::read_diff()` and `Ply::diff()`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diff {
+    /// Differences in the headers, including element counts.
+    pub header: Vec<HeaderDifference>,
+    /// One entry per element type declared in both files, in the order of the left file.
+    pub elements: Vec<ElementDiff>,
+}
+
+impl Diff {
+    /// Returns `true` if no differences were found.
+    pub fn is_equal(&self) -> bool {
+        self.header.is_empty() && self.elements.iter().all(|e| e.differing == 0)
+    }
+
+    /// Looks up the comparison of an element by name.
+    pub fn element(&self, name: &str) -> Option<&ElementDiff> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+}
+
+fn format_value(v: &Option<Property>) -> String {
+    let v = match *v {
+        Some(ref v) => v,
+        None => return "-".to_string(),
+    };
+    match v.as_f64() {
+        Some(s) => s.to_string(),
+        None => {
+            let l: Vec<String> = v
+                .list_as_f64()
+                .unwrap_or_default()
+                .iter()
+                .map(|x| x.to_string())
+                .collect();
+            format!("[{}]", l.join(" "))
+        }
+    }
+}
+
+/// Lists header differences, followed by a summary and the first differing rows of every element type.
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        for h in &self.header {
+            writeln!(f, "{}", h)?;
+        }
+        for e in self.elements.iter().filter(|e| e.differing > 0) {
+            writeln!(
+                f,
+                "element {}: {} of {} rows differ",
+                e.name, e.differing, e.compared
+            )?;
+            for p in e.properties.iter().filter(|p| p.differing > 0) {
+                let max = p.differences.max.map_or("-".to_string(), |m| m.to_string());
+                let mean = p
+                    .differences
+                    .mean()
+                    .map_or("-".to_string(), |m| m.to_string());
+                writeln!(
+                    f,
+                    "  {}: {} rows differ, max difference {}, mean difference {}",
+                    p.name, p.differing, max, mean
+                )?;
+            }
+            for r in &e.rows {
+                let values: Vec<String> = r
+                    .values
+                    .iter()
+                    .map(|v| {
+                        format!(
+                            "{} {} vs {}",
+                            v.property,
+                            format_value(&v.left),
+                            format_value(&v.right)
+                        )
+                    })
+                    .collect();
+                writeln!(f, "  row {}: {}", r.index, values.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Properties declared in both files.
+#[derive(Debug, Clone)]
+struct CommonElement {
+    diff: ElementDiff,
+    left: ElementDef,
+    right: ElementDef,
+    /// Indices of the properties both sides have, into `left` and `right`.
+    properties: Vec<(usize, usize)>,
+    /// Bound on the first call to `DiffAccumulator::add()`.
+    schemas: Option<(Option<PropertySchema>, Option<PropertySchema>)>,
+}
+
+/// Collects a `Diff` one pair of elements at a time.
+///
+/// Use it together with `Parser::read_element()` to compare files without keeping the payloads in memory,
+/// see `Parser::read_diff()`.
+#[derive(Debug, Clone)]
+pub struct DiffAccumulator {
+    options: DiffOptions,
+    header: Vec<HeaderDifference>,
+    elements: Vec<CommonElement>,
+}
+
+impl DiffAccumulator {
+    /// Compares the headers and prepares a comparison for every element type declared in both.
+    pub fn new(left: &Header, right: &Header, options: &DiffOptions) -> Self {
+        let mut header = Vec::new();
+        if left.comments != right.comments {
+            header.push(HeaderDifference::Comments(
+                left.comments.clone(),
+                right.comments.clone(),
+            ));
+        }
+        if left.obj_infos != right.obj_infos {
+            header.push(HeaderDifference::ObjInfos(
+                left.obj_infos.clone(),
+                right.obj_infos.clone(),
+            ));
+        }
+        let mut elements = Vec::new();
+        for l in &left.elements {
+            let r = match right.elements.iter().find(|r| r.name == l.name) {
+                Some(r) => r,
+                None => {
+                    header.push(HeaderDifference::ElementOnlyLeft(l.name.clone()));
+                    continue;
+                }
+            };
+            if l.count != r.count {
+                header.push(HeaderDifference::Count(l.name.clone(), l.count, r.count));
+            }
+            elements.push(Self::compare_definitions(l, r, &mut header));
+        }
+        for r in &right.elements {
+            if !left.elements.iter().any(|l| l.name == r.name) {
+                header.push(HeaderDifference::ElementOnlyRight(r.name.clone()));
+            }
+        }
+        DiffAccumulator {
+            options: *options,
+            header,
+            elements,
+        }
+    }
+
+    fn compare_definitions(
+        left: &ElementDef,
+        right: &ElementDef,
+        header: &mut Vec<HeaderDifference>,
+    ) -> CommonElement {
+        let mut properties = Vec::new();
+        for (l_index, l) in left.properties.iter().enumerate() {
+            match right.properties.iter().position(|r| r.name == l.name) {
+                Some(r_index) => {
+                    let r = &right.properties[r_index];
+                    if l.data_type != r.data_type {
+                        header.push(HeaderDifference::PropertyType(
+                            left.name.clone(),
+                            l.name.clone(),
+                            l.data_type,
+                            r.data_type,
+                        ));
+                    }
+                    properties.push((l_index, r_index));
+                }
+                None => header.push(HeaderDifference::PropertyOnlyLeft(
+                    left.name.clone(),
+                    l.name.clone(),
+                )),
+            }
+        }
+        for r in &right.properties {
+            if !left.properties.iter().any(|l| l.name == r.name) {
+                header.push(HeaderDifference::PropertyOnlyRight(
+                    left.name.clone(),
+                    r.name.clone(),
+                ));
+            }
+        }
+        CommonElement {
+            diff: ElementDiff {
+                name: left.name.clone(),
+                compared: 0,
+                differing: 0,
+                properties: properties
+                    .iter()
+                    .map(|(l, _)| PropertyDiff {
+                        name: left.properties[*l].name.clone(),
+                        compared: 0,
+                        differing: 0,
+                        differences: ValueStatistics::new(),
+                    })
+                    .collect(),
+                rows: Vec::new(),
+            },
+            left: left.clone(),
+            right: right.clone(),
+            properties,
+            schemas: None,
+        }
+    }
+
+    /// Compares the `index`th element of type `element_name` of both files.
+    ///
+    /// Elements of a type not declared in both headers are ignored.
+    pub fn add<L: PropertyAccess, R: PropertyAccess>(
+        &mut self,
+        element_name: &str,
+        index: usize,
+        left: &L,
+        right: &R,
+    ) {
+        let options = self.options;
+        let common = match self
+            .elements
+            .iter_mut()
+            .find(|e| e.diff.name == element_name)
+        {
+            Some(e) => e,
+            None => return,
+        };
+        let diff = &mut common.diff;
+        diff.compared += 1;
+        let mut values = Vec::new();
+        let (l_schema, r_schema) = common
+            .schemas
+            .get_or_insert_with(|| (L::bind_schema(&common.left), R::bind_schema(&common.right)));
+        for (&(l_index, r_index), p_diff) in
+            common.properties.iter().zip(diff.properties.iter_mut())
+        {
+            let l = Property::read_bound(left, &common.left, l_schema.as_ref(), l_index);
+            let r = Property::read_bound(right, &common.right, r_schema.as_ref(), r_index);
+            p_diff.compared += 1;
+            let equal = match (&l, &r) {
+                (Some(l), Some(r)) => compare(l, r, &options, &mut p_diff.differences),
+                (None, None) => true,
+                _ => false,
+            };
+            if !equal {
+                p_diff.differing += 1;
+                values.push(ValueDifference {
+                    property: p_diff.name.clone(),
+                    left: l,
+                    right: r,
+                });
+            }
+        }
+        if !values.is_empty() {
+            diff.differing += 1;
+            if diff.rows.len() < options.max_rows {
+                diff.rows.push(RowDifference { index, values });
+            }
+        }
+    }
+
+    /// Returns the collected differences.
+    pub fn finish(self) -> Diff {
+        Diff {
+            header: self.header,
+            elements: self.elements.into_iter().map(|e| e.diff).collect(),
+        }
+    }
+}
+
+/// Adds the absolute differences to `differences`, returns `true` if all values are equal.
+fn compare(
+    left: &Property,
+    right: &Property,
+    options: &DiffOptions,
+    differences: &mut ValueStatistics,
+) -> bool {
+    let (l, r) = match (left.as_f64(), right.as_f64()) {
+        (Some(l), Some(r)) => (vec![l], vec![r]),
+        _ => (
+            left.list_as_f64()
+                .unwrap_or_else(|| left.as_f64().into_iter().collect()),
+            right
+                .list_as_f64()
+                .unwrap_or_else(|| right.as_f64().into_iter().collect()),
+        ),
+    };
+    let mut equal = true;
+    for (a, b) in l.iter().zip(r.iter()) {
+        if a.is_nan() && b.is_nan() {
+            differences.add(0.0);
+        } else {
+            differences.add((a - b).abs());
+        }
+        equal &= options.is_equal(*a, *b);
+    }
+    if l.len() != r.len() {
+        differences.add(f64::INFINITY);
+        equal = false;
+    }
+    equal
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Compares header and payload with `other`.
+    ///
+    /// Element types are compared in the order of `self`.
+    pub fn diff<F: PropertyAccess>(&self, other: &Ply<F>, options: &DiffOptions) -> Diff {
+        let mut acc = DiffAccumulator::new(&self.header, &other.header, options);
+        for element_def in &self.header.elements {
+            let (left, right) = match (
+                self.payload.get(&element_def.name),
+                other.payload.get(&element_def.name),
+            ) {
+                (Some(l), Some(r)) => (l, r),
+                _ => continue,
+            };
+            for (i, (l, r)) in left.iter().zip(right.iter()).enumerate() {
+                acc.add(&element_def.name, i, l, r);
+            }
+        }
+        acc.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn sample(values: &[(f32, Vec<i32>)]) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut e = ElementDef::new("point");
+        e.properties.push(PropertyDef::new(
+            "x",
+            PropertyType::Scalar(ScalarType::Float),
+        ));
+        e.properties.push(PropertyDef::new(
+            "l",
+            PropertyType::List(ScalarType::UChar, ScalarType::Int),
+        ));
+        ply.header.elements.push(e);
+        let points = values
+            .iter()
+            .map(|(x, l)| {
+                let mut p = DefaultElement::new();
+                p.insert("x".to_string(), Property::Float(*x));
+                p.insert("l".to_string(), Property::ListInt(l.clone()));
+                p
+            })
+            .collect();
+        ply.payload.insert("point".to_string(), points);
+        ply.make_consistent().unwrap();
+        ply
+    }
+
+    #[test]
+    fn equal_ok() {
+        let a = sample(&[(1.0, vec![1, 2]), (f32::NAN, vec![])]);
+        let diff = a.diff(&a, &DiffOptions::default());
+        assert!(diff.is_equal());
+        assert_eq!(diff.element("point").unwrap().compared, 2);
+        assert_eq!(diff.to_string(), "");
+    }
+    #[test]
+    fn values_ok() {
+        let a = sample(&[(1.0, vec![1, 2]), (2.0, vec![3]), (3.0, vec![])]);
+        let b = sample(&[(1.0, vec![1, 2]), (2.5, vec![3, 4]), (3.001, vec![])]);
+        let diff = a.diff(&b, &DiffOptions::default());
+        let e = diff.element("point").unwrap();
+        assert_eq!(e.differing, 2);
+        assert_eq!(e.property("x").unwrap().differing, 2);
+        assert_eq!(e.property("l").unwrap().differing, 1);
+        assert_eq!(e.property("l").unwrap().differences.inf_count, 1);
+        assert_eq!(e.rows[0].index, 1);
+        assert_eq!(e.rows[0].values.len(), 2);
+        assert!(diff
+            .to_string()
+            .contains("row 1: x 2 vs 2.5, l [3] vs [3 4]"));
+
+        let options = DiffOptions {
+            absolute_tolerance: 0.01,
+            max_rows: 0,
+            ..DiffOptions::default()
+        };
+        let diff = a.diff(&b, &options);
+        let e = diff.element("point").unwrap();
+        assert_eq!(e.property("x").unwrap().differing, 1);
+        assert!(e.rows.is_empty());
+    }
+    #[test]
+    fn relative_tolerance_ok() {
+        let options = DiffOptions {
+            relative_tolerance: 1e-3,
+            ..DiffOptions::default()
+        };
+        assert!(options.is_equal(1000.0, 1000.5));
+        assert!(!options.is_equal(1.0, 1.5));
+        assert!(options.is_equal(f64::NAN, f64::NAN));
+        assert!(!options.is_equal(0.0, f64::NAN));
+    }
+    #[test]
+    fn header_ok() {
+        let a = sample(&[(1.0, vec![])]);
+        let mut b = sample(&[(1.0, vec![]), (2.0, vec![])]);
+        b.header.comments.push("changed".to_string());
+        b.header.elements[0].properties[1].data_type =
+            PropertyType::List(ScalarType::UChar, ScalarType::UInt);
+        b.header.elements[0].properties.push(PropertyDef::new(
+            "y",
+            PropertyType::Scalar(ScalarType::Float),
+        ));
+        b.header.elements.push(ElementDef::new("edge"));
+        let diff = a.diff(&b, &DiffOptions::default());
+        assert_eq!(
+            diff.header,
+            vec![
+                HeaderDifference::Comments(vec![], vec!["changed".to_string()]),
+                HeaderDifference::Count("point".to_string(), 1, 2),
+                HeaderDifference::PropertyType(
+                    "point".to_string(),
+                    "l".to_string(),
+                    PropertyType::List(ScalarType::UChar, ScalarType::Int),
+                    PropertyType::List(ScalarType::UChar, ScalarType::UInt),
+                ),
+                HeaderDifference::PropertyOnlyRight("point".to_string(), "y".to_string()),
+                HeaderDifference::ElementOnlyRight("edge".to_string()),
+            ]
+        );
+        assert!(!diff.is_equal());
+    }
+}
@@ -0,0 +1,268 @@
+//! Content hash of a PLY file that doesn't depend on its encoding.
+//!
+//! The hash covers, in this order:
+//!
+//! - optionally the comments and obj_info lines,
+//! - every element definition: name, count and its property names and types,
+//! - every value in little endian binary form of its declared type, lists prefixed by their length.
+//!
+//! Type names are hashed by their meaning, so `float32` and `float` result in the same fingerprint.
+//! All `NaN` values are hashed alike. The hash function is 128 bit FNV-1a.
+
+use super::{
+    ElementDef, Header, Ply, Property, PropertyAccess, PropertyType, ScalarType, SchemaCache,
+};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Controls which header lines are part of a fingerprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FingerprintOptions {
+    /// Whether comments are hashed.
+    pub comments: bool,
+    /// Whether obj_info lines are hashed.
+    pub obj_infos: bool,
+}
+
+/// 128 bit content hash, displayed as 32 hexadecimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u128);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+const FNV_OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+fn scalar_code(s: ScalarType) -> u8 {
+    match s {
+        ScalarType::Char => 1,
+        ScalarType::UChar => 2,
+        ScalarType::Short => 3,
+        ScalarType::UShort => 4,
+        ScalarType::Int => 5,
+        ScalarType::UInt => 6,
+        ScalarType::Float => 7,
+        ScalarType::Double => 8,
+    }
+}
+
+/// Computes a `Fingerprint` one element at a time.
+///
+/// Use it together with `Parser::read_payload_with()` to hash files without keeping the payload in memory,
+/// see `Parser::read_fingerprint()`.
+#[derive(Debug, Clone)]
+pub struct FingerprintAccumulator {
+    hash: u128,
+    schemas: SchemaCache,
+}
+
+impl FingerprintAccumulator {
+    /// Hashes the parts of `header` selected by `options` and all element definitions.
+    pub fn new(header: &Header, options: &FingerprintOptions) -> Self {
+        let mut acc = FingerprintAccumulator {
+            hash: FNV_OFFSET,
+            schemas: SchemaCache::default(),
+        };
+        if options.comments {
+            acc.write(b"c");
+            acc.write_strings(&header.comments);
+        }
+        if options.obj_infos {
+            acc.write(b"o");
+            acc.write_strings(&header.obj_infos);
+        }
+        acc.write_len(header.elements.len());
+        for e in &header.elements {
+            acc.write(b"e");
+            acc.write_str(&e.name);
+            acc.write_len(e.count);
+            acc.write_len(e.properties.len());
+            for p in &e.properties {
+                acc.write(b"p");
+                acc.write_str(&p.name);
+                match p.data_type {
+                    PropertyType::Scalar(s) => acc.write(&[0, scalar_code(s)]),
+                    PropertyType::List(i, s) => acc.write(&[1, scalar_code(i), scalar_code(s)]),
+                }
+            }
+        }
+        acc
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.hash ^= *b as u128;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_len(s.len());
+        self.write(s.as_bytes());
+    }
+
+    fn write_strings(&mut self, strings: &[String]) {
+        self.write_len(strings.len());
+        for s in strings {
+            self.write_str(s);
+        }
+    }
+
+    fn write_f32(&mut self, v: f32) {
+        let v = if v.is_nan() { f32::NAN } else { v };
+        self.write(&v.to_le_bytes());
+    }
+
+    fn write_f64(&mut self, v: f64) {
+        let v = if v.is_nan() { f64::NAN } else { v };
+        self.write(&v.to_le_bytes());
+    }
+
+    fn write_list<V: Copy>(&mut self, list: &[V], write: fn(&mut Self, V)) {
+        self.write_len(list.len());
+        for v in list {
+            write(self, *v);
+        }
+    }
+
+    fn write_property(&mut self, p: &Property) {
+        match *p {
+            Property::Char(v) => self.write(&v.to_le_bytes()),
+            Property::UChar(v) => self.write(&v.to_le_bytes()),
+            Property::Short(v) => self.write(&v.to_le_bytes()),
+            Property::UShort(v) => self.write(&v.to_le_bytes()),
+            Property::Int(v) => self.write(&v.to_le_bytes()),
+            Property::UInt(v) => self.write(&v.to_le_bytes()),
+            Property::Float(v) => self.write_f32(v),
+            Property::Double(v) => self.write_f64(v),
+            Property::ListChar(ref l) => self.write_list(l, |s, v| s.write(&v.to_le_bytes())),
+            Property::ListUChar(ref l) => self.write_list(l, |s, v| s.write(&[v])),
+            Property::ListShort(ref l) => self.write_list(l, |s, v| s.write(&v.to_le_bytes())),
+            Property::ListUShort(ref l) => self.write_list(l, |s, v| s.write(&v.to_le_bytes())),
+            Property::ListInt(ref l) => self.write_list(l, |s, v| s.write(&v.to_le_bytes())),
+            Property::ListUInt(ref l) => self.write_list(l, |s, v| s.write(&v.to_le_bytes())),
+            Property::ListFloat(ref l) => self.write_list(l, Self::write_f32),
+            Property::ListDouble(ref l) => self.write_list(l, Self::write_f64),
+        }
+    }
+
+    /// Hashes the values of a single element, in the order of `element_def`.
+    ///
+    /// Elements have to be added in the order of the payload.
+    pub fn add<E: PropertyAccess>(&mut self, element_def: &ElementDef, element: &E) {
+        // taken out while hashing, `write()` borrows `self`
+        let mut schemas = std::mem::take(&mut self.schemas);
+        let schema = schemas.get::<E>(element_def);
+        for index in 0..element_def.properties.len() {
+            match Property::read_bound(element, element_def, schema, index) {
+                Some(p) => {
+                    self.write(&[1]);
+                    self.write_property(&p);
+                }
+                None => self.write(&[0]),
+            }
+        }
+        self.schemas = schemas;
+    }
+
+    /// Returns the fingerprint of everything hashed so far.
+    pub fn finish(self) -> Fingerprint {
+        Fingerprint(self.hash)
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Computes a fingerprint that is the same for all encodings of this content.
+    ///
+    /// Element types are hashed in the order of the header.
+    pub fn fingerprint(&self, options: &FingerprintOptions) -> Fingerprint {
+        let mut acc = FingerprintAccumulator::new(&self.header, options);
+        for element_def in &self.header.elements {
+            if let Some(elements) = self.payload.get(&element_def.name) {
+                for e in elements {
+                    acc.add(element_def, e);
+                }
+            }
+        }
+        acc.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::parser::Parser;
+    use crate::writer::Writer;
+
+    fn house() -> Ply<DefaultElement> {
+        let mut f = std::fs::File::open("example_plys/house_ok_ascii.ply").unwrap();
+        Parser::<DefaultElement>::new().read_ply(&mut f).unwrap()
+    }
+
+    #[test]
+    fn encodings_ok() {
+        let options = FingerprintOptions::default();
+        let mut ply = house();
+        let expected = ply.fingerprint(&options);
+        for encoding in [
+            Encoding::Ascii,
+            Encoding::BinaryBigEndian,
+            Encoding::BinaryLittleEndian,
+        ] {
+            ply.header.encoding = encoding;
+            let mut buf = Vec::new();
+            Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+            let (_, f) = Parser::<DefaultElement>::new()
+                .read_fingerprint(&mut buf.as_slice(), &options)
+                .unwrap();
+            assert_eq!(f, expected, "{}", encoding);
+        }
+    }
+    #[test]
+    fn type_aliases_ok() {
+        let a = "ply\nformat ascii 1.0\nelement point 1\nproperty float x\nproperty list uchar int l\nend_header\n1.5 2 3 4\n";
+        let b = "ply\nformat ascii 1.0\nelement point 1\nproperty float32 x\nproperty list uint8 int32 l\nend_header\n1.5 2 3 4\n";
+        let p = Parser::<DefaultElement>::new();
+        let options = FingerprintOptions::default();
+        let (_, a) = p.read_fingerprint(&mut a.as_bytes(), &options).unwrap();
+        let (_, b) = p.read_fingerprint(&mut b.as_bytes(), &options).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.to_string().len(), 32);
+    }
+    #[test]
+    fn content_ok() {
+        let ply = house();
+        let options = FingerprintOptions::default();
+        let mut commented = house();
+        commented.header.comments.push("uploaded".to_string());
+        assert_eq!(ply.fingerprint(&options), commented.fingerprint(&options));
+        let with_comments = FingerprintOptions {
+            comments: true,
+            ..options
+        };
+        assert_ne!(
+            ply.fingerprint(&with_comments),
+            commented.fingerprint(&with_comments)
+        );
+
+        let mut changed = house();
+        changed.payload.get_mut("vertex").unwrap()[0].insert("x".to_string(), Property::Float(0.5));
+        assert_ne!(ply.fingerprint(&options), changed.fingerprint(&options));
+
+        let mut nan_a = house();
+        nan_a.payload.get_mut("vertex").unwrap()[0]
+            .insert("x".to_string(), Property::Float(f32::NAN));
+        let mut nan_b = house();
+        nan_b.payload.get_mut("vertex").unwrap()[0]
+            .insert("x".to_string(), Property::Float(-f32::NAN));
+        assert_eq!(nan_a.fingerprint(&options), nan_b.fingerprint(&options));
+    }
+}
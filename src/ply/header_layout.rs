@@ -0,0 +1,62 @@
+//! Original text and order of header lines.
+//!
+//! `Header` keeps comments, obj_infos and elements in separate lists and the writer emits them in a fixed order
+//! with canonical type names. A `HeaderLayout` records every line as it was read,
+//! so `Writer::write_header_with_layout()` can reproduce an unmodified header byte for byte.
+
+use super::{Comment, ElementDef, Encoding, ObjInfo, PropertyDef, Version};
+
+/// Meaning of a single header line, as returned by `Parser::read_header_line()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum HeaderLine {
+    /// The `ply` line.
+    MagicNumber,
+    /// The `format` line.
+    Format(Encoding, Version),
+    /// A `comment` line.
+    Comment(Comment),
+    /// An `obj_info` line.
+    ObjInfo(ObjInfo),
+    /// An `element` line, the definition has no properties.
+    Element(ElementDef),
+    /// A `property` line, belongs to the preceding element.
+    Property(PropertyDef),
+    /// The `end_header` line.
+    EndHeader,
+}
+
+/// A header line together with its exact text, including the line break.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LayoutLine {
+    /// The parsed line.
+    pub line: HeaderLine,
+    /// The line as read, e.g. with extra spaces or `\r\n`.
+    pub text: String,
+}
+
+/// All header lines of a file in their original order.
+///
+/// Obtained from `Parser::read_header_with_layout()`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct HeaderLayout {
+    /// From `ply` to `end_header`.
+    pub lines: Vec<LayoutLine>,
+}
+
+impl HeaderLayout {
+    /// Creates an empty layout.
+    pub fn new() -> Self {
+        HeaderLayout { lines: Vec::new() }
+    }
+    /// Appends a line with its original text.
+    pub fn push(&mut self, line: HeaderLine, text: &str) {
+        self.lines.push(LayoutLine {
+            line,
+            text: text.to_string(),
+        });
+    }
+    /// Concatenated text of all lines, i.e. the header as it was read.
+    pub fn text(&self) -> String {
+        self.lines.iter().map(|l| l.text.as_str()).collect()
+    }
+}
@@ -0,0 +1,338 @@
+//! Structured data stored in `comment` and `obj_info` lines.
+//!
+//! Many tools use these lines for `key value` or `key: value` pairs, for example
+//! `comment TextureFile tex.png` (MeshLab, CloudCompare), `obj_info num_cols 512` or `comment author: Greg Turk`.
+//! Blender writes `comment Created by Blender ...`, which is read with the key `Created by`.
+
+use super::Header;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Comment key MeshLab and CloudCompare use to reference texture images.
+pub const TEXTURE_FILE_KEY: &str = "TextureFile";
+
+/// Keys consisting of several words, matched at the start of a line.
+const PHRASE_KEYS: [&str; 2] = ["Created by", "Generated by"];
+
+/// Header line a metadata entry was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataSource {
+    /// Index into `Header::comments`.
+    Comment(usize),
+    /// Index into `Header::obj_infos`.
+    ObjInfo(usize),
+}
+
+/// How key and value are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataStyle {
+    /// `key value`
+    Space,
+    /// `key: value`
+    Colon,
+}
+
+/// A single `key value` pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataEntry {
+    /// Header line the entry was read from.
+    pub source: MetadataSource,
+    /// Separator between key and value, kept by `Display`.
+    pub style: MetadataStyle,
+    /// Text before the separator, e.g. `Created by` or `units`, compare with `has_key()`.
+    pub key: String,
+    /// Rest of the line, may be empty.
+    pub value: String,
+}
+
+impl MetadataEntry {
+    /// Splits a comment or obj_info line into key and value.
+    ///
+    /// Returns `None` for empty lines.
+    pub fn parse(source: MetadataSource, line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        let entry = |style, key: &str, value: &str| {
+            Some(MetadataEntry {
+                source,
+                style,
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            })
+        };
+        for phrase in PHRASE_KEYS {
+            if let Some(rest) = line.strip_prefix(phrase) {
+                if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                    return entry(MetadataStyle::Space, phrase, rest);
+                }
+            }
+        }
+        // "key: value", but not "C:\path" or "http://host"
+        if let Some((key, value)) = line.split_once(':') {
+            let key_ok = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == ' ');
+            if key_ok && (value.is_empty() || value.starts_with(char::is_whitespace)) {
+                return entry(MetadataStyle::Colon, key, value);
+            }
+        }
+        match line.split_once(char::is_whitespace) {
+            Some((key, value)) => entry(MetadataStyle::Space, key, value),
+            None => entry(MetadataStyle::Space, line, ""),
+        }
+    }
+
+    /// Whether `key` equals this entry's key, ignoring ascii case.
+    pub fn has_key(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+}
+
+/// Formats the entry as the text of a comment or obj_info line.
+///
+/// The colon is kept for empty values, so keys with whitespace parse back unchanged.
+impl Display for MetadataEntry {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match (self.style, self.value.is_empty()) {
+            (MetadataStyle::Space, true) => f.write_str(&self.key),
+            (MetadataStyle::Space, false) => write!(f, "{} {}", self.key, self.value),
+            (MetadataStyle::Colon, true) => write!(f, "{}:", self.key),
+            (MetadataStyle::Colon, false) => write!(f, "{}: {}", self.key, self.value),
+        }
+    }
+}
+
+/// All `key value` pairs of a header, in the order comments and then obj_infos appear.
+///
+/// Keys are compared ignoring ascii case and may occur several times.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Metadata {
+    /// Entries of all non-empty lines.
+    pub entries: Vec<MetadataEntry>,
+}
+
+impl Metadata {
+    /// Value of the first entry with `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.has_key(key))
+            .map(|e| e.value.as_str())
+    }
+    /// Values of all entries with `key`.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |e| e.has_key(key))
+            .map(|e| e.value.as_str())
+    }
+    /// Parses the value of the first entry with `key`, e.g. `obj_info num_cols 512`.
+    pub fn get_parsed<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|v| v.parse().ok())
+    }
+    /// Whether an entry with `key` exists, even with an empty value.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+    /// All entries in header order.
+    pub fn iter(&self) -> std::slice::Iter<'_, MetadataEntry> {
+        self.entries.iter()
+    }
+}
+
+/// Image referenced by a `comment TextureFile <path>` line.
+///
+/// Faces select a texture by its position in `Header::texture_files()`,
+/// usually through a `texnumber` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureFile {
+    /// Path as written in the header, usually relative to the PLY file.
+    pub path: String,
+}
+
+impl Header {
+    /// Parses all comments and obj_infos into `key value` pairs.
+    pub fn metadata(&self) -> Metadata {
+        let comments = self
+            .comments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| MetadataEntry::parse(MetadataSource::Comment(i), c));
+        let obj_infos = self
+            .obj_infos
+            .iter()
+            .enumerate()
+            .filter_map(|(i, o)| MetadataEntry::parse(MetadataSource::ObjInfo(i), o));
+        Metadata {
+            entries: comments.chain(obj_infos).collect(),
+        }
+    }
+
+    /// Texture images in the order faces reference them.
+    pub fn texture_files(&self) -> Vec<TextureFile> {
+        self.comments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| MetadataEntry::parse(MetadataSource::Comment(i), c))
+            .filter(|e| e.has_key(TEXTURE_FILE_KEY) && !e.value.is_empty())
+            .map(|e| TextureFile { path: e.value })
+            .collect()
+    }
+
+    /// Appends a `comment TextureFile <path>` line as written by MeshLab and CloudCompare.
+    ///
+    /// Returns the index faces use to reference the texture.
+    pub fn add_texture_file(&mut self, path: &str) -> usize {
+        let index = self.texture_files().len();
+        self.comments.push(format!("{} {}", TEXTURE_FILE_KEY, path));
+        index
+    }
+
+    /// Sets the value of `key`.
+    ///
+    /// The first line with `key` is rewritten in its original style and place, further ones are removed.
+    /// New keys are appended as `comment key value`, or `comment key: value` if the key contains whitespace.
+    /// New lines are comments, since MeshLab and CloudCompare only read `TextureFile` from comments.
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        let mut entries = self.metadata().entries;
+        entries.retain(|e| e.has_key(key));
+        let Some(first) = entries.first() else {
+            let phrase = PHRASE_KEYS.contains(&key);
+            let style = if key.contains(char::is_whitespace) && !phrase {
+                MetadataStyle::Colon
+            } else {
+                MetadataStyle::Space
+            };
+            let entry = MetadataEntry {
+                source: MetadataSource::Comment(self.comments.len()),
+                style,
+                key: key.to_string(),
+                value: value.to_string(),
+            };
+            self.comments.push(entry.to_string());
+            return;
+        };
+        let line = MetadataEntry {
+            value: value.to_string(),
+            ..first.clone()
+        }
+        .to_string();
+        match first.source {
+            MetadataSource::Comment(i) => self.comments[i] = line,
+            MetadataSource::ObjInfo(i) => self.obj_infos[i] = line,
+        }
+        self.remove_entries(&entries[1..]);
+    }
+
+    /// Removes all comments and obj_infos with `key`, returns how many were removed.
+    pub fn remove_metadata(&mut self, key: &str) -> usize {
+        let mut entries = self.metadata().entries;
+        entries.retain(|e| e.has_key(key));
+        self.remove_entries(&entries);
+        entries.len()
+    }
+
+    fn remove_entries(&mut self, entries: &[MetadataEntry]) {
+        // back to front, so indices stay valid
+        for e in entries.iter().rev() {
+            match e.source {
+                MetadataSource::Comment(i) => {
+                    self.comments.remove(i);
+                }
+                MetadataSource::ObjInfo(i) => {
+                    self.obj_infos.remove(i);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        let mut header = Header::new();
+        header.comments = vec![
+            "Created by Blender 3.6.0 - www.blender.org".to_string(),
+            "TextureFile wall.png".to_string(),
+            "author: Greg Turk".to_string(),
+            "texturefile floor.png".to_string(),
+            "saved to C:\\scans".to_string(),
+        ];
+        header.obj_infos = vec!["num_cols 512".to_string(), "is_mesh".to_string()];
+        header
+    }
+
+    #[test]
+    fn parse_ok() {
+        let m = header().metadata();
+        assert_eq!(m.entries.len(), 7);
+        assert_eq!(m.get("created by"), Some("Blender 3.6.0 - www.blender.org"));
+        assert_eq!(m.get("Author"), Some("Greg Turk"));
+        assert_eq!(m.get_parsed::<u32>("num_cols"), Some(512));
+        assert_eq!(m.get("saved"), Some("to C:\\scans"));
+        assert_eq!(m.get("is_mesh"), Some(""));
+        assert_eq!(m.get_all(TEXTURE_FILE_KEY).count(), 2);
+        assert!(!m.contains_key("url"));
+        let e = MetadataEntry::parse(MetadataSource::Comment(0), "url http://example.com").unwrap();
+        assert_eq!(
+            (e.key.as_str(), e.value.as_str()),
+            ("url", "http://example.com")
+        );
+    }
+    #[test]
+    fn texture_files_ok() {
+        let mut h = header();
+        let paths: Vec<String> = h.texture_files().into_iter().map(|t| t.path).collect();
+        assert_eq!(paths, vec!["wall.png", "floor.png"]);
+        assert_eq!(h.add_texture_file("roof.png"), 2);
+        assert_eq!(h.comments.last().unwrap(), "TextureFile roof.png");
+        assert_eq!(h.texture_files()[2].path, "roof.png");
+    }
+    #[test]
+    fn set_metadata_ok() {
+        let mut h = header();
+        h.set_metadata("author", "Marc Levoy");
+        assert_eq!(h.comments[2], "author: Marc Levoy");
+        h.set_metadata("num_cols", "256");
+        assert_eq!(h.obj_infos[0], "num_cols 256");
+        h.set_metadata("scanner", "Cyberware");
+        h.set_metadata("scan date", "1994");
+        h.set_metadata("Generated by", "ply-rs");
+        assert_eq!(
+            &h.comments[5..],
+            [
+                "scanner Cyberware",
+                "scan date: 1994",
+                "Generated by ply-rs"
+            ]
+        );
+        assert_eq!(h.metadata().get("generated by"), Some("ply-rs"));
+        assert_eq!(h.metadata().get("scan date"), Some("1994"));
+        h.set_metadata("scan date", "");
+        assert_eq!(h.comments[6], "scan date:");
+        assert_eq!(h.metadata().get("scan date"), Some(""));
+        h.set_metadata("operator", "");
+        assert_eq!(h.comments.last().unwrap(), "operator");
+        h.set_metadata("scanned by", "");
+        assert_eq!(h.comments.last().unwrap(), "scanned by:");
+        assert!(h.metadata().contains_key("scanned by"));
+        assert_eq!(h.remove_metadata("operator"), 1);
+        assert_eq!(h.remove_metadata("scanned by"), 1);
+
+        h.set_metadata("TextureFile", "all.png");
+        assert_eq!(
+            h.texture_files(),
+            vec![TextureFile {
+                path: "all.png".to_string()
+            }]
+        );
+        assert_eq!(h.remove_metadata("texturefile"), 1);
+        assert_eq!(h.remove_metadata("texturefile"), 0);
+        assert!(h.texture_files().is_empty());
+    }
+}
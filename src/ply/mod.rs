@@ -1,16 +1,37 @@
 //! Definitions used to model PLY files.
 
+mod approx;
+pub use self::approx::*;
+
+mod compact_element;
+pub use self::compact_element::*;
+
 mod consistency;
 pub use self::consistency::*;
 
+mod diff;
+pub use self::diff::*;
+
 mod default_element;
 pub use self::default_element::*;
 
+mod fingerprint;
+pub use self::fingerprint::*;
+
+mod header_layout;
+pub use self::header_layout::*;
+
 mod key_map;
 pub use self::key_map::*;
 
+mod metadata;
+pub use self::metadata::*;
+
 mod ply_data_structure;
 pub use self::ply_data_structure::*;
 
 mod property;
 pub use self::property::*;
+
+mod statistics;
+pub use self::statistics::*;
@@ -1,3 +1,8 @@
+use super::{ElementDef, PropertyDef};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
 /// Scalar type used to encode properties in the payload.
 ///
 /// For the translation to rust types, see individual documentation.
@@ -73,12 +78,78 @@ pub enum Property {
 /// If you know, that you only expect unsigned shorts, don't bother about implementing signed shorts or floats, it won't be called.
 ///
 /// The getters are named in congruence with `PropertyType` and `ScalarType`.
+///
+/// The parser calls the typed setters, e.g. `set_float()` or `set_list_u32_from()`.
+/// By default they wrap the value into a `Property` and call `set_property()`,
+/// override them to copy values straight into your own storage.
+///
+/// Types that know their properties up front can implement `bind_schema()`.
+/// Names are then resolved once per `ElementDef`, and the parser and writer call `set_property_at()` and the `get_*_at()` functions with indices.
 pub trait PropertyAccess {
     fn new() -> Self;
+    /// If `true`, the parser creates elements with `new_shared()`, see `CompactElement`.
+    const SHARES_ELEMENT_DEF: bool = false;
+    /// Creates an element for `element_def`, which is shared by all elements read for the same definition.
+    fn new_shared(_element_def: &Arc<ElementDef>) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new()
+    }
     fn set_property(&mut self, _property_name: &str, _property: Property) {
         // By default, do nothing
         // Sombody might only want to write, no point in bothering him/her with setter implementations.
     }
+    // Typed setters, by default wrapped into a `Property`.
+    // The slices passed to list setters are only valid during the call.
+    fn set_char(&mut self, property_name: &str, value: i8) {
+        self.set_property(property_name, Property::Char(value));
+    }
+    fn set_uchar(&mut self, property_name: &str, value: u8) {
+        self.set_property(property_name, Property::UChar(value));
+    }
+    fn set_short(&mut self, property_name: &str, value: i16) {
+        self.set_property(property_name, Property::Short(value));
+    }
+    fn set_ushort(&mut self, property_name: &str, value: u16) {
+        self.set_property(property_name, Property::UShort(value));
+    }
+    fn set_int(&mut self, property_name: &str, value: i32) {
+        self.set_property(property_name, Property::Int(value));
+    }
+    fn set_uint(&mut self, property_name: &str, value: u32) {
+        self.set_property(property_name, Property::UInt(value));
+    }
+    fn set_float(&mut self, property_name: &str, value: f32) {
+        self.set_property(property_name, Property::Float(value));
+    }
+    fn set_double(&mut self, property_name: &str, value: f64) {
+        self.set_property(property_name, Property::Double(value));
+    }
+    fn set_list_i8_from(&mut self, property_name: &str, values: &[i8]) {
+        self.set_property(property_name, Property::ListChar(values.to_vec()));
+    }
+    fn set_list_u8_from(&mut self, property_name: &str, values: &[u8]) {
+        self.set_property(property_name, Property::ListUChar(values.to_vec()));
+    }
+    fn set_list_i16_from(&mut self, property_name: &str, values: &[i16]) {
+        self.set_property(property_name, Property::ListShort(values.to_vec()));
+    }
+    fn set_list_u16_from(&mut self, property_name: &str, values: &[u16]) {
+        self.set_property(property_name, Property::ListUShort(values.to_vec()));
+    }
+    fn set_list_i32_from(&mut self, property_name: &str, values: &[i32]) {
+        self.set_property(property_name, Property::ListInt(values.to_vec()));
+    }
+    fn set_list_u32_from(&mut self, property_name: &str, values: &[u32]) {
+        self.set_property(property_name, Property::ListUInt(values.to_vec()));
+    }
+    fn set_list_f32_from(&mut self, property_name: &str, values: &[f32]) {
+        self.set_property(property_name, Property::ListFloat(values.to_vec()));
+    }
+    fn set_list_f64_from(&mut self, property_name: &str, values: &[f64]) {
+        self.set_property(property_name, Property::ListDouble(values.to_vec()));
+    }
     fn get_char(&self, _property_name: &str) -> Option<i8> {
         None
     }
@@ -127,4 +198,449 @@ pub trait PropertyAccess {
     fn get_list_double(&self, _property_name: &str) -> Option<&[f64]> {
         None
     }
+    /// Resolves property names to the indices passed to the `_at` functions, see `PropertySchema`.
+    ///
+    /// By default `None` is returned and the name based functions are used.
+    fn bind_schema(_element_def: &ElementDef) -> Option<PropertySchema> {
+        None
+    }
+    /// Sets the property at `index` of the bound schema.
+    ///
+    /// The default discards the value, types that return a schema from `bind_schema()` have to implement it.
+    fn set_property_at(&mut self, _index: usize, _property: Property) {}
+    /// Whether the value at `index` of the bound schema is the property `def`, checked before reading it by index.
+    ///
+    /// By default true. If false, the name based getters are used for this element.
+    fn is_bound_at(&self, _index: usize, _def: &PropertyDef) -> bool {
+        true
+    }
+    fn set_char_at(&mut self, index: usize, value: i8) {
+        self.set_property_at(index, Property::Char(value));
+    }
+    fn set_uchar_at(&mut self, index: usize, value: u8) {
+        self.set_property_at(index, Property::UChar(value));
+    }
+    fn set_short_at(&mut self, index: usize, value: i16) {
+        self.set_property_at(index, Property::Short(value));
+    }
+    fn set_ushort_at(&mut self, index: usize, value: u16) {
+        self.set_property_at(index, Property::UShort(value));
+    }
+    fn set_int_at(&mut self, index: usize, value: i32) {
+        self.set_property_at(index, Property::Int(value));
+    }
+    fn set_uint_at(&mut self, index: usize, value: u32) {
+        self.set_property_at(index, Property::UInt(value));
+    }
+    fn set_float_at(&mut self, index: usize, value: f32) {
+        self.set_property_at(index, Property::Float(value));
+    }
+    fn set_double_at(&mut self, index: usize, value: f64) {
+        self.set_property_at(index, Property::Double(value));
+    }
+    fn set_list_i8_from_at(&mut self, index: usize, values: &[i8]) {
+        self.set_property_at(index, Property::ListChar(values.to_vec()));
+    }
+    fn set_list_u8_from_at(&mut self, index: usize, values: &[u8]) {
+        self.set_property_at(index, Property::ListUChar(values.to_vec()));
+    }
+    fn set_list_i16_from_at(&mut self, index: usize, values: &[i16]) {
+        self.set_property_at(index, Property::ListShort(values.to_vec()));
+    }
+    fn set_list_u16_from_at(&mut self, index: usize, values: &[u16]) {
+        self.set_property_at(index, Property::ListUShort(values.to_vec()));
+    }
+    fn set_list_i32_from_at(&mut self, index: usize, values: &[i32]) {
+        self.set_property_at(index, Property::ListInt(values.to_vec()));
+    }
+    fn set_list_u32_from_at(&mut self, index: usize, values: &[u32]) {
+        self.set_property_at(index, Property::ListUInt(values.to_vec()));
+    }
+    fn set_list_f32_from_at(&mut self, index: usize, values: &[f32]) {
+        self.set_property_at(index, Property::ListFloat(values.to_vec()));
+    }
+    fn set_list_f64_from_at(&mut self, index: usize, values: &[f64]) {
+        self.set_property_at(index, Property::ListDouble(values.to_vec()));
+    }
+    fn get_char_at(&self, _index: usize) -> Option<i8> {
+        None
+    }
+    fn get_uchar_at(&self, _index: usize) -> Option<u8> {
+        None
+    }
+    fn get_short_at(&self, _index: usize) -> Option<i16> {
+        None
+    }
+    fn get_ushort_at(&self, _index: usize) -> Option<u16> {
+        None
+    }
+    fn get_int_at(&self, _index: usize) -> Option<i32> {
+        None
+    }
+    fn get_uint_at(&self, _index: usize) -> Option<u32> {
+        None
+    }
+    fn get_float_at(&self, _index: usize) -> Option<f32> {
+        None
+    }
+    fn get_double_at(&self, _index: usize) -> Option<f64> {
+        None
+    }
+    fn get_list_char_at(&self, _index: usize) -> Option<&[i8]> {
+        None
+    }
+    fn get_list_uchar_at(&self, _index: usize) -> Option<&[u8]> {
+        None
+    }
+    fn get_list_short_at(&self, _index: usize) -> Option<&[i16]> {
+        None
+    }
+    fn get_list_ushort_at(&self, _index: usize) -> Option<&[u16]> {
+        None
+    }
+    fn get_list_int_at(&self, _index: usize) -> Option<&[i32]> {
+        None
+    }
+    fn get_list_uint_at(&self, _index: usize) -> Option<&[u32]> {
+        None
+    }
+    fn get_list_float_at(&self, _index: usize) -> Option<&[f32]> {
+        None
+    }
+    fn get_list_double_at(&self, _index: usize) -> Option<&[f64]> {
+        None
+    }
+}
+
+/// Indices of an element's properties, as resolved by `PropertyAccess::bind_schema()`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::ply::*;
+/// struct Vertex {
+///     p: [f32; 3],
+/// }
+/// impl PropertyAccess for Vertex {
+///     fn new() -> Self {
+///         Vertex { p: [0.0; 3] }
+///     }
+///     fn bind_schema(element_def: &ElementDef) -> Option<PropertySchema> {
+///         Some(PropertySchema::resolve(element_def, |name| {
+///             ["x", "y", "z"].iter().position(|n| *n == name)
+///         }))
+///     }
+///     fn set_property_at(&mut self, index: usize, property: Property) {
+///         if let Property::Float(v) = property {
+///             self.p[index] = v;
+///         }
+///     }
+///     fn get_float_at(&self, index: usize) -> Option<f32> {
+///         Some(self.p[index])
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PropertySchema {
+    /// One entry per property of the `ElementDef`, `None` for properties the type doesn't store.
+    pub indices: Vec<Option<usize>>,
+}
+
+impl PropertySchema {
+    /// Looks up the index of every property by its name.
+    pub fn resolve<F: FnMut(&str) -> Option<usize>>(
+        element_def: &ElementDef,
+        mut index_of: F,
+    ) -> Self {
+        PropertySchema {
+            indices: element_def
+                .properties
+                .iter()
+                .map(|p| index_of(&p.name))
+                .collect(),
+        }
+    }
+}
+
+/// Schemas bound once per element name, for types that are handed elements one at a time.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SchemaCache {
+    schemas: Vec<(ElementDef, Option<PropertySchema>)>,
+}
+
+impl SchemaCache {
+    /// Schema of `element_def`, bound again only if its properties differ from the last one of that name.
+    pub fn get<E: PropertyAccess>(&mut self, element_def: &ElementDef) -> Option<&PropertySchema> {
+        let position = self
+            .schemas
+            .iter()
+            .position(|(def, _)| def.name == element_def.name);
+        let i = match position {
+            Some(i) if self.schemas[i].0.properties == element_def.properties => i,
+            Some(i) => {
+                self.schemas[i] = (element_def.clone(), E::bind_schema(element_def));
+                i
+            }
+            None => {
+                self.schemas
+                    .push((element_def.clone(), E::bind_schema(element_def)));
+                self.schemas.len() - 1
+            }
+        };
+        self.schemas[i].1.as_ref()
+    }
+}
+
+/// How the parser and writer address a property of an element.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PropertyKey<'a> {
+    Name(&'a str),
+    Index(usize),
+    /// Not stored by the element type.
+    Unbound,
+}
+
+macro_rules! keyed_getters {
+    ($($get:ident, $get_at:ident -> $t:ty;)*) => {$(
+        pub fn $get<E: PropertyAccess>(self, element: &E) -> Option<$t> {
+            match self {
+                PropertyKey::Name(name) => element.$get(name),
+                PropertyKey::Index(index) => element.$get_at(index),
+                PropertyKey::Unbound => None,
+            }
+        }
+    )*};
+    ($(list $get:ident, $get_at:ident -> $t:ty;)*) => {$(
+        pub fn $get<E: PropertyAccess>(self, element: &E) -> Option<&[$t]> {
+            match self {
+                PropertyKey::Name(name) => element.$get(name),
+                PropertyKey::Index(index) => element.$get_at(index),
+                PropertyKey::Unbound => None,
+            }
+        }
+    )*};
+}
+
+macro_rules! keyed_setters {
+    ($($set:ident, $set_at:ident ($t:ty);)*) => {$(
+        pub fn $set<E: PropertyAccess>(self, element: &mut E, value: $t) {
+            match self {
+                PropertyKey::Name(name) => element.$set(name, value),
+                PropertyKey::Index(index) => element.$set_at(index, value),
+                PropertyKey::Unbound => (),
+            }
+        }
+    )*};
+}
+
+impl<'a> PropertyKey<'a> {
+    /// Key of the property at `index` in `element_def.properties`.
+    pub fn new(schema: Option<&PropertySchema>, index: usize, def: &'a PropertyDef) -> Self {
+        match schema {
+            None => PropertyKey::Name(&def.name),
+            Some(schema) => match schema.indices.get(index) {
+                Some(Some(i)) => PropertyKey::Index(*i),
+                _ => PropertyKey::Unbound,
+            },
+        }
+    }
+    /// Key of the property at `index` to read from `element`, by name if `element` doesn't follow `schema`.
+    pub fn bound<E: PropertyAccess>(
+        element: &E,
+        schema: Option<&PropertySchema>,
+        index: usize,
+        def: &'a PropertyDef,
+    ) -> Self {
+        match PropertyKey::new(schema, index, def) {
+            PropertyKey::Index(i) if !element.is_bound_at(i, def) => PropertyKey::Name(&def.name),
+            key => key,
+        }
+    }
+    keyed_setters! {
+        set_char, set_char_at (i8);
+        set_uchar, set_uchar_at (u8);
+        set_short, set_short_at (i16);
+        set_ushort, set_ushort_at (u16);
+        set_int, set_int_at (i32);
+        set_uint, set_uint_at (u32);
+        set_float, set_float_at (f32);
+        set_double, set_double_at (f64);
+        set_list_i8_from, set_list_i8_from_at (&[i8]);
+        set_list_u8_from, set_list_u8_from_at (&[u8]);
+        set_list_i16_from, set_list_i16_from_at (&[i16]);
+        set_list_u16_from, set_list_u16_from_at (&[u16]);
+        set_list_i32_from, set_list_i32_from_at (&[i32]);
+        set_list_u32_from, set_list_u32_from_at (&[u32]);
+        set_list_f32_from, set_list_f32_from_at (&[f32]);
+        set_list_f64_from, set_list_f64_from_at (&[f64]);
+    }
+    keyed_getters! {
+        get_char, get_char_at -> i8;
+        get_uchar, get_uchar_at -> u8;
+        get_short, get_short_at -> i16;
+        get_ushort, get_ushort_at -> u16;
+        get_int, get_int_at -> i32;
+        get_uint, get_uint_at -> u32;
+        get_float, get_float_at -> f32;
+        get_double, get_double_at -> f64;
+    }
+    keyed_getters! {
+        list get_list_char, get_list_char_at -> i8;
+        list get_list_uchar, get_list_uchar_at -> u8;
+        list get_list_short, get_list_short_at -> i16;
+        list get_list_ushort, get_list_ushort_at -> u16;
+        list get_list_int, get_list_int_at -> i32;
+        list get_list_uint, get_list_uint_at -> u32;
+        list get_list_float, get_list_float_at -> f32;
+        list get_list_double, get_list_double_at -> f64;
+    }
+}
+
+impl ScalarType {
+    /// Number of bytes used to encode a value of this type in a binary payload.
+    pub fn byte_size(&self) -> usize {
+        match *self {
+            ScalarType::Char | ScalarType::UChar => 1,
+            ScalarType::Short | ScalarType::UShort => 2,
+            ScalarType::Int | ScalarType::UInt | ScalarType::Float => 4,
+            ScalarType::Double => 8,
+        }
+    }
+}
+
+/// Uses the canonical spelling of the PLY header, e.g. `uchar` or `float`.
+impl Display for ScalarType {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str(match *self {
+            ScalarType::Char => "char",
+            ScalarType::UChar => "uchar",
+            ScalarType::Short => "short",
+            ScalarType::UShort => "ushort",
+            ScalarType::Int => "int",
+            ScalarType::UInt => "uint",
+            ScalarType::Float => "float",
+            ScalarType::Double => "double",
+        })
+    }
+}
+
+/// Uses the spelling of the PLY header, e.g. `float` or `list uchar int`.
+impl Display for PropertyType {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            PropertyType::Scalar(ref s) => write!(f, "{}", s),
+            PropertyType::List(ref i, ref s) => write!(f, "list {} {}", i, s),
+        }
+    }
+}
+
+impl Property {
+    /// Reads the property defined by `def` from `element` using the getter matching its type.
+    ///
+    /// Returns `None` if `element` doesn't provide a value of the declared type.
+    /// Only the name based getters are used, see `read_bound()` for types that implement `bind_schema()`.
+    pub fn read_from<E: PropertyAccess>(element: &E, def: &PropertyDef) -> Option<Property> {
+        Self::read_keyed(element, PropertyKey::Name(&def.name), &def.data_type)
+    }
+
+    /// Reads the property at `index` of `element_def`, like `read_from()`.
+    ///
+    /// `schema` is the result of `E::bind_schema(element_def)`, bind it once for all elements of `element_def`.
+    /// With a schema, the `get_*_at()` functions are used.
+    pub fn read_bound<E: PropertyAccess>(
+        element: &E,
+        element_def: &ElementDef,
+        schema: Option<&PropertySchema>,
+        index: usize,
+    ) -> Option<Property> {
+        let def = element_def.properties.get(index)?;
+        Self::read_keyed(
+            element,
+            PropertyKey::bound(element, schema, index, def),
+            &def.data_type,
+        )
+    }
+
+    pub(crate) fn read_keyed<E: PropertyAccess>(
+        element: &E,
+        k: PropertyKey,
+        data_type: &PropertyType,
+    ) -> Option<Property> {
+        Some(match *data_type {
+            PropertyType::Scalar(ref scalar_type) => match *scalar_type {
+                ScalarType::Char => Property::Char(k.get_char(element)?),
+                ScalarType::UChar => Property::UChar(k.get_uchar(element)?),
+                ScalarType::Short => Property::Short(k.get_short(element)?),
+                ScalarType::UShort => Property::UShort(k.get_ushort(element)?),
+                ScalarType::Int => Property::Int(k.get_int(element)?),
+                ScalarType::UInt => Property::UInt(k.get_uint(element)?),
+                ScalarType::Float => Property::Float(k.get_float(element)?),
+                ScalarType::Double => Property::Double(k.get_double(element)?),
+            },
+            PropertyType::List(_, ref scalar_type) => match *scalar_type {
+                ScalarType::Char => Property::ListChar(k.get_list_char(element)?.to_vec()),
+                ScalarType::UChar => Property::ListUChar(k.get_list_uchar(element)?.to_vec()),
+                ScalarType::Short => Property::ListShort(k.get_list_short(element)?.to_vec()),
+                ScalarType::UShort => Property::ListUShort(k.get_list_ushort(element)?.to_vec()),
+                ScalarType::Int => Property::ListInt(k.get_list_int(element)?.to_vec()),
+                ScalarType::UInt => Property::ListUInt(k.get_list_uint(element)?.to_vec()),
+                ScalarType::Float => Property::ListFloat(k.get_list_float(element)?.to_vec()),
+                ScalarType::Double => Property::ListDouble(k.get_list_double(element)?.to_vec()),
+            },
+        })
+    }
+
+    /// Converts a scalar property to `f64`, lists return `None`.
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match *self {
+            Property::Char(v) => v as f64,
+            Property::UChar(v) => v as f64,
+            Property::Short(v) => v as f64,
+            Property::UShort(v) => v as f64,
+            Property::Int(v) => v as f64,
+            Property::UInt(v) => v as f64,
+            Property::Float(v) => v as f64,
+            Property::Double(v) => v,
+            _ => return None,
+        })
+    }
+
+    /// Converts every entry of a list property to `f64`, scalars return `None`.
+    pub fn list_as_f64(&self) -> Option<Vec<f64>> {
+        Some(match *self {
+            Property::ListChar(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListUChar(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListShort(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListUShort(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListInt(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListUInt(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListFloat(ref v) => v.iter().map(|x| *x as f64).collect(),
+            Property::ListDouble(ref v) => v.clone(),
+            _ => return None,
+        })
+    }
+
+    /// Type of the property as it would be declared in a header.
+    ///
+    /// Lists are reported with a `UInt` index type, since the index type isn't stored in a `Property`.
+    pub fn property_type(&self) -> PropertyType {
+        match *self {
+            Property::Char(_) => PropertyType::Scalar(ScalarType::Char),
+            Property::UChar(_) => PropertyType::Scalar(ScalarType::UChar),
+            Property::Short(_) => PropertyType::Scalar(ScalarType::Short),
+            Property::UShort(_) => PropertyType::Scalar(ScalarType::UShort),
+            Property::Int(_) => PropertyType::Scalar(ScalarType::Int),
+            Property::UInt(_) => PropertyType::Scalar(ScalarType::UInt),
+            Property::Float(_) => PropertyType::Scalar(ScalarType::Float),
+            Property::Double(_) => PropertyType::Scalar(ScalarType::Double),
+            Property::ListChar(_) => PropertyType::List(ScalarType::UInt, ScalarType::Char),
+            Property::ListUChar(_) => PropertyType::List(ScalarType::UInt, ScalarType::UChar),
+            Property::ListShort(_) => PropertyType::List(ScalarType::UInt, ScalarType::Short),
+            Property::ListUShort(_) => PropertyType::List(ScalarType::UInt, ScalarType::UShort),
+            Property::ListInt(_) => PropertyType::List(ScalarType::UInt, ScalarType::Int),
+            Property::ListUInt(_) => PropertyType::List(ScalarType::UInt, ScalarType::UInt),
+            Property::ListFloat(_) => PropertyType::List(ScalarType::UInt, ScalarType::Float),
+            Property::ListDouble(_) => PropertyType::List(ScalarType::UInt, ScalarType::Double),
+        }
+    }
 }
@@ -0,0 +1,476 @@
+//! Per element and per property summaries of a payload.
+
+use super::{
+    ElementDef, Encoding, Header, Ply, Property, PropertyAccess, PropertyType, SchemaCache,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Summary of a sequence of numbers.
+///
+/// `NaN` and infinite values are counted, but excluded from minimum, maximum, mean and standard deviation.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValueStatistics {
+    /// Number of values, including `NaN` and infinite values.
+    pub count: u64,
+    /// Number of `NaN` values.
+    pub nan_count: u64,
+    /// Number of positive or negative infinite values.
+    pub inf_count: u64,
+    /// Smallest finite value.
+    pub min: Option<f64>,
+    /// Largest finite value.
+    pub max: Option<f64>,
+    mean: f64,
+    /// Sum of squared differences from the mean (Welford's algorithm).
+    m2: f64,
+}
+
+impl ValueStatistics {
+    /// Creates an empty summary.
+    pub fn new() -> Self {
+        ValueStatistics::default()
+    }
+
+    /// Adds a single value.
+    pub fn add(&mut self, v: f64) {
+        self.count += 1;
+        if v.is_nan() {
+            self.nan_count += 1;
+            return;
+        }
+        if v.is_infinite() {
+            self.inf_count += 1;
+            return;
+        }
+        self.min = Some(self.min.map_or(v, |m| m.min(v)));
+        self.max = Some(self.max.map_or(v, |m| m.max(v)));
+        let n = self.finite_count() as f64;
+        let delta = v - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (v - self.mean);
+    }
+
+    /// Number of values that are neither `NaN` nor infinite.
+    pub fn finite_count(&self) -> u64 {
+        self.count - self.nan_count - self.inf_count
+    }
+
+    /// Arithmetic mean of all finite values.
+    pub fn mean(&self) -> Option<f64> {
+        if self.finite_count() == 0 {
+            None
+        } else {
+            Some(self.mean)
+        }
+    }
+
+    /// Population standard deviation of all finite values.
+    pub fn stddev(&self) -> Option<f64> {
+        if self.finite_count() == 0 {
+            None
+        } else {
+            Some((self.m2 / self.finite_count() as f64).sqrt())
+        }
+    }
+}
+
+/// Summary of a single property over all elements of an element type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyStatistics {
+    /// Name of the property.
+    pub name: String,
+    /// Declared type of the property.
+    pub data_type: PropertyType,
+    /// Summary of all values. For lists, every entry of every list counts as a value.
+    pub values: ValueStatistics,
+    /// For lists: how many lists (value) have a given length (key).
+    pub list_lengths: Option<BTreeMap<usize, u64>>,
+    /// Number of elements that didn't provide a value of the declared type.
+    pub missing: u64,
+}
+
+impl PropertyStatistics {
+    fn new(name: &str, data_type: PropertyType) -> Self {
+        PropertyStatistics {
+            name: name.to_string(),
+            data_type,
+            values: ValueStatistics::new(),
+            list_lengths: match data_type {
+                PropertyType::List(_, _) => Some(BTreeMap::new()),
+                PropertyType::Scalar(_) => None,
+            },
+            missing: 0,
+        }
+    }
+}
+
+/// Summary of all elements of one element type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementStatistics {
+    /// Name of the element.
+    pub name: String,
+    /// Number of elements seen.
+    pub count: u64,
+    /// One entry per property, in the order of the element definition.
+    pub properties: Vec<PropertyStatistics>,
+    ascii_size: u64,
+    binary_size: u64,
+}
+
+impl ElementStatistics {
+    fn new(element_def: &ElementDef) -> Self {
+        ElementStatistics {
+            name: element_def.name.clone(),
+            count: 0,
+            properties: element_def
+                .properties
+                .iter()
+                .map(|p| PropertyStatistics::new(&p.name, p.data_type))
+                .collect(),
+            ascii_size: 0,
+            binary_size: 0,
+        }
+    }
+
+    /// Number of bytes all elements occupy in the payload when written with the given encoding.
+    ///
+    /// The ascii size assumes the number formatting of `Writer`.
+    pub fn byte_size(&self, encoding: Encoding) -> u64 {
+        match encoding {
+            Encoding::Ascii => self.ascii_size,
+            Encoding::BinaryBigEndian | Encoding::BinaryLittleEndian => self.binary_size,
+        }
+    }
+
+    /// Looks up the statistics of a property by name.
+    pub fn property(&self, name: &str) -> Option<&PropertyStatistics> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+/// Statistics of an entire payload, see `Ply::statistics()`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statistics {
+    /// One entry per element type, in the order of the header.
+    pub elements: Vec<ElementStatistics>,
+}
+
+impl Statistics {
+    /// Looks up the statistics of an element by name.
+    pub fn element(&self, name: &str) -> Option<&ElementStatistics> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+
+    /// Number of bytes the entire payload occupies when written with the given encoding.
+    pub fn byte_size(&self, encoding: Encoding) -> u64 {
+        self.elements.iter().map(|e| e.byte_size(encoding)).sum()
+    }
+}
+
+fn format_number(v: Option<f64>) -> String {
+    match v {
+        None => "-".to_string(),
+        Some(v) if v.fract() == 0.0 && v.abs() < 1e15 => format!("{}", v),
+        Some(v) => format!("{:.6}", v),
+    }
+}
+
+/// Renders one table per element.
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        let titles = [
+            "property", "type", "count", "min", "max", "mean", "stddev", "nan", "inf",
+        ];
+        for e in &self.elements {
+            writeln!(
+                f,
+                "element {}: {} elements, ascii: {} bytes, binary: {} bytes",
+                e.name, e.count, e.ascii_size, e.binary_size
+            )?;
+            let rows: Vec<[String; 9]> = e
+                .properties
+                .iter()
+                .map(|p| {
+                    let v = &p.values;
+                    [
+                        p.name.clone(),
+                        p.data_type.to_string(),
+                        v.count.to_string(),
+                        format_number(v.min),
+                        format_number(v.max),
+                        format_number(v.mean()),
+                        format_number(v.stddev()),
+                        v.nan_count.to_string(),
+                        v.inf_count.to_string(),
+                    ]
+                })
+                .collect();
+            let mut widths = titles.map(|t| t.len());
+            for r in &rows {
+                for (w, c) in widths.iter_mut().zip(r.iter()) {
+                    *w = (*w).max(c.len());
+                }
+            }
+            let titles = titles.map(|t| t.to_string());
+            for r in std::iter::once(&titles).chain(rows.iter()) {
+                let cells: Vec<String> = r
+                    .iter()
+                    .zip(widths.iter())
+                    .map(|(c, w)| format!("{:<w$}", c, w = w))
+                    .collect();
+                writeln!(f, "  {}", cells.join("  ").trim_end())?;
+            }
+            for p in &e.properties {
+                if let Some(ref lengths) = p.list_lengths {
+                    let h: Vec<String> = lengths
+                        .iter()
+                        .map(|(len, n)| format!("{}: {}", len, n))
+                        .collect();
+                    writeln!(f, "  list lengths of {}: {}", p.name, h.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ascii_len(p: &Property) -> u64 {
+    fn list<V: ToString>(l: &[V]) -> u64 {
+        l.len().to_string().len() as u64
+            + l.iter()
+                .map(|v| 1 + v.to_string().len() as u64)
+                .sum::<u64>()
+    }
+    match *p {
+        Property::Char(v) => v.to_string().len() as u64,
+        Property::UChar(v) => v.to_string().len() as u64,
+        Property::Short(v) => v.to_string().len() as u64,
+        Property::UShort(v) => v.to_string().len() as u64,
+        Property::Int(v) => v.to_string().len() as u64,
+        Property::UInt(v) => v.to_string().len() as u64,
+        Property::Float(v) => v.to_string().len() as u64,
+        Property::Double(v) => v.to_string().len() as u64,
+        Property::ListChar(ref l) => list(l),
+        Property::ListUChar(ref l) => list(l),
+        Property::ListShort(ref l) => list(l),
+        Property::ListUShort(ref l) => list(l),
+        Property::ListInt(ref l) => list(l),
+        Property::ListUInt(ref l) => list(l),
+        Property::ListFloat(ref l) => list(l),
+        Property::ListDouble(ref l) => list(l),
+    }
+}
+
+/// Collects `Statistics` one element at a time.
+///
+/// Use it together with `Parser::read_payload_with()` to summarize files without keeping the payload in memory.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::parser::Parser;
+/// # use ply_rs::ply::{DefaultElement, StatisticsAccumulator};
+/// let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+/// let mut f = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+/// let p = Parser::<DefaultElement>::new();
+/// let header = p.read_header(&mut f).unwrap();
+///
+/// let mut acc = StatisticsAccumulator::new(&header);
+/// p.read_payload_with(&mut f, &header, |def, e| {
+///     acc.add(def, &e);
+///     Ok(())
+/// }).unwrap();
+/// let stats = acc.finish();
+/// assert_eq!(stats.element("vertex").unwrap().count, 8);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatisticsAccumulator {
+    statistics: Statistics,
+    schemas: SchemaCache,
+}
+
+impl StatisticsAccumulator {
+    /// Prepares an empty summary for every element declared in `header`.
+    pub fn new(header: &Header) -> Self {
+        StatisticsAccumulator {
+            statistics: Statistics {
+                elements: header.elements.iter().map(ElementStatistics::new).collect(),
+            },
+            schemas: SchemaCache::default(),
+        }
+    }
+
+    /// Adds a single element.
+    ///
+    /// Elements of a type not declared in the header passed to `new()` are added as a new element type.
+    pub fn add<E: PropertyAccess>(&mut self, element_def: &ElementDef, element: &E) {
+        let elements = &mut self.statistics.elements;
+        let i = match elements.iter().position(|e| e.name == element_def.name) {
+            Some(i) => i,
+            None => {
+                elements.push(ElementStatistics::new(element_def));
+                elements.len() - 1
+            }
+        };
+        let stats = &mut elements[i];
+        stats.count += 1;
+        stats.ascii_size += 1; // line break
+        let schema = self.schemas.get::<E>(element_def);
+        for (index, def) in element_def.properties.iter().enumerate() {
+            let p_stats = match stats.properties.iter_mut().find(|p| p.name == def.name) {
+                Some(p) => p,
+                None => continue,
+            };
+            stats.ascii_size += 1; // separator
+            let property = match Property::read_bound(element, element_def, schema, index) {
+                Some(p) => p,
+                None => {
+                    p_stats.missing += 1;
+                    continue;
+                }
+            };
+            stats.ascii_size += ascii_len(&property);
+            match def.data_type {
+                PropertyType::Scalar(s) => {
+                    stats.binary_size += s.byte_size() as u64;
+                    p_stats.values.add(property.as_f64().unwrap());
+                }
+                PropertyType::List(index_type, s) => {
+                    let list = property.list_as_f64().unwrap();
+                    stats.binary_size +=
+                        (index_type.byte_size() + list.len() * s.byte_size()) as u64;
+                    if let Some(ref mut lengths) = p_stats.list_lengths {
+                        *lengths.entry(list.len()).or_insert(0) += 1;
+                    }
+                    for v in list {
+                        p_stats.values.add(v);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the collected statistics.
+    pub fn finish(self) -> Statistics {
+        self.statistics
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Computes per element and per property statistics of the payload.
+    ///
+    /// Element types are summarized in the order of the header.
+    pub fn statistics(&self) -> Statistics {
+        let mut acc = StatisticsAccumulator::new(&self.header);
+        for element_def in &self.header.elements {
+            if let Some(elements) = self.payload.get(&element_def.name) {
+                for e in elements {
+                    acc.add(element_def, e);
+                }
+            }
+        }
+        acc.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::writer::Writer;
+
+    fn sample() -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut e = ElementDef::new("point");
+        e.properties.push(PropertyDef::new(
+            "x",
+            PropertyType::Scalar(ScalarType::Float),
+        ));
+        e.properties.push(PropertyDef::new(
+            "l",
+            PropertyType::List(ScalarType::UChar, ScalarType::Int),
+        ));
+        ply.header.elements.push(e);
+        let values = [(1.0, vec![1, 2]), (f32::NAN, vec![]), (3.0, vec![-3, 4])];
+        let points = values
+            .iter()
+            .map(|(x, l)| {
+                let mut p = DefaultElement::new();
+                p.insert("x".to_string(), Property::Float(*x));
+                p.insert("l".to_string(), Property::ListInt(l.clone()));
+                p
+            })
+            .collect();
+        ply.payload.insert("point".to_string(), points);
+        ply.make_consistent().unwrap();
+        ply
+    }
+
+    #[test]
+    fn values_ok() {
+        let stats = sample().statistics();
+        let e = stats.element("point").unwrap();
+        assert_eq!(e.count, 3);
+        let x = &e.property("x").unwrap().values;
+        assert_eq!(x.count, 3);
+        assert_eq!(x.nan_count, 1);
+        assert_eq!(x.min, Some(1.0));
+        assert_eq!(x.max, Some(3.0));
+        assert_eq!(x.mean(), Some(2.0));
+        assert_eq!(x.stddev(), Some(1.0));
+        let l = e.property("l").unwrap();
+        assert_eq!(l.values.count, 4);
+        assert_eq!(l.values.min, Some(-3.0));
+        let lengths: Vec<(usize, u64)> = l.list_lengths.clone().unwrap().into_iter().collect();
+        assert_eq!(lengths, vec![(0, 1), (2, 2)]);
+    }
+    #[test]
+    fn byte_size_ok() {
+        let mut ply = sample();
+        let stats = ply.statistics();
+        for encoding in [Encoding::Ascii, Encoding::BinaryLittleEndian] {
+            ply.header.encoding = encoding;
+            let mut buf = Vec::new();
+            let w = Writer::new();
+            w.write_payload(&mut buf, &ply.payload, &ply.header)
+                .unwrap();
+            assert_eq!(stats.byte_size(encoding), buf.len() as u64);
+        }
+    }
+    #[test]
+    fn display_ok() {
+        let table = sample().statistics().to_string();
+        assert!(table.contains("element point: 3 elements"));
+        assert!(table.contains("list lengths of l: 0: 1, 2: 2"));
+    }
+    #[test]
+    fn schema_bound_once() {
+        use std::cell::Cell;
+        thread_local!(static BINDS: Cell<usize> = const { Cell::new(0) });
+        struct Point(f32);
+        impl PropertyAccess for Point {
+            fn new() -> Self {
+                Point(0.0)
+            }
+            fn bind_schema(element_def: &ElementDef) -> Option<PropertySchema> {
+                BINDS.with(|b| b.set(b.get() + 1));
+                Some(PropertySchema::resolve(element_def, |name| {
+                    (name == "x").then_some(0)
+                }))
+            }
+            fn get_float_at(&self, _index: usize) -> Option<f32> {
+                Some(self.0)
+            }
+        }
+        let ply = sample();
+        let def = &ply.header.elements[0];
+        let mut acc = StatisticsAccumulator::new(&ply.header);
+        for x in [1.0, 2.0, 4.0] {
+            acc.add(def, &Point(x));
+        }
+        assert_eq!(BINDS.with(Cell::get), 1);
+        let stats = acc.finish();
+        assert_eq!(stats.element("point").unwrap().count, 3);
+    }
+}
@@ -0,0 +1,495 @@
+//! Compressed splat layout as used by several web viewers.
+//!
+//! Gaussians are grouped into chunks of `CHUNK_SIZE`.
+//! Each `chunk` element stores the quantization bounds of its Gaussians,
+//! each `vertex` element stores one Gaussian in four packed `uint` properties
+//! and the optional `sh` element stores the higher order spherical harmonics as `uchar`.
+
+use super::{sh_rest_count, Gaussian, GaussianCloud, SH_C0};
+use crate::ply::{
+    ElementDef, Encoding, Header, Property, PropertyAccess, PropertyDef, PropertyType, ScalarType,
+};
+
+/// Number of Gaussians sharing one `SplatChunk`.
+pub const CHUNK_SIZE: usize = 256;
+
+/// Property names of the packed `vertex` element.
+pub const PACKED_PROPERTIES: [&str; 4] = [
+    "packed_position",
+    "packed_rotation",
+    "packed_scale",
+    "packed_color",
+];
+
+const CHUNK_PROPERTIES: [&str; 18] = [
+    "min_x",
+    "min_y",
+    "min_z",
+    "max_x",
+    "max_y",
+    "max_z",
+    "min_scale_x",
+    "min_scale_y",
+    "min_scale_z",
+    "max_scale_x",
+    "max_scale_y",
+    "max_scale_z",
+    "min_r",
+    "min_g",
+    "min_b",
+    "max_r",
+    "max_g",
+    "max_b",
+];
+
+/// Quantization bounds shared by up to `CHUNK_SIZE` Gaussians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplatChunk {
+    /// `min_x min_y min_z`
+    pub min_position: [f32; 3],
+    /// `max_x max_y max_z`
+    pub max_position: [f32; 3],
+    /// `min_scale_x min_scale_y min_scale_z`, in log space.
+    pub min_scale: [f32; 3],
+    /// `max_scale_x max_scale_y max_scale_z`, in log space.
+    pub max_scale: [f32; 3],
+    /// `min_r min_g min_b`, defaults to 0 for files without color bounds.
+    pub min_color: [f32; 3],
+    /// `max_r max_g max_b`, defaults to 1 for files without color bounds.
+    pub max_color: [f32; 3],
+}
+
+impl SplatChunk {
+    fn groups(&self) -> [&[f32; 3]; 6] {
+        [
+            &self.min_position,
+            &self.max_position,
+            &self.min_scale,
+            &self.max_scale,
+            &self.min_color,
+            &self.max_color,
+        ]
+    }
+    fn groups_mut(&mut self) -> [&mut [f32; 3]; 6] {
+        [
+            &mut self.min_position,
+            &mut self.max_position,
+            &mut self.min_scale,
+            &mut self.max_scale,
+            &mut self.min_color,
+            &mut self.max_color,
+        ]
+    }
+}
+
+fn chunk_index(name: &str) -> Option<(usize, usize)> {
+    let i = CHUNK_PROPERTIES.iter().position(|n| *n == name)?;
+    Some((i / 3, i % 3))
+}
+
+impl PropertyAccess for SplatChunk {
+    fn new() -> Self {
+        SplatChunk {
+            min_position: [0.0; 3],
+            max_position: [0.0; 3],
+            min_scale: [0.0; 3],
+            max_scale: [0.0; 3],
+            min_color: [0.0; 3],
+            max_color: [1.0; 3],
+        }
+    }
+    fn set_property(&mut self, property_name: &str, property: Property) {
+        if let (Some((g, i)), Property::Float(v)) = (chunk_index(property_name), property) {
+            self.groups_mut()[g][i] = v;
+        }
+    }
+    fn get_float(&self, property_name: &str) -> Option<f32> {
+        chunk_index(property_name).map(|(g, i)| self.groups()[g][i])
+    }
+}
+
+/// A single Gaussian packed into four `uint`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PackedGaussian {
+    /// Position relative to the chunk bounds, 11, 10 and 11 bits.
+    pub packed_position: u32,
+    /// Rotation quaternion: 2 bits for the index of the largest component, 10 bits for each of the others.
+    pub packed_rotation: u32,
+    /// Log scale relative to the chunk bounds, 11, 10 and 11 bits.
+    pub packed_scale: u32,
+    /// Linear color relative to the chunk bounds and opacity after the sigmoid, 8 bits each.
+    pub packed_color: u32,
+}
+
+impl PropertyAccess for PackedGaussian {
+    fn new() -> Self {
+        PackedGaussian::default()
+    }
+    fn set_property(&mut self, property_name: &str, property: Property) {
+        let v = match property {
+            Property::UInt(v) => v,
+            _ => return,
+        };
+        match property_name {
+            "packed_position" => self.packed_position = v,
+            "packed_rotation" => self.packed_rotation = v,
+            "packed_scale" => self.packed_scale = v,
+            "packed_color" => self.packed_color = v,
+            _ => (),
+        }
+    }
+    fn get_uint(&self, property_name: &str) -> Option<u32> {
+        match property_name {
+            "packed_position" => Some(self.packed_position),
+            "packed_rotation" => Some(self.packed_rotation),
+            "packed_scale" => Some(self.packed_scale),
+            "packed_color" => Some(self.packed_color),
+            _ => None,
+        }
+    }
+}
+
+/// Higher order spherical harmonics of a single Gaussian, quantized to 8 bits.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PackedSh {
+    /// `f_rest_0..N` in file order.
+    pub coefficients: Vec<u8>,
+}
+
+impl PropertyAccess for PackedSh {
+    fn new() -> Self {
+        PackedSh::default()
+    }
+    fn set_property(&mut self, property_name: &str, property: Property) {
+        if let (Some(i), Property::UChar(v)) = (super::f_rest_index(property_name), property) {
+            if i >= super::MAX_SH_REST_COUNT {
+                return;
+            }
+            if self.coefficients.len() <= i {
+                self.coefficients.resize(i + 1, 0);
+            }
+            self.coefficients[i] = v;
+        }
+    }
+    fn get_uchar(&self, property_name: &str) -> Option<u8> {
+        self.coefficients
+            .get(super::f_rest_index(property_name)?)
+            .cloned()
+    }
+}
+
+/// A splatting checkpoint in the compressed layout.
+///
+/// Quantization is lossy and normals are dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::splat::{CompressedSplat, Gaussian, GaussianCloud};
+/// let mut cloud = GaussianCloud::new(0);
+/// cloud.gaussians.push(Gaussian::new(0));
+///
+/// let compressed = CompressedSplat::compress(&cloud);
+/// assert_eq!(compressed.chunks.len(), 1);
+/// let restored = compressed.decompress();
+/// assert_eq!(restored.gaussians.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompressedSplat {
+    /// Degree of the spherical harmonics stored in `sh`.
+    pub sh_degree: u8,
+    /// One chunk for every `CHUNK_SIZE` vertices.
+    pub chunks: Vec<SplatChunk>,
+    /// The packed Gaussians.
+    pub vertices: Vec<PackedGaussian>,
+    /// Higher order spherical harmonics, one entry per vertex. Empty for degree 0.
+    pub sh: Vec<PackedSh>,
+}
+
+fn pack_unorm(v: f32, bits: u32) -> u32 {
+    let max = ((1u32 << bits) - 1) as f32;
+    (v.clamp(0.0, 1.0) * max + 0.5) as u32
+}
+
+fn unpack_unorm(v: u32, bits: u32) -> f32 {
+    let max = (1u32 << bits) - 1;
+    (v & max) as f32 / max as f32
+}
+
+fn normalize(v: f32, min: f32, max: f32) -> f32 {
+    if max - min > 0.0 {
+        (v - min) / (max - min)
+    } else {
+        0.0
+    }
+}
+
+fn lerp(min: f32, max: f32, t: f32) -> f32 {
+    min + (max - min) * t
+}
+
+fn pack_111011(v: [f32; 3]) -> u32 {
+    (pack_unorm(v[0], 11) << 21) | (pack_unorm(v[1], 10) << 11) | pack_unorm(v[2], 11)
+}
+
+fn unpack_111011(v: u32) -> [f32; 3] {
+    [
+        unpack_unorm(v >> 21, 11),
+        unpack_unorm(v >> 11, 10),
+        unpack_unorm(v, 11),
+    ]
+}
+
+fn pack_8888(v: [f32; 4]) -> u32 {
+    (pack_unorm(v[0], 8) << 24)
+        | (pack_unorm(v[1], 8) << 16)
+        | (pack_unorm(v[2], 8) << 8)
+        | pack_unorm(v[3], 8)
+}
+
+fn unpack_8888(v: u32) -> [f32; 4] {
+    [
+        unpack_unorm(v >> 24, 8),
+        unpack_unorm(v >> 16, 8),
+        unpack_unorm(v >> 8, 8),
+        unpack_unorm(v, 8),
+    ]
+}
+
+/// "Smallest three" encoding: the largest component is dropped and reconstructed from the unit length.
+fn pack_rotation(q: [f32; 4]) -> u32 {
+    let len = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+    let q = if len > 0.0 {
+        [q[0] / len, q[1] / len, q[2] / len, q[3] / len]
+    } else {
+        [1.0, 0.0, 0.0, 0.0]
+    };
+    let largest = (0..4)
+        .max_by(|a, b| q[*a].abs().total_cmp(&q[*b].abs()))
+        .unwrap();
+    let sign = if q[largest] < 0.0 { -1.0 } else { 1.0 };
+    let mut packed = largest as u32;
+    for (i, c) in q.iter().enumerate() {
+        if i != largest {
+            packed =
+                (packed << 10) | pack_unorm(c * sign * std::f32::consts::FRAC_1_SQRT_2 + 0.5, 10);
+        }
+    }
+    packed
+}
+
+fn unpack_rotation(v: u32) -> [f32; 4] {
+    let norm = std::f32::consts::SQRT_2;
+    let a = (unpack_unorm(v >> 20, 10) - 0.5) * norm;
+    let b = (unpack_unorm(v >> 10, 10) - 0.5) * norm;
+    let c = (unpack_unorm(v, 10) - 0.5) * norm;
+    let m = (1.0 - (a * a + b * b + c * c)).max(0.0).sqrt();
+    match v >> 30 {
+        0 => [m, a, b, c],
+        1 => [a, m, b, c],
+        2 => [a, b, m, c],
+        _ => [a, b, c, m],
+    }
+}
+
+fn sigmoid(v: f32) -> f32 {
+    1.0 / (1.0 + (-v).exp())
+}
+
+fn inverse_sigmoid(v: f32) -> f32 {
+    let v = v.clamp(1e-6, 1.0 - 1e-6);
+    -(1.0 / v - 1.0).ln()
+}
+
+fn pack_sh(v: f32) -> u8 {
+    ((v / 8.0 + 0.5) * 256.0).floor().clamp(0.0, 255.0) as u8
+}
+
+fn unpack_sh(v: u8) -> f32 {
+    ((v as f32 + 0.5) / 256.0 - 0.5) * 8.0
+}
+
+fn color(g: &Gaussian) -> [f32; 3] {
+    [
+        g.sh_dc[0] * SH_C0 + 0.5,
+        g.sh_dc[1] * SH_C0 + 0.5,
+        g.sh_dc[2] * SH_C0 + 0.5,
+    ]
+}
+
+fn bounds<F: Fn(&Gaussian) -> [f32; 3]>(gaussians: &[Gaussian], f: F) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for g in gaussians {
+        let v = f(g);
+        for i in 0..3 {
+            min[i] = min[i].min(v[i]);
+            max[i] = max[i].max(v[i]);
+        }
+    }
+    (min, max)
+}
+
+impl CompressedSplat {
+    /// Creates an empty compressed splat with the given spherical harmonics degree.
+    pub fn new(sh_degree: u8) -> Self {
+        CompressedSplat {
+            sh_degree,
+            chunks: Vec::new(),
+            vertices: Vec::new(),
+            sh: Vec::new(),
+        }
+    }
+
+    /// Quantizes `cloud` chunk by chunk in its current order.
+    ///
+    /// Spatially sorting the Gaussians beforehand leads to tighter chunk bounds and better precision.
+    pub fn compress(cloud: &GaussianCloud) -> Self {
+        let mut splat = CompressedSplat::new(cloud.sh_degree);
+        for gaussians in cloud.gaussians.chunks(CHUNK_SIZE) {
+            let (min_position, max_position) = bounds(gaussians, |g| g.position);
+            let (min_scale, max_scale) = bounds(gaussians, |g| g.scale);
+            let (min_color, max_color) = bounds(gaussians, color);
+            let chunk = SplatChunk {
+                min_position,
+                max_position,
+                min_scale,
+                max_scale,
+                min_color,
+                max_color,
+            };
+            for g in gaussians {
+                let rel = |v: [f32; 3], min: [f32; 3], max: [f32; 3]| {
+                    [
+                        normalize(v[0], min[0], max[0]),
+                        normalize(v[1], min[1], max[1]),
+                        normalize(v[2], min[2], max[2]),
+                    ]
+                };
+                let c = rel(color(g), min_color, max_color);
+                splat.vertices.push(PackedGaussian {
+                    packed_position: pack_111011(rel(g.position, min_position, max_position)),
+                    packed_rotation: pack_rotation(g.rotation),
+                    packed_scale: pack_111011(rel(g.scale, min_scale, max_scale)),
+                    packed_color: pack_8888([c[0], c[1], c[2], sigmoid(g.opacity)]),
+                });
+                if cloud.sh_degree > 0 {
+                    splat.sh.push(PackedSh {
+                        coefficients: g.sh_rest.iter().map(|v| pack_sh(*v)).collect(),
+                    });
+                }
+            }
+            splat.chunks.push(chunk);
+        }
+        splat
+    }
+
+    /// Restores full precision Gaussians, normals are set to zero.
+    pub fn decompress(&self) -> GaussianCloud {
+        let mut cloud = GaussianCloud::new(self.sh_degree);
+        for (i, v) in self.vertices.iter().enumerate() {
+            let chunk = match self.chunks.get(i / CHUNK_SIZE) {
+                Some(c) => c,
+                None => break,
+            };
+            let abs = |t: [f32; 3], min: [f32; 3], max: [f32; 3]| {
+                [
+                    lerp(min[0], max[0], t[0]),
+                    lerp(min[1], max[1], t[1]),
+                    lerp(min[2], max[2], t[2]),
+                ]
+            };
+            let c = unpack_8888(v.packed_color);
+            let rgb = abs([c[0], c[1], c[2]], chunk.min_color, chunk.max_color);
+            let mut g = Gaussian::new(self.sh_degree);
+            g.position = abs(
+                unpack_111011(v.packed_position),
+                chunk.min_position,
+                chunk.max_position,
+            );
+            g.scale = abs(
+                unpack_111011(v.packed_scale),
+                chunk.min_scale,
+                chunk.max_scale,
+            );
+            g.rotation = unpack_rotation(v.packed_rotation);
+            g.sh_dc = [
+                (rgb[0] - 0.5) / SH_C0,
+                (rgb[1] - 0.5) / SH_C0,
+                (rgb[2] - 0.5) / SH_C0,
+            ];
+            g.opacity = inverse_sigmoid(c[3]);
+            if let Some(sh) = self.sh.get(i) {
+                for (dst, src) in g.sh_rest.iter_mut().zip(sh.coefficients.iter()) {
+                    *dst = unpack_sh(*src);
+                }
+            }
+            cloud.gaussians.push(g);
+        }
+        cloud
+    }
+
+    /// Creates a header describing this compressed splat.
+    pub fn header(&self, encoding: Encoding) -> Header {
+        let mut header = Header::new();
+        header.encoding = encoding;
+
+        let mut chunk = ElementDef::new("chunk");
+        chunk.count = self.chunks.len();
+        chunk.properties = CHUNK_PROPERTIES
+            .iter()
+            .map(|n| PropertyDef::new(n, PropertyType::Scalar(ScalarType::Float)))
+            .collect();
+        header.elements.push(chunk);
+
+        let mut vertex = ElementDef::new("vertex");
+        vertex.count = self.vertices.len();
+        vertex.properties = PACKED_PROPERTIES
+            .iter()
+            .map(|n| PropertyDef::new(n, PropertyType::Scalar(ScalarType::UInt)))
+            .collect();
+        header.elements.push(vertex);
+
+        if self.sh_degree > 0 {
+            let mut sh = ElementDef::new("sh");
+            sh.count = self.sh.len();
+            sh.properties = (0..sh_rest_count(self.sh_degree))
+                .map(|i| {
+                    PropertyDef::new(
+                        &format!("f_rest_{}", i),
+                        PropertyType::Scalar(ScalarType::UChar),
+                    )
+                })
+                .collect();
+            header.elements.push(sh);
+        }
+        header
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn rotation_round_trip_ok() {
+        for q in [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, -1.0],
+            [0.5, -0.5, 0.5, 0.5],
+            [0.1, 0.7, -0.1, 0.7],
+        ] {
+            let r = unpack_rotation(pack_rotation(q));
+            // q and -q describe the same rotation
+            let dot: f32 = q.iter().zip(r.iter()).map(|(a, b)| a * b).sum();
+            assert!((dot.abs() - 1.0).abs() < 1e-3, "{:?} -> {:?}", q, r);
+        }
+    }
+    #[test]
+    fn unorm_round_trip_ok() {
+        assert_eq!(unpack_111011(pack_111011([0.0, 1.0, 0.0])), [0.0, 1.0, 0.0]);
+        assert_eq!(
+            unpack_8888(pack_8888([1.0, 0.0, 1.0, 0.0])),
+            [1.0, 0.0, 1.0, 0.0]
+        );
+    }
+}
@@ -0,0 +1,711 @@
+//! Reads and writes 3D Gaussian Splatting checkpoints.
+//!
+//! Splatting checkpoints are PLY files with a single `vertex` element.
+//! Each vertex is one Gaussian described by the following float properties:
+//!
+//! - position: `x y z`
+//! - normal: `nx ny nz` (usually all zero, but present in most files)
+//! - spherical harmonics: `f_dc_0..2` for the base color and `f_rest_0..N` for the higher bands
+//! - `opacity` before the sigmoid activation
+//! - `scale_0..2` in log space
+//! - rotation quaternion: `rot_0..3`
+//!
+//! Some viewers use a compressed layout with `chunk` elements and packed `uint` properties,
+//! see `CompressedSplat`.
+//!
+//! Both layouts are read into typed structs directly, without going through `DefaultElement`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::splat::{Gaussian, GaussianCloud, SplatParser, SplatWriter};
+//! # use ply_rs::ply::Encoding;
+//! let mut cloud = GaussianCloud::new(1);
+//! cloud.gaussians.push(Gaussian::new(1));
+//!
+//! let mut buf = Vec::<u8>::new();
+//! let w = SplatWriter::new();
+//! w.write_splat(&mut buf, &cloud, Encoding::BinaryLittleEndian).unwrap();
+//!
+//! let p = SplatParser::new();
+//! let read = p.read_splat(&mut buf.as_slice()).unwrap();
+//! assert_eq!(read, cloud);
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Result, Write};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::parser::Parser;
+use crate::ply::{
+    ElementDef, Encoding, Header, Property, PropertyAccess, PropertyDef, PropertyType, ScalarType,
+};
+use crate::writer::Writer;
+
+mod compressed;
+pub use self::compressed::*;
+
+/// Zeroth order spherical harmonics constant, converts `f_dc_*` to linear color and back.
+pub const SH_C0: f32 = 0.282_094_8;
+
+/// Highest spherical harmonics degree read from files, checkpoints of the reference implementation use 3.
+pub const MAX_SH_DEGREE: u8 = 3;
+
+/// Number of `f_rest_*` properties needed to store spherical harmonics of the given degree.
+///
+/// Each color channel stores `(degree + 1)^2 - 1` coefficients beyond the `f_dc_*` term.
+pub const fn sh_rest_count(degree: u8) -> usize {
+    let per_channel = (degree as usize + 1) * (degree as usize + 1) - 1;
+    3 * per_channel
+}
+
+/// Inverse of `sh_rest_count()`.
+///
+/// Returns `None` if `rest_count` doesn't correspond to any degree.
+pub fn sh_degree_from_rest_count(rest_count: usize) -> Option<u8> {
+    (0..=u8::MAX)
+        .take_while(|d| sh_rest_count(*d) <= rest_count)
+        .find(|d| sh_rest_count(*d) == rest_count)
+}
+
+fn f_rest_index(name: &str) -> Option<usize> {
+    name.strip_prefix("f_rest_")?.parse().ok()
+}
+
+fn count_f_rest(element: &ElementDef) -> usize {
+    element
+        .properties
+        .iter()
+        .filter(|p| f_rest_index(&p.name).is_some())
+        .count()
+}
+
+/// Upper bound of `f_rest_*` indices, elements never grow beyond it.
+const MAX_SH_REST_COUNT: usize = sh_rest_count(MAX_SH_DEGREE);
+
+/// Rejects `f_rest_*` indices the element can't hold, they come straight from the header.
+fn check_f_rest(element: &ElementDef) -> Result<()> {
+    let limit = count_f_rest(element).min(MAX_SH_REST_COUNT);
+    for p in &element.properties {
+        if f_rest_index(&p.name).is_some_and(|i| i >= limit) {
+            return invalid_input(format!(
+                "Property '{}' of element '{}' is out of range, expected at most {} spherical harmonics coefficients (degree {}).",
+                p.name, element.name, limit, MAX_SH_DEGREE
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Layout of a splatting checkpoint as detected from its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplatLayout {
+    /// One float per attribute in the `vertex` element.
+    Full {
+        /// Degree of the spherical harmonics, between 0 and 3 for common checkpoints.
+        sh_degree: u8,
+    },
+    /// `chunk` element with quantization bounds, packed `uint` vertices and an optional `sh` element.
+    Compressed {
+        /// Degree of the spherical harmonics stored in the `sh` element.
+        sh_degree: u8,
+    },
+}
+
+impl SplatLayout {
+    /// Inspects the element definitions of `header` and detects the splat layout.
+    ///
+    /// Returns `None` if the header doesn't describe a splatting checkpoint.
+    pub fn detect(header: &Header) -> Option<SplatLayout> {
+        let vertex = header.elements.iter().find(|e| e.name == "vertex")?;
+        let has = |e: &ElementDef, name: &str| e.properties.iter().any(|p| p.name == name);
+        if header.elements.iter().any(|e| e.name == "chunk") {
+            if !PACKED_PROPERTIES.iter().all(|n| has(vertex, n)) {
+                return None;
+            }
+            let sh_degree = match header.elements.iter().find(|e| e.name == "sh") {
+                Some(sh) => sh_degree_from_rest_count(count_f_rest(sh))?,
+                None => 0,
+            };
+            return Some(SplatLayout::Compressed { sh_degree });
+        }
+        let required = [
+            "x", "y", "z", "f_dc_0", "f_dc_1", "f_dc_2", "opacity", "scale_0", "scale_1",
+            "scale_2", "rot_0", "rot_1", "rot_2", "rot_3",
+        ];
+        if !required.iter().all(|n| has(vertex, n)) {
+            return None;
+        }
+        let sh_degree = sh_degree_from_rest_count(count_f_rest(vertex))?;
+        Some(SplatLayout::Full { sh_degree })
+    }
+
+    /// Degree of the spherical harmonics, independent of the layout.
+    pub fn sh_degree(&self) -> u8 {
+        match *self {
+            SplatLayout::Full { sh_degree } => sh_degree,
+            SplatLayout::Compressed { sh_degree } => sh_degree,
+        }
+    }
+}
+
+/// A single Gaussian of a splatting checkpoint.
+///
+/// All values are stored as found in the file, no activation functions are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gaussian {
+    /// Center: `x y z`.
+    pub position: [f32; 3],
+    /// `nx ny nz`, unused by most renderers.
+    pub normal: [f32; 3],
+    /// Zeroth order spherical harmonics: `f_dc_0..2`.
+    pub sh_dc: [f32; 3],
+    /// Higher order spherical harmonics: `f_rest_0..N` in file order.
+    ///
+    /// The coefficients are grouped by channel: first all red, then all green, then all blue coefficients.
+    pub sh_rest: Vec<f32>,
+    /// Opacity before the sigmoid activation.
+    pub opacity: f32,
+    /// Scale in log space: `scale_0..2`.
+    pub scale: [f32; 3],
+    /// Rotation quaternion: `rot_0..3`, `rot_0` is the real part.
+    pub rotation: [f32; 4],
+}
+
+impl Gaussian {
+    /// Creates an identity Gaussian at the origin with zeroed spherical harmonics of the given degree.
+    pub fn new(sh_degree: u8) -> Self {
+        Gaussian {
+            position: [0.0; 3],
+            normal: [0.0; 3],
+            sh_dc: [0.0; 3],
+            sh_rest: vec![0.0; sh_rest_count(sh_degree)],
+            opacity: 0.0,
+            scale: [0.0; 3],
+            rotation: [1.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    fn value_mut(&mut self, target: Target) -> Option<&mut f32> {
+        Some(match target {
+            Target::Position(i) => &mut self.position[i],
+            Target::Normal(i) => &mut self.normal[i],
+            Target::Dc(i) => &mut self.sh_dc[i],
+            Target::Rest(i) if i >= MAX_SH_REST_COUNT => return None,
+            Target::Rest(i) => {
+                if self.sh_rest.len() <= i {
+                    self.sh_rest.resize(i + 1, 0.0);
+                }
+                &mut self.sh_rest[i]
+            }
+            Target::Opacity => &mut self.opacity,
+            Target::Scale(i) => &mut self.scale[i],
+            Target::Rotation(i) => &mut self.rotation[i],
+            Target::Ignore => return None,
+        })
+    }
+
+    fn value(&self, target: Target) -> Option<f32> {
+        match target {
+            Target::Position(i) => Some(self.position[i]),
+            Target::Normal(i) => Some(self.normal[i]),
+            Target::Dc(i) => Some(self.sh_dc[i]),
+            Target::Rest(i) => self.sh_rest.get(i).cloned(),
+            Target::Opacity => Some(self.opacity),
+            Target::Scale(i) => Some(self.scale[i]),
+            Target::Rotation(i) => Some(self.rotation[i]),
+            Target::Ignore => None,
+        }
+    }
+}
+
+/// Where a property of the `vertex` element ends up in a `Gaussian`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Position(usize),
+    Normal(usize),
+    Dc(usize),
+    Rest(usize),
+    Opacity,
+    Scale(usize),
+    Rotation(usize),
+    Ignore,
+}
+
+impl Target {
+    fn from_name(name: &str) -> Target {
+        match name {
+            "x" => Target::Position(0),
+            "y" => Target::Position(1),
+            "z" => Target::Position(2),
+            "nx" => Target::Normal(0),
+            "ny" => Target::Normal(1),
+            "nz" => Target::Normal(2),
+            "f_dc_0" => Target::Dc(0),
+            "f_dc_1" => Target::Dc(1),
+            "f_dc_2" => Target::Dc(2),
+            "opacity" => Target::Opacity,
+            "scale_0" => Target::Scale(0),
+            "scale_1" => Target::Scale(1),
+            "scale_2" => Target::Scale(2),
+            "rot_0" => Target::Rotation(0),
+            "rot_1" => Target::Rotation(1),
+            "rot_2" => Target::Rotation(2),
+            "rot_3" => Target::Rotation(3),
+            n => match f_rest_index(n) {
+                Some(i) => Target::Rest(i),
+                None => Target::Ignore,
+            },
+        }
+    }
+}
+
+impl PropertyAccess for Gaussian {
+    fn new() -> Self {
+        Gaussian::new(0)
+    }
+    fn set_property(&mut self, property_name: &str, property: Property) {
+        let v = match property {
+            Property::Float(v) => v,
+            Property::Double(v) => v as f32,
+            _ => return,
+        };
+        if let Some(slot) = self.value_mut(Target::from_name(property_name)) {
+            *slot = v;
+        }
+    }
+    fn get_float(&self, property_name: &str) -> Option<f32> {
+        self.value(Target::from_name(property_name))
+    }
+}
+
+/// A complete splatting checkpoint.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GaussianCloud {
+    /// Degree of the spherical harmonics, every Gaussian stores `sh_rest_count(sh_degree)` rest coefficients.
+    pub sh_degree: u8,
+    /// The Gaussians in file order.
+    pub gaussians: Vec<Gaussian>,
+}
+
+impl GaussianCloud {
+    /// Creates an empty cloud with the given spherical harmonics degree.
+    pub fn new(sh_degree: u8) -> Self {
+        GaussianCloud {
+            sh_degree,
+            gaussians: Vec::new(),
+        }
+    }
+
+    /// Creates the element definition of the `vertex` element as written by the reference implementation.
+    pub fn element_def(&self) -> ElementDef {
+        let mut names: Vec<String> = [
+            "x", "y", "z", "nx", "ny", "nz", "f_dc_0", "f_dc_1", "f_dc_2",
+        ]
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+        names.extend((0..sh_rest_count(self.sh_degree)).map(|i| format!("f_rest_{}", i)));
+        names.push("opacity".to_string());
+        names.extend((0..3).map(|i| format!("scale_{}", i)));
+        names.extend((0..4).map(|i| format!("rot_{}", i)));
+
+        let mut e = ElementDef::new("vertex");
+        e.count = self.gaussians.len();
+        e.properties = names
+            .iter()
+            .map(|n| PropertyDef::new(n, PropertyType::Scalar(ScalarType::Float)))
+            .collect();
+        e
+    }
+
+    /// Creates a header describing this cloud.
+    pub fn header(&self, encoding: Encoding) -> Header {
+        let mut header = Header::new();
+        header.encoding = encoding;
+        header.elements.push(self.element_def());
+        header
+    }
+}
+
+fn invalid_input<T>(message: String) -> Result<T> {
+    Err(io::Error::new(ErrorKind::InvalidInput, message))
+}
+
+/// Reads splatting checkpoints in either layout.
+///
+/// Binary files whose `vertex` element only consists of scalar properties are decoded record by record,
+/// everything else is read through the generic `Parser`.
+#[derive(Debug, Default)]
+pub struct SplatParser {}
+
+impl SplatParser {
+    /// Creates a new `SplatParser`.
+    pub fn new() -> Self {
+        SplatParser {}
+    }
+
+    /// Reads an entire splatting checkpoint, compressed files are decompressed.
+    pub fn read_splat<T: Read>(&self, source: &mut T) -> Result<GaussianCloud> {
+        let mut reader = BufReader::new(source);
+        let header = Parser::<Gaussian>::new().read_header(&mut reader)?;
+        match SplatLayout::detect(&header) {
+            Some(SplatLayout::Full { sh_degree }) => Ok(GaussianCloud {
+                sh_degree,
+                gaussians: self.read_gaussians(&mut reader, &header)?,
+            }),
+            Some(SplatLayout::Compressed { .. }) => {
+                Ok(self.read_compressed(&mut reader, &header)?.decompress())
+            }
+            None => invalid_input("Header doesn't describe a Gaussian splatting file.".to_string()),
+        }
+    }
+
+    /// Reads the payload of a full layout file, the reader must be positioned after the header.
+    ///
+    /// Elements other than `vertex` are skipped.
+    pub fn read_gaussians<T: BufRead>(
+        &self,
+        reader: &mut T,
+        header: &Header,
+    ) -> Result<Vec<Gaussian>> {
+        let sh_degree = match SplatLayout::detect(header) {
+            Some(SplatLayout::Full { sh_degree }) => sh_degree,
+            _ => {
+                return invalid_input(
+                    "Header doesn't describe a full layout Gaussian splatting file.".to_string(),
+                )
+            }
+        };
+        let mut gaussians = Vec::new();
+        for element_def in &header.elements {
+            if element_def.name != "vertex" {
+                Parser::<crate::ply::DefaultElement>::new().read_payload_for_element(
+                    reader,
+                    element_def,
+                    header,
+                )?;
+                continue;
+            }
+            check_f_rest(element_def)?;
+            gaussians = match header.encoding {
+                Encoding::Ascii => Parser::<Gaussian>::new().read_payload_for_element(
+                    reader,
+                    element_def,
+                    header,
+                )?,
+                Encoding::BinaryBigEndian => {
+                    self.__read_binary_gaussians::<T, BigEndian>(reader, element_def, sh_degree)?
+                }
+                Encoding::BinaryLittleEndian => {
+                    self.__read_binary_gaussians::<T, LittleEndian>(reader, element_def, sh_degree)?
+                }
+            };
+        }
+        Ok(gaussians)
+    }
+
+    /// Reads the payload of a compressed file, the reader must be positioned after the header.
+    pub fn read_compressed<T: BufRead>(
+        &self,
+        reader: &mut T,
+        header: &Header,
+    ) -> Result<CompressedSplat> {
+        let sh_degree = match SplatLayout::detect(header) {
+            Some(SplatLayout::Compressed { sh_degree }) => sh_degree,
+            _ => {
+                return invalid_input(
+                    "Header doesn't describe a compressed Gaussian splatting file.".to_string(),
+                )
+            }
+        };
+        let mut splat = CompressedSplat::new(sh_degree);
+        for element_def in &header.elements {
+            match element_def.name.as_ref() {
+                "chunk" => {
+                    splat.chunks = Parser::<SplatChunk>::new().read_payload_for_element(
+                        reader,
+                        element_def,
+                        header,
+                    )?
+                }
+                "vertex" => {
+                    splat.vertices = Parser::<PackedGaussian>::new().read_payload_for_element(
+                        reader,
+                        element_def,
+                        header,
+                    )?
+                }
+                "sh" => {
+                    check_f_rest(element_def)?;
+                    splat.sh = Parser::<PackedSh>::new().read_payload_for_element(
+                        reader,
+                        element_def,
+                        header,
+                    )?
+                }
+                _ => {
+                    Parser::<crate::ply::DefaultElement>::new().read_payload_for_element(
+                        reader,
+                        element_def,
+                        header,
+                    )?;
+                }
+            }
+        }
+        Ok(splat)
+    }
+
+    fn __read_binary_gaussians<T: Read, B: ByteOrder>(
+        &self,
+        reader: &mut T,
+        element_def: &ElementDef,
+        sh_degree: u8,
+    ) -> Result<Vec<Gaussian>> {
+        // (offset in record, type, target)
+        let mut layout = Vec::with_capacity(element_def.properties.len());
+        let mut record_size = 0;
+        for def in &element_def.properties {
+            let scalar_type = match def.data_type {
+                PropertyType::Scalar(s) => s,
+                PropertyType::List(_, _) => {
+                    return invalid_input(format!(
+                        "Unexpected list property '{}' in Gaussian splatting file.",
+                        def.name
+                    ))
+                }
+            };
+            layout.push((record_size, scalar_type, Target::from_name(&def.name)));
+            record_size += scalar_type.byte_size();
+        }
+
+        let mut record = vec![0u8; record_size];
+        let mut gaussians = Vec::with_capacity(element_def.count);
+        for _ in 0..element_def.count {
+            reader.read_exact(&mut record)?;
+            let mut g = Gaussian::new(sh_degree);
+            for &(offset, scalar_type, target) in &layout {
+                if let Some(slot) = g.value_mut(target) {
+                    *slot = read_scalar_as_f32::<B>(&record[offset..], scalar_type);
+                }
+            }
+            gaussians.push(g);
+        }
+        Ok(gaussians)
+    }
+}
+
+fn read_scalar_as_f32<B: ByteOrder>(buf: &[u8], scalar_type: ScalarType) -> f32 {
+    match scalar_type {
+        ScalarType::Char => buf[0] as i8 as f32,
+        ScalarType::UChar => buf[0] as f32,
+        ScalarType::Short => B::read_i16(buf) as f32,
+        ScalarType::UShort => B::read_u16(buf) as f32,
+        ScalarType::Int => B::read_i32(buf) as f32,
+        ScalarType::UInt => B::read_u32(buf) as f32,
+        ScalarType::Float => B::read_f32(buf),
+        ScalarType::Double => B::read_f64(buf) as f32,
+    }
+}
+
+/// Writes splatting checkpoints in either layout.
+#[derive(Debug, Default)]
+pub struct SplatWriter {}
+
+impl SplatWriter {
+    /// Creates a new `SplatWriter`.
+    pub fn new() -> Self {
+        SplatWriter {}
+    }
+
+    /// Writes `cloud` in the full layout with the given encoding.
+    ///
+    /// Returns number of bytes written.
+    pub fn write_splat<T: Write>(
+        &self,
+        out: &mut T,
+        cloud: &GaussianCloud,
+        encoding: Encoding,
+    ) -> Result<usize> {
+        let expected = sh_rest_count(cloud.sh_degree);
+        if let Some(g) = cloud.gaussians.iter().find(|g| g.sh_rest.len() != expected) {
+            return invalid_input(format!(
+                "Gaussian has {} rest coefficients, but degree {} requires {}.",
+                g.sh_rest.len(),
+                cloud.sh_degree,
+                expected
+            ));
+        }
+        let header = cloud.header(encoding);
+        let w = Writer::<Gaussian>::new();
+        let mut written = w.write_header(out, &header)?;
+        written +=
+            w.write_payload_of_element(out, &cloud.gaussians, &header.elements[0], &header)?;
+        out.flush()?;
+        Ok(written)
+    }
+
+    /// Writes `splat` in the compressed layout with the given encoding.
+    ///
+    /// Returns number of bytes written.
+    pub fn write_compressed<T: Write>(
+        &self,
+        out: &mut T,
+        splat: &CompressedSplat,
+        encoding: Encoding,
+    ) -> Result<usize> {
+        let header = splat.header(encoding);
+        let mut written = Writer::<SplatChunk>::new().write_header(out, &header)?;
+        for element_def in &header.elements {
+            written += match element_def.name.as_ref() {
+                "chunk" => Writer::<SplatChunk>::new().write_payload_of_element(
+                    out,
+                    &splat.chunks,
+                    element_def,
+                    &header,
+                )?,
+                "vertex" => Writer::<PackedGaussian>::new().write_payload_of_element(
+                    out,
+                    &splat.vertices,
+                    element_def,
+                    &header,
+                )?,
+                _ => Writer::<PackedSh>::new().write_payload_of_element(
+                    out,
+                    &splat.sh,
+                    element_def,
+                    &header,
+                )?,
+            };
+        }
+        out.flush()?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cloud(sh_degree: u8) -> GaussianCloud {
+        let mut cloud = GaussianCloud::new(sh_degree);
+        for i in 0..600 {
+            let f = i as f32;
+            let mut g = Gaussian::new(sh_degree);
+            g.position = [f * 0.01, -f * 0.02, (f * 0.1).sin()];
+            g.sh_dc = [0.5, -0.25, (f * 0.3).cos()];
+            for (k, c) in g.sh_rest.iter_mut().enumerate() {
+                *c = ((k as f32 + f) * 0.7).sin() * 0.5;
+            }
+            g.opacity = (f * 0.05).sin() * 3.0;
+            g.scale = [-4.0 + f * 0.001, -3.0, -5.0];
+            let q = [1.0, f * 0.001, -0.3, 0.2];
+            let n = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+            g.rotation = [q[0] / n, q[1] / n, q[2] / n, q[3] / n];
+            cloud.gaussians.push(g);
+        }
+        cloud
+    }
+
+    #[test]
+    fn sh_degree_ok() {
+        assert_eq!(sh_rest_count(0), 0);
+        assert_eq!(sh_rest_count(1), 9);
+        assert_eq!(sh_rest_count(2), 24);
+        assert_eq!(sh_rest_count(3), 45);
+        assert_eq!(sh_degree_from_rest_count(45), Some(3));
+        assert_eq!(sh_degree_from_rest_count(0), Some(0));
+        assert_eq!(sh_degree_from_rest_count(10), None);
+    }
+    #[test]
+    fn detect_full_ok() {
+        let cloud = sample_cloud(3);
+        assert_eq!(
+            SplatLayout::detect(&cloud.header(Encoding::Ascii)),
+            Some(SplatLayout::Full { sh_degree: 3 })
+        );
+    }
+    #[test]
+    fn detect_compressed_ok() {
+        let splat = CompressedSplat::compress(&sample_cloud(1));
+        assert_eq!(
+            SplatLayout::detect(&splat.header(Encoding::BinaryLittleEndian)),
+            Some(SplatLayout::Compressed { sh_degree: 1 })
+        );
+    }
+    #[test]
+    fn detect_err() {
+        let mut header = Header::new();
+        header.elements.push(ElementDef::new("vertex"));
+        assert_eq!(SplatLayout::detect(&header), None);
+    }
+    #[test]
+    fn f_rest_out_of_range_err() {
+        let cloud = sample_cloud(1);
+        for encoding in [Encoding::Ascii, Encoding::BinaryLittleEndian] {
+            let mut buf = Vec::<u8>::new();
+            SplatWriter::new()
+                .write_splat(&mut buf, &cloud, encoding)
+                .unwrap();
+            let pos = buf.windows(9).position(|w| w == b"f_rest_8\n").unwrap();
+            buf.splice(pos..pos + 8, b"f_rest_4000000000".iter().cloned());
+            let e = SplatParser::new()
+                .read_splat(&mut buf.as_slice())
+                .unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidInput);
+            assert!(e.to_string().contains("'f_rest_4000000000'"), "{}", e);
+        }
+        let mut g = Gaussian::new(0);
+        g.set_property("f_rest_4000000000", Property::Float(1.0));
+        assert!(g.sh_rest.is_empty());
+        let mut sh = PackedSh::default();
+        sh.set_property("f_rest_4000000000", Property::UChar(1));
+        assert!(sh.coefficients.is_empty());
+    }
+    #[test]
+    fn full_round_trip_ok() {
+        let cloud = sample_cloud(2);
+        for encoding in [
+            Encoding::Ascii,
+            Encoding::BinaryBigEndian,
+            Encoding::BinaryLittleEndian,
+        ] {
+            let mut buf = Vec::<u8>::new();
+            SplatWriter::new()
+                .write_splat(&mut buf, &cloud, encoding)
+                .unwrap();
+            let read = SplatParser::new().read_splat(&mut buf.as_slice()).unwrap();
+            assert_eq!(read, cloud);
+        }
+    }
+    #[test]
+    fn compressed_round_trip_ok() {
+        let cloud = sample_cloud(1);
+        let splat = CompressedSplat::compress(&cloud);
+        assert_eq!(splat.chunks.len(), 3);
+
+        let mut buf = Vec::<u8>::new();
+        SplatWriter::new()
+            .write_compressed(&mut buf, &splat, Encoding::BinaryLittleEndian)
+            .unwrap();
+        let read = SplatParser::new().read_splat(&mut buf.as_slice()).unwrap();
+        assert_eq!(read.sh_degree, 1);
+        assert_eq!(read.gaussians.len(), cloud.gaussians.len());
+        for (a, b) in read.gaussians.iter().zip(cloud.gaussians.iter()) {
+            for i in 0..3 {
+                assert!((a.position[i] - b.position[i]).abs() < 1e-2);
+                assert!((a.scale[i] - b.scale[i]).abs() < 1e-2);
+                assert!((a.sh_dc[i] - b.sh_dc[i]).abs() < 2e-2);
+            }
+            for i in 0..4 {
+                assert!((a.rotation[i] - b.rotation[i]).abs() < 2e-3);
+            }
+            for (x, y) in a.sh_rest.iter().zip(b.sh_rest.iter()) {
+                assert!((x - y).abs() < 2e-2);
+            }
+            let alpha = |o: f32| 1.0 / (1.0 + (-o).exp());
+            assert!((alpha(a.opacity) - alpha(b.opacity)).abs() < 1e-2);
+        }
+    }
+}
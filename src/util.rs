@@ -1,12 +1,17 @@
 #[derive(Debug, Clone, Copy)]
 pub struct LocationTracker {
     pub line_index: usize,
+    pub column_index: usize,
 }
 impl LocationTracker {
     pub fn new() -> Self {
-        LocationTracker { line_index: 0 }
+        LocationTracker {
+            line_index: 0,
+            column_index: 0,
+        }
     }
     pub fn next_line(&mut self) {
         self.line_index += 1;
+        self.column_index = 0;
     }
 }
@@ -0,0 +1,168 @@
+//! Writes PLY files to tokio's `AsyncWrite`.
+
+use super::Writer;
+use crate::ply::{ElementDef, Header, HeaderLayout, Payload, Ply, PropertyAccess};
+use std::io;
+use std::io::{ErrorKind, Result};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Number of encoded bytes collected before they are handed to the output.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes a PLY file to an `AsyncWrite`, available with the `tokio` feature.
+///
+/// Elements are encoded by `Writer` and passed on in chunks of at most 64 KiB,
+/// each awaited before the next one is encoded, so a slow output throttles the writer.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut ply = ply::Ply::<ply::DefaultElement>::new();
+/// let mut buf = Vec::<u8>::new();
+/// let w = writer::AsyncWriter::new();
+/// let written = w.write_ply(&mut buf, &mut ply).await.unwrap();
+/// assert_eq!(written, buf.len());
+/// # });
+/// ```
+#[derive(Default)]
+pub struct AsyncWriter<E: PropertyAccess> {
+    writer: Writer<E>,
+}
+
+impl<E: PropertyAccess> AsyncWriter<E> {
+    /// Create a new `AsyncWriter<E>` where `E` is the element type.
+    pub fn new() -> Self {
+        AsyncWriter {
+            writer: Writer::new(),
+        }
+    }
+
+    /// Writes an entire PLY file and flushes `out`, see `Writer::write_ply()`.
+    pub async fn write_ply<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        ply: &mut Ply<E>,
+    ) -> Result<usize> {
+        match ply.make_consistent() {
+            Ok(()) => (),
+            Err(e) => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("The given ply isn't consistent: {:?}", e),
+                ))
+            }
+        };
+        self.write_ply_unchecked(out, ply).await
+    }
+
+    /// Like `write_ply()`, but doesn't check the input for inconsistency.
+    pub async fn write_ply_unchecked<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        ply: &Ply<E>,
+    ) -> Result<usize> {
+        let mut written = 0;
+        written += self.write_header(out, &ply.header).await?;
+        written += self.write_payload(out, &ply.payload, &ply.header).await?;
+        out.flush().await?;
+        Ok(written)
+    }
+
+    /// Writes all header lines, see `Writer::write_header()`.
+    pub async fn write_header<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        header: &Header,
+    ) -> Result<usize> {
+        let mut buf = Vec::new();
+        self.writer.write_header(&mut buf, header)?;
+        out.write_all(&buf).await?;
+        Ok(buf.len())
+    }
+
+    /// Writes the header in its recorded layout, see `Writer::write_header_with_layout()`.
+    pub async fn write_header_with_layout<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        header: &Header,
+        layout: &HeaderLayout,
+    ) -> Result<usize> {
+        let mut buf = Vec::new();
+        self.writer
+            .write_header_with_layout(&mut buf, header, layout)?;
+        out.write_all(&buf).await?;
+        Ok(buf.len())
+    }
+
+    /// Writes the payload, see `Writer::write_payload()`.
+    pub async fn write_payload<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        payload: &Payload<E>,
+        header: &Header,
+    ) -> Result<usize> {
+        let mut written = 0;
+        for (k, element_list) in payload {
+            let element_def = header.elements.iter().find(|x| x.name == *k).unwrap();
+            written += self
+                .write_payload_of_element(out, element_list, element_def, header)
+                .await?;
+        }
+        Ok(written)
+    }
+
+    /// Writes all elements of one type, see `Writer::write_payload_of_element()`.
+    pub async fn write_payload_of_element<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        element_list: &[E],
+        element_def: &ElementDef,
+        header: &Header,
+    ) -> Result<usize> {
+        let mut written = 0;
+        let mut buf = Vec::with_capacity(CHUNK_SIZE);
+        let schema = E::bind_schema(element_def);
+        for element in element_list {
+            self.writer.__write_element(
+                &mut buf,
+                element,
+                element_def,
+                schema.as_ref(),
+                header.encoding,
+            )?;
+            if buf.len() >= CHUNK_SIZE {
+                out.write_all(&buf).await?;
+                written += buf.len();
+                buf.clear();
+            }
+        }
+        out.write_all(&buf).await?;
+        written += buf.len();
+        Ok(written)
+    }
+
+    /// Writes a single element. Encoding is chosen according to `header`.
+    ///
+    /// Together with `write_header()` this allows to stream a file element by element.
+    pub async fn write_element<T: AsyncWrite + Unpin>(
+        &self,
+        out: &mut T,
+        element: &E,
+        element_def: &ElementDef,
+        header: &Header,
+    ) -> Result<usize> {
+        let mut buf = Vec::new();
+        let schema = E::bind_schema(element_def);
+        self.writer.__write_element(
+            &mut buf,
+            element,
+            element_def,
+            schema.as_ref(),
+            header.encoding,
+        )?;
+        out.write_all(&buf).await?;
+        Ok(buf.len())
+    }
+}
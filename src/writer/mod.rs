@@ -52,8 +52,16 @@ use ply::PropertyAccess;
 use std::io;
 use std::io::{ErrorKind, Result, Write};
 
+#[cfg(feature = "tokio")]
+mod async_writer;
+#[cfg(feature = "tokio")]
+pub use self::async_writer::AsyncWriter;
+
 use crate::ply::Ply;
 
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::compression::{Compression, Compressor};
+
 // ////////////////////////////
 // General
 // /////////////
@@ -96,6 +104,21 @@ impl<E: PropertyAccess> Writer<E> {
         out.flush().unwrap();
         Ok(written)
     }
+    /// Writes an entire PLY file like `write_ply()`, compressed as given by `compression`.
+    ///
+    /// Returns the number of bytes before compression.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn write_ply_compressed<T: Write>(
+        &self,
+        out: &mut T,
+        ply: &mut Ply<E>,
+        compression: Compression,
+    ) -> Result<usize> {
+        let mut out = Compressor::new(out, compression)?;
+        let written = self.write_ply(&mut out, ply)?;
+        out.finish()?.flush()?;
+        Ok(written)
+    }
     fn write_new_line<T: Write>(&self, out: &mut T) -> Result<usize> {
         out.write(self.new_line.as_bytes())
     }
@@ -109,7 +132,8 @@ use super::general;
 use ply::PropertyAccess;
 // */
 use crate::ply::{
-    Comment, ElementDef, Encoding, Header, ObjInfo, PropertyDef, PropertyType, ScalarType, Version,
+    Comment, ElementDef, Encoding, Header, HeaderLayout, HeaderLine, LayoutLine, ObjInfo,
+    PropertyDef, PropertyType, ScalarType, Version,
 };
 
 // ////////////////////////
@@ -236,6 +260,170 @@ impl<E: PropertyAccess> Writer<E> {
         written += self.write_line_end_header(out)?;
         Ok(written)
     }
+    /// Writes `header` in the line order and spelling recorded in `layout`.
+    ///
+    /// Lines whose content didn't change are copied verbatim, so an unmodified header is reproduced byte for byte.
+    /// Changed lines are written like `write_header()` does.
+    /// New comments and obj_infos follow their predecessor in `header`, or the format line,
+    /// new properties end their element and new elements are written before `end_header`.
+    /// Entries missing in `header` are dropped.
+    ///
+    /// Elements and properties are always written in the order of `header`.
+    /// Make sure the header is consistent with the payload.
+    pub fn write_header_with_layout<T: Write>(
+        &self,
+        out: &mut T,
+        header: &Header,
+        layout: &HeaderLayout,
+    ) -> Result<usize> {
+        if layout.lines.is_empty() {
+            return self.write_header(out, header);
+        }
+        // if none of the recorded comments or obj_infos are kept, new ones follow the format line
+        let format_line = layout
+            .lines
+            .iter()
+            .position(|l| matches!(l.line, HeaderLine::Format(..)));
+        let kept = |l: &LayoutLine| match l.line {
+            HeaderLine::Comment(ref c) => header.comments.contains(c),
+            _ => false,
+        };
+        let mut comment_anchor = format_line.filter(|_| !layout.lines.iter().any(kept));
+        let kept = |l: &LayoutLine| match l.line {
+            HeaderLine::ObjInfo(ref o) => header.obj_infos.contains(o),
+            _ => false,
+        };
+        let mut obj_info_anchor = format_line.filter(|_| !layout.lines.iter().any(kept));
+
+        let mut written = 0;
+        let mut comments = &header.comments[..];
+        let mut obj_infos = &header.obj_infos[..];
+        let mut elements = &header.elements[..];
+        // properties of the current element that haven't been written yet
+        let mut properties: &[PropertyDef] = &[];
+        let mut end_header = None;
+        for (i, l) in layout.lines.iter().enumerate() {
+            match l.line {
+                HeaderLine::MagicNumber => written += out.write(l.text.as_bytes())?,
+                HeaderLine::Format(encoding, version) => {
+                    if encoding == header.encoding && version == header.version {
+                        written += out.write(l.text.as_bytes())?;
+                    } else {
+                        written +=
+                            self.write_line_format(out, &header.encoding, &header.version)?;
+                    }
+                }
+                HeaderLine::Comment(ref c) => {
+                    if let Some(k) = comments.iter().position(|h| h == c) {
+                        for h in &comments[..k] {
+                            written += self.write_line_comment(out, h)?;
+                        }
+                        written += out.write(l.text.as_bytes())?;
+                        comments = &comments[k + 1..];
+                        comment_anchor = Some(i);
+                    }
+                }
+                HeaderLine::ObjInfo(ref o) => {
+                    if let Some(k) = obj_infos.iter().position(|h| h == o) {
+                        for h in &obj_infos[..k] {
+                            written += self.write_line_obj_info(out, h)?;
+                        }
+                        written += out.write(l.text.as_bytes())?;
+                        obj_infos = &obj_infos[k + 1..];
+                        obj_info_anchor = Some(i);
+                    }
+                }
+                HeaderLine::Element(ref e) => {
+                    for p in properties {
+                        written += self.write_line_property_definition(out, p)?;
+                    }
+                    properties = &[];
+                    if let Some(k) = elements.iter().position(|h| h.name == e.name) {
+                        for h in &elements[..k] {
+                            written += self.write_element_definition(out, h)?;
+                        }
+                        let h = &elements[k];
+                        if h.count == e.count {
+                            written += out.write(l.text.as_bytes())?;
+                        } else {
+                            written += self.write_line_element_definition(out, h)?;
+                        }
+                        properties = &h.properties;
+                        elements = &elements[k + 1..];
+                    }
+                }
+                HeaderLine::Property(ref p) => {
+                    if let Some(k) = properties.iter().position(|h| h.name == p.name) {
+                        for h in &properties[..k] {
+                            written += self.write_line_property_definition(out, h)?;
+                        }
+                        if properties[k] == *p {
+                            written += out.write(l.text.as_bytes())?;
+                        } else {
+                            written += self.write_line_property_definition(out, &properties[k])?;
+                        }
+                        properties = &properties[k + 1..];
+                    }
+                }
+                HeaderLine::EndHeader => {
+                    end_header = Some(&l.text);
+                    break;
+                }
+            }
+            // new entries follow their predecessor, recorded ones are left for their own lines
+            if comment_anchor == Some(i) {
+                let later: Vec<&Comment> = layout.lines[i + 1..]
+                    .iter()
+                    .filter_map(|l| match l.line {
+                        HeaderLine::Comment(ref c) => Some(c),
+                        _ => None,
+                    })
+                    .collect();
+                let k = comments
+                    .iter()
+                    .position(|c| later.contains(&c))
+                    .unwrap_or(comments.len());
+                for c in &comments[..k] {
+                    written += self.write_line_comment(out, c)?;
+                }
+                comments = &comments[k..];
+            }
+            if obj_info_anchor == Some(i) {
+                let later: Vec<&ObjInfo> = layout.lines[i + 1..]
+                    .iter()
+                    .filter_map(|l| match l.line {
+                        HeaderLine::ObjInfo(ref o) => Some(o),
+                        _ => None,
+                    })
+                    .collect();
+                let k = obj_infos
+                    .iter()
+                    .position(|o| later.contains(&o))
+                    .unwrap_or(obj_infos.len());
+                for o in &obj_infos[..k] {
+                    written += self.write_line_obj_info(out, o)?;
+                }
+                obj_infos = &obj_infos[k..];
+            }
+        }
+        for c in comments {
+            written += self.write_line_comment(out, c)?;
+        }
+        for o in obj_infos {
+            written += self.write_line_obj_info(out, o)?;
+        }
+        for p in properties {
+            written += self.write_line_property_definition(out, p)?;
+        }
+        for e in elements {
+            written += self.write_element_definition(out, e)?;
+        }
+        match end_header {
+            Some(text) => written += out.write(text.as_bytes())?,
+            None => written += self.write_line_end_header(out)?,
+        }
+        Ok(written)
+    }
     fn write_encoding<T: Write>(&self, out: &mut T, encoding: &Encoding) -> Result<usize> {
         let s = match *encoding {
             Encoding::Ascii => "ascii",
@@ -293,7 +481,7 @@ use writer::Writer;
 use std::io::{ Write, Result };
 use ply::{ Header, PropertyAccess, Encoding, ElementDef };
 // */
-use crate::ply::Payload;
+use crate::ply::{Payload, PropertyKey, PropertySchema};
 
 // ////////////////////////
 /// # Payload
@@ -327,24 +515,31 @@ impl<E: PropertyAccess> Writer<E> {
         header: &Header,
     ) -> Result<usize> {
         let mut written = 0;
-        match header.encoding {
-            Encoding::Ascii => {
-                for element in element_list {
-                    written += self.write_ascii_element(out, element, element_def)?;
-                }
-            }
+        let schema = E::bind_schema(element_def);
+        for element in element_list {
+            written +=
+                self.__write_element(out, element, element_def, schema.as_ref(), header.encoding)?;
+        }
+        Ok(written)
+    }
+    /// Writes one element, addressing its properties through `schema` if the element type bound one.
+    pub(crate) fn __write_element<T: Write>(
+        &self,
+        out: &mut T,
+        element: &E,
+        element_def: &ElementDef,
+        schema: Option<&PropertySchema>,
+        encoding: Encoding,
+    ) -> Result<usize> {
+        match encoding {
+            Encoding::Ascii => self.__write_ascii_element(out, element, element_def, schema),
             Encoding::BinaryBigEndian => {
-                for element in element_list {
-                    written += self.write_big_endian_element(out, element, element_def)?;
-                }
+                self.__write_binary_element::<T, BigEndian>(out, element, element_def, schema)
             }
             Encoding::BinaryLittleEndian => {
-                for element in element_list {
-                    written += self.write_little_endian_element(out, element, element_def)?;
-                }
+                self.__write_binary_element::<T, LittleEndian>(out, element, element_def, schema)
             }
         }
-        Ok(written)
     }
 }
 /*
@@ -366,19 +561,23 @@ impl<E: PropertyAccess> Writer<E> {
         out: &mut T,
         element: &E,
         element_def: &ElementDef,
+    ) -> Result<usize> {
+        let schema = E::bind_schema(element_def);
+        self.__write_ascii_element(out, element, element_def, schema.as_ref())
+    }
+    fn __write_ascii_element<T: Write>(
+        &self,
+        out: &mut T,
+        element: &E,
+        element_def: &ElementDef,
+        schema: Option<&PropertySchema>,
     ) -> Result<usize> {
         let mut written = 0;
-        let mut p_iter = element_def.properties.iter();
-        let def = p_iter.next().unwrap();
-        written += self.write_ascii_property(out, element, def)?;
-        loop {
+        // an element without properties is an empty line
+        for (i, def) in element_def.properties.iter().enumerate() {
+            let k = PropertyKey::bound(element, schema, i, def);
+            written += self.write_ascii_property(out, element, def, k)?;
             written += out.write(" ".as_bytes())?;
-            let n = p_iter.next();
-            if n.is_none() {
-                break;
-            }
-            let def = n.unwrap();
-            written += self.write_ascii_property(out, element, def)?;
         }
         written += self.write_new_line(out)?;
         Ok(written)
@@ -388,48 +587,47 @@ impl<E: PropertyAccess> Writer<E> {
         out: &mut T,
         element: &E,
         prop_type: &PropertyDef,
+        k: PropertyKey,
     ) -> Result<usize> {
-        let k = &prop_type.name;
-
         match prop_type.data_type {
             PropertyType::Scalar(ref scalar_type) => match *scalar_type {
-                ScalarType::Char => self.write_ascii_scalar(out, get_prop!(element.get_char(k))),
-                ScalarType::UChar => self.write_ascii_scalar(out, get_prop!(element.get_uchar(k))),
-                ScalarType::Short => self.write_ascii_scalar(out, get_prop!(element.get_short(k))),
+                ScalarType::Char => self.write_ascii_scalar(out, get_prop!(k.get_char(element))),
+                ScalarType::UChar => self.write_ascii_scalar(out, get_prop!(k.get_uchar(element))),
+                ScalarType::Short => self.write_ascii_scalar(out, get_prop!(k.get_short(element))),
                 ScalarType::UShort => {
-                    self.write_ascii_scalar(out, get_prop!(element.get_ushort(k)))
+                    self.write_ascii_scalar(out, get_prop!(k.get_ushort(element)))
                 }
-                ScalarType::Int => self.write_ascii_scalar(out, get_prop!(element.get_int(k))),
-                ScalarType::UInt => self.write_ascii_scalar(out, get_prop!(element.get_uint(k))),
-                ScalarType::Float => self.write_ascii_scalar(out, get_prop!(element.get_float(k))),
+                ScalarType::Int => self.write_ascii_scalar(out, get_prop!(k.get_int(element))),
+                ScalarType::UInt => self.write_ascii_scalar(out, get_prop!(k.get_uint(element))),
+                ScalarType::Float => self.write_ascii_scalar(out, get_prop!(k.get_float(element))),
                 ScalarType::Double => {
-                    self.write_ascii_scalar(out, get_prop!(element.get_double(k)))
+                    self.write_ascii_scalar(out, get_prop!(k.get_double(element)))
                 }
             },
             PropertyType::List(_, ref scalar_type) => match *scalar_type {
-                ScalarType::Char => self.write_ascii_list(get_prop!(element.get_list_char(k)), out),
+                ScalarType::Char => self.write_ascii_list(get_prop!(k.get_list_char(element)), out),
                 ScalarType::UChar => {
-                    self.write_ascii_list(get_prop!(element.get_list_uchar(k)), out)
+                    self.write_ascii_list(get_prop!(k.get_list_uchar(element)), out)
                 }
                 ScalarType::Short => {
-                    self.write_ascii_list(get_prop!(element.get_list_short(k)), out)
+                    self.write_ascii_list(get_prop!(k.get_list_short(element)), out)
                 }
                 ScalarType::UShort => {
-                    self.write_ascii_list(get_prop!(element.get_list_ushort(k)), out)
+                    self.write_ascii_list(get_prop!(k.get_list_ushort(element)), out)
                 }
-                ScalarType::Int => self.write_ascii_list(get_prop!(element.get_list_int(k)), out),
-                ScalarType::UInt => self.write_ascii_list(get_prop!(element.get_list_uint(k)), out),
+                ScalarType::Int => self.write_ascii_list(get_prop!(k.get_list_int(element)), out),
+                ScalarType::UInt => self.write_ascii_list(get_prop!(k.get_list_uint(element)), out),
                 ScalarType::Float => {
-                    self.write_ascii_list(get_prop!(element.get_list_float(k)), out)
+                    self.write_ascii_list(get_prop!(k.get_list_float(element)), out)
                 }
                 ScalarType::Double => {
-                    self.write_ascii_list(get_prop!(element.get_list_double(k)), out)
+                    self.write_ascii_list(get_prop!(k.get_list_double(element)), out)
                 }
             },
         }
     }
-    fn write_ascii_scalar<T: Write, V: ToString>(&self, out: &mut T, value: V) -> Result<usize> {
-        out.write(value.to_string().as_bytes())
+    fn write_ascii_scalar<T: Write, V: Display>(&self, out: &mut T, value: V) -> Result<usize> {
+        out.write(ascii_value(&value).as_bytes())
     }
     fn write_ascii_list<T: Write, D: Clone + Display>(
         &self,
@@ -441,11 +639,23 @@ impl<E: PropertyAccess> Writer<E> {
         let b = " ".as_bytes();
         for v in list {
             written += out.write(b)?;
-            written += out.write(v.to_string().as_bytes())?;
+            written += out.write(ascii_value(v).as_bytes())?;
         }
         Ok(written)
     }
 }
+
+/// Formats a value for ascii data.
+///
+/// Rust writes `NaN`, `inf` and `-inf`, C's `printf` and most PLY tools use `nan` instead of `NaN`.
+fn ascii_value<V: Display>(value: &V) -> String {
+    let s = value.to_string();
+    if s == "NaN" {
+        "nan".to_string()
+    } else {
+        s
+    }
+}
 /*
 use ply::{ PropertyAccess, ElementDef, PropertyType, ScalarType };
 use super::Writer;
@@ -471,7 +681,8 @@ impl<E: PropertyAccess> Writer<E> {
         element: &E,
         element_def: &ElementDef,
     ) -> Result<usize> {
-        self.__write_binary_element::<T, BigEndian>(out, element, element_def)
+        let schema = E::bind_schema(element_def);
+        self.__write_binary_element::<T, BigEndian>(out, element, element_def, schema.as_ref())
     }
     /// Write a single binary formatted element in little endian.
     pub fn write_little_endian_element<T: Write>(
@@ -480,57 +691,68 @@ impl<E: PropertyAccess> Writer<E> {
         element: &E,
         element_def: &ElementDef,
     ) -> Result<usize> {
-        self.__write_binary_element::<T, LittleEndian>(out, element, element_def)
+        let schema = E::bind_schema(element_def);
+        self.__write_binary_element::<T, LittleEndian>(out, element, element_def, schema.as_ref())
     }
     fn __write_binary_element<T: Write, B: ByteOrder>(
         &self,
         out: &mut T,
         element: &E,
         element_def: &ElementDef,
+        schema: Option<&PropertySchema>,
     ) -> Result<usize> {
         let mut written = 0;
-        for def in &element_def.properties {
-            let k = &def.name;
+        for (i, def) in element_def.properties.iter().enumerate() {
+            let k = PropertyKey::bound(element, schema, i, def);
 
             match def.data_type {
                 PropertyType::Scalar(ref scalar_type) => {
                     written += match *scalar_type {
                         ScalarType::Char => {
-                            out.write_i8(get_prop!(element.get_char(k)))?;
+                            out.write_i8(get_prop!(k.get_char(element)))?;
                             1
                         }
                         ScalarType::UChar => {
-                            out.write_u8(get_prop!(element.get_uchar(k)))?;
+                            out.write_u8(get_prop!(k.get_uchar(element)))?;
                             1
                         }
                         ScalarType::Short => {
-                            out.write_i16::<B>(get_prop!(element.get_short(k)))?;
+                            out.write_i16::<B>(get_prop!(k.get_short(element)))?;
                             2
                         }
                         ScalarType::UShort => {
-                            out.write_u16::<B>(get_prop!(element.get_ushort(k)))?;
+                            out.write_u16::<B>(get_prop!(k.get_ushort(element)))?;
                             2
                         }
                         ScalarType::Int => {
-                            out.write_i32::<B>(get_prop!(element.get_int(k)))?;
+                            out.write_i32::<B>(get_prop!(k.get_int(element)))?;
                             4
                         }
                         ScalarType::UInt => {
-                            out.write_u32::<B>(get_prop!(element.get_uint(k)))?;
+                            out.write_u32::<B>(get_prop!(k.get_uint(element)))?;
                             4
                         }
                         ScalarType::Float => {
-                            out.write_f32::<B>(get_prop!(element.get_float(k)))?;
+                            out.write_f32::<B>(get_prop!(k.get_float(element)))?;
                             4
                         }
                         ScalarType::Double => {
-                            out.write_f64::<B>(get_prop!(element.get_double(k)))?;
+                            out.write_f64::<B>(get_prop!(k.get_double(element)))?;
                             8
                         }
                     };
                 }
                 PropertyType::List(ref index_type, ref scalar_type) => {
-                    let vec_len = element_def.count;
+                    let vec_len = match *scalar_type {
+                        ScalarType::Char => get_prop!(k.get_list_char(element)).len(),
+                        ScalarType::UChar => get_prop!(k.get_list_uchar(element)).len(),
+                        ScalarType::Short => get_prop!(k.get_list_short(element)).len(),
+                        ScalarType::UShort => get_prop!(k.get_list_ushort(element)).len(),
+                        ScalarType::Int => get_prop!(k.get_list_int(element)).len(),
+                        ScalarType::UInt => get_prop!(k.get_list_uint(element)).len(),
+                        ScalarType::Float => get_prop!(k.get_list_float(element)).len(),
+                        ScalarType::Double => get_prop!(k.get_list_double(element)).len(),
+                    };
                     written += match *index_type {
                         ScalarType::Char => {out.write_i8(vec_len as i8)?; 1},
                         ScalarType::UChar => {out.write_u8(vec_len as u8)?; 1},
@@ -544,7 +766,7 @@ impl<E: PropertyAccess> Writer<E> {
 
                     written += match *scalar_type {
                         ScalarType::Char => self.write_binary_list::<T, i8>(
-                            get_prop!(element.get_list_char(k)),
+                            get_prop!(k.get_list_char(element)),
                             out,
                             &|o, x| {
                                 o.write_i8(*x)?;
@@ -552,7 +774,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::UChar => self.write_binary_list::<T, u8>(
-                            get_prop!(element.get_list_uchar(k)),
+                            get_prop!(k.get_list_uchar(element)),
                             out,
                             &|o, x| {
                                 o.write_u8(*x)?;
@@ -560,7 +782,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::Short => self.write_binary_list::<T, i16>(
-                            get_prop!(element.get_list_short(k)),
+                            get_prop!(k.get_list_short(element)),
                             out,
                             &|o, x| {
                                 o.write_i16::<B>(*x)?;
@@ -568,7 +790,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::UShort => self.write_binary_list::<T, u16>(
-                            get_prop!(element.get_list_ushort(k)),
+                            get_prop!(k.get_list_ushort(element)),
                             out,
                             &|o, x| {
                                 o.write_u16::<B>(*x)?;
@@ -576,7 +798,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::Int => self.write_binary_list::<T, i32>(
-                            get_prop!(element.get_list_int(k)),
+                            get_prop!(k.get_list_int(element)),
                             out,
                             &|o, x| {
                                 o.write_i32::<B>(*x)?;
@@ -584,7 +806,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::UInt => self.write_binary_list::<T, u32>(
-                            get_prop!(element.get_list_uint(k)),
+                            get_prop!(k.get_list_uint(element)),
                             out,
                             &|o, x| {
                                 o.write_u32::<B>(*x)?;
@@ -592,7 +814,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::Float => self.write_binary_list::<T, f32>(
-                            get_prop!(element.get_list_float(k)),
+                            get_prop!(k.get_list_float(element)),
                             out,
                             &|o, x| {
                                 o.write_f32::<B>(*x)?;
@@ -600,7 +822,7 @@ impl<E: PropertyAccess> Writer<E> {
                             },
                         )?,
                         ScalarType::Double => self.write_binary_list::<T, f64>(
-                            get_prop!(element.get_list_double(k)),
+                            get_prop!(k.get_list_double(element)),
                             out,
                             &|o, x| {
                                 o.write_f64::<B>(*x)?;
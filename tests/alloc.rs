@@ -0,0 +1,153 @@
+extern crate ply_rs;
+use ply_rs::ply::{
+    ElementDef, Encoding, Property, PropertyAccess, PropertyDef, PropertyType, ScalarType,
+};
+use ply_rs::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the allocations of the current thread, tests run in parallel.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|c| c.set(c.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|c| c.get())
+}
+
+/// Vertex or triangle, copied from the typed setters.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Item {
+    position: [f32; 3],
+    indices: [u32; 3],
+}
+
+impl PropertyAccess for Item {
+    fn new() -> Self {
+        Item::default()
+    }
+    fn set_property(&mut self, key: &str, property: Property) {
+        panic!("Unexpected property {}: {:?}", key, property);
+    }
+    fn set_float(&mut self, key: &str, value: f32) {
+        let i = ["x", "y", "z"].iter().position(|n| *n == key).unwrap();
+        self.position[i] = value;
+    }
+    fn set_list_u32_from(&mut self, _key: &str, values: &[u32]) {
+        self.indices.copy_from_slice(values);
+    }
+    fn get_float(&self, key: &str) -> Option<f32> {
+        let i = ["x", "y", "z"].iter().position(|n| *n == key)?;
+        Some(self.position[i])
+    }
+    fn get_list_uint(&self, _key: &str) -> Option<&[u32]> {
+        Some(&self.indices)
+    }
+}
+
+fn mesh(encoding: Encoding, faces: u32) -> Vec<u8> {
+    let mut ply = ply::Ply::<Item>::new();
+    ply.header.encoding = encoding;
+    let mut vertex = ElementDef::new("vertex");
+    for name in ["x", "y", "z"] {
+        let p = PropertyDef::new(name, PropertyType::Scalar(ScalarType::Float));
+        vertex.properties.push(p);
+    }
+    let mut face = ElementDef::new("face");
+    let list = PropertyType::List(ScalarType::UChar, ScalarType::UInt);
+    face.properties
+        .push(PropertyDef::new("vertex_indices", list));
+    ply.header.elements.push(vertex);
+    ply.header.elements.push(face);
+
+    let vertices = (0..faces + 2)
+        .map(|i| Item {
+            position: [i as f32, 0.5, -1.0],
+            ..Item::default()
+        })
+        .collect();
+    let triangles = (0..faces)
+        .map(|i| Item {
+            indices: [i, i + 1, i + 2],
+            ..Item::default()
+        })
+        .collect();
+    ply.payload.insert("vertex".to_string(), vertices);
+    ply.payload.insert("face".to_string(), triangles);
+    let mut buf = Vec::new();
+    writer::Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+    buf
+}
+
+#[test]
+fn read_lists_without_allocations() {
+    let faces = 10_000;
+    for encoding in [
+        Encoding::Ascii,
+        Encoding::BinaryBigEndian,
+        Encoding::BinaryLittleEndian,
+    ] {
+        let data = mesh(encoding, faces);
+        let p = parser::Parser::<Item>::new();
+        let mut reader = data.as_slice();
+        let header = p.read_header(&mut reader).unwrap();
+        let mut items = Vec::with_capacity(2 * faces as usize + 2);
+
+        let before = allocations();
+        p.read_payload_with(&mut reader, &header, |_, item| {
+            items.push(item);
+            Ok(())
+        })
+        .unwrap();
+        let used = allocations() - before;
+
+        // buffers are allocated per element definition, not per element
+        assert!(used < 20, "{}: {} allocations", encoding, used);
+        assert_eq!(items[3].position, [3.0, 0.5, -1.0]);
+        assert_eq!(items.last().unwrap().indices, [9_999, 10_000, 10_001]);
+    }
+}
+
+#[test]
+fn read_default_element_allocations() {
+    let faces = 10_000;
+    let data = mesh(Encoding::BinaryLittleEndian, faces);
+    let p = parser::Parser::<ply::DefaultElement>::new();
+    let mut reader = data.as_slice();
+    let header = p.read_header(&mut reader).unwrap();
+    let mut elements = Vec::with_capacity(2 * faces as usize + 2);
+
+    let before = allocations();
+    p.read_payload_with(&mut reader, &header, |_, element| {
+        elements.push(element);
+        Ok(())
+    })
+    .unwrap();
+    let used = allocations() - before;
+
+    // a DefaultElement owns its map, keys and lists, see its docs, but nothing is allocated per value beyond that
+    let elements_read = 2 * faces as usize + 2;
+    let values = 3 * (faces as usize + 2) + faces as usize;
+    let lists = faces as usize;
+    let expected = 2 * elements_read + 2 * values + lists;
+    assert!(used <= expected + 20, "{} allocations", used);
+    assert_eq!(
+        elements.last().unwrap()["vertex_indices"],
+        Property::ListUInt(vec![9_999, 10_000, 10_001])
+    );
+}
@@ -0,0 +1,154 @@
+extern crate ply_rs;
+use ply_rs::ply::*;
+use ply_rs::*;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+type Ply = ply::Ply<ply::DefaultElement>;
+
+fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ply"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn run_ok(args: &[&str], stdin: &[u8]) -> Vec<u8> {
+    let output = run(args, stdin);
+    assert!(
+        output.status.success(),
+        "failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+fn read_buff(mut buf: &[u8]) -> Ply {
+    let p = parser::Parser::new();
+    let ply = p.read_ply(&mut buf);
+    assert!(ply.is_ok(), "failed: {}", ply.err().unwrap());
+    ply.unwrap()
+}
+
+fn house() -> Vec<u8> {
+    std::fs::read("example_plys/house_ok_ascii.ply").unwrap()
+}
+
+#[test]
+fn info_ok() {
+    let out = run_ok(&["info", "example_plys/house_ok_ascii.ply"], b"");
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("ply\nformat ascii 1.0\n"));
+    assert!(text.contains("element vertex: 5 elements"));
+    assert!(text.contains("payload: "));
+}
+#[test]
+fn convert_pipe_ok() {
+    let binary = run_ok(&["convert", "--to", "binary_le"], &house());
+    let ply = read_buff(&binary);
+    assert_eq!(ply.header.encoding, Encoding::BinaryLittleEndian);
+    let ascii = run_ok(&["convert", "--to", "ascii", "-", "-"], &binary);
+    assert_eq!(read_buff(&ascii).payload, read_buff(&house()).payload);
+}
+#[test]
+fn validate_ok() {
+    run_ok(&["validate"], &house());
+    let mut truncated = house();
+    truncated.truncate(truncated.len() - 10);
+    let output = run(&["validate"], &truncated);
+    assert_eq!(output.status.code(), Some(1));
+    let bad = b"ply\nformat ascii 1.0\nelement point 2\nproperty int x\nend_header\n1\nfoo\n";
+    let output = run(&["validate"], bad);
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("ply: Line 7:"));
+    let mut trailing = run_ok(&["convert", "--to", "binary_be"], &house());
+    trailing.push(0);
+    assert_eq!(run(&["validate"], &trailing).status.code(), Some(1));
+}
+#[test]
+fn head_ok() {
+    let out = run_ok(&["head", "-n", "2", "example_plys/house_ok_ascii.ply"], b"");
+    let ply = read_buff(&out);
+    assert_eq!(ply.header.elements[0].count, 2);
+    assert_eq!(ply.payload["vertex"].len(), 2);
+    assert_eq!(ply.payload["face"].len(), 2);
+}
+#[test]
+fn strip_ok() {
+    let out = run_ok(&["strip", "--property", "nx,ny,nz"], &house());
+    let ply = read_buff(&out);
+    let names: Vec<&str> = ply.header.elements[0]
+        .properties
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["x", "y", "z"]);
+    assert!(!ply.payload["vertex"][0].contains_key("nx"));
+
+    let points = b"ply\nformat ascii 1.0\nelement point 2\nproperty float x\nproperty float y\nend_header\n1 2\n3 4\n";
+    let out = run_ok(&["strip", "--property", "x,y"], points);
+    let ply = read_buff(&out);
+    assert!(ply.header.elements[0].properties.is_empty());
+    assert_eq!(ply.payload["point"].len(), 2);
+    assert!(out.ends_with(b"end_header\n\n\n"));
+}
+#[test]
+fn comment_ok() {
+    let added = run_ok(&["comment", "add", "scanned on monday"], &house());
+    let ply = read_buff(&added);
+    assert_eq!(ply.header.comments.last().unwrap(), "scanned on monday");
+    let removed = run_ok(&["comment", "remove", "--matching", "monday"], &added);
+    assert_eq!(
+        read_buff(&removed).header.comments,
+        read_buff(&house()).header.comments
+    );
+    let cleared = run_ok(&["comment", "remove"], &added);
+    assert!(read_buff(&cleared).header.comments.is_empty());
+
+    let header = "ply\nformat ascii 1.0\nelement point 0\ncomment TextureFile a.png\nproperty int8 x\nend_header\n";
+    let kept = run_ok(
+        &["comment", "remove", "--matching", "monday"],
+        header.as_bytes(),
+    );
+    assert_eq!(kept, header.as_bytes());
+}
+#[test]
+fn usage_err() {
+    assert_eq!(run(&["frobnicate"], b"").status.code(), Some(2));
+    assert_eq!(run(&["convert"], b"").status.code(), Some(2));
+    assert_eq!(
+        run(&["convert", "--to", "utf8"], b"").status.code(),
+        Some(2)
+    );
+    assert_eq!(
+        run(&["comment", "add", "a", "--matching", "b"], b"")
+            .status
+            .code(),
+        Some(2)
+    );
+}
+#[test]
+fn diff_ok() {
+    let binary = run_ok(&["convert", "--to", "binary_le"], &house());
+    std::fs::write(std::env::temp_dir().join("ply_cli_diff.ply"), &binary).unwrap();
+    let right = std::env::temp_dir().join("ply_cli_diff.ply");
+    let right = right.to_str().unwrap();
+    let equal = run(&["diff", "example_plys/house_ok_ascii.ply", right], b"");
+    assert_eq!(equal.status.code(), Some(0));
+    assert!(equal.stdout.is_empty());
+
+    let stripped = run_ok(&["strip", "--property", "nz"], &house());
+    let different = run(&["diff", "-", right], &stripped);
+    assert_eq!(different.status.code(), Some(1));
+    let text = String::from_utf8(different.stdout).unwrap();
+    assert_eq!(text, "property vertex.nz: only right\n");
+
+    let missing = run(&["diff", "-", "example_plys/missing.ply"], &house());
+    assert_eq!(missing.status.code(), Some(2));
+    let broken = run(&["diff", "-", right], b"ply\nformat ascii 1.0\n");
+    assert_eq!(broken.status.code(), Some(2));
+}
@@ -0,0 +1,87 @@
+#![cfg(any(feature = "gzip", feature = "zstd"))]
+extern crate ply_rs;
+use ply_rs::compression::*;
+use ply_rs::ply::*;
+use ply_rs::*;
+
+type Ply = ply::Ply<ply::DefaultElement>;
+
+fn house(encoding: Encoding) -> Ply {
+    let mut f = std::fs::File::open("example_plys/house_2_ok_ascii.ply").unwrap();
+    let mut ply: Ply = parser::Parser::new().read_ply(&mut f).unwrap();
+    ply.header.encoding = encoding;
+    ply
+}
+
+fn compressions() -> Vec<Compression> {
+    vec![
+        #[cfg(feature = "gzip")]
+        Compression::Gzip(6),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip(0),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(3),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(19),
+    ]
+}
+
+#[test]
+fn read_ply_compressed() {
+    for compression in compressions() {
+        for encoding in [Encoding::Ascii, Encoding::BinaryLittleEndian] {
+            let mut ply = house(encoding);
+            let mut buf = Vec::new();
+            let w = writer::Writer::new();
+            let written = w
+                .write_ply_compressed(&mut buf, &mut ply, compression)
+                .unwrap();
+            let mut plain = Vec::new();
+            w.write_ply(&mut plain, &mut ply).unwrap();
+            assert_eq!(written, plain.len());
+            assert!(CompressionFormat::detect(&buf).is_some());
+
+            let read: Ply = parser::Parser::new().read_ply(&mut buf.as_slice()).unwrap();
+            assert_eq!(read, ply, "{:?}", compression);
+        }
+    }
+}
+#[test]
+fn stream_compressed() {
+    for compression in compressions() {
+        let ply = house(Encoding::BinaryBigEndian);
+        let w = writer::Writer::<DefaultElement>::new();
+        let mut out = Compressor::new(Vec::new(), compression).unwrap();
+        w.write_header(&mut out, &ply.header).unwrap();
+        for def in &ply.header.elements {
+            for e in &ply.payload[&def.name] {
+                w.write_big_endian_element(&mut out, e, def).unwrap();
+            }
+        }
+        let buf = out.finish().unwrap();
+
+        let mut input = Decompressor::new(buf.as_slice()).unwrap();
+        let p = parser::Parser::<DefaultElement>::new();
+        let header = p.read_header(&mut input).unwrap();
+        assert_eq!(header, ply.header);
+        let def = &header.elements[0];
+        let first = p.read_element(&mut input, def, &header).unwrap();
+        assert_eq!(first, ply.payload[&def.name][0]);
+    }
+}
+#[test]
+#[cfg(feature = "gzip")]
+fn read_gzip_members() {
+    use std::io::Write;
+    // `cat a.gz b.gz` is a valid gzip stream
+    let text = std::fs::read("example_plys/house_2_ok_ascii.ply").unwrap();
+    let (head, tail) = text.split_at(text.len() / 2);
+    let mut buf = Vec::new();
+    for part in [head, tail] {
+        let mut out = Compressor::new(Vec::new(), Compression::Gzip(6)).unwrap();
+        out.write_all(part).unwrap();
+        buf.extend(out.finish().unwrap());
+    }
+    let read: Ply = parser::Parser::new().read_ply(&mut buf.as_slice()).unwrap();
+    assert_eq!(read, house(Encoding::Ascii));
+}
@@ -206,3 +206,298 @@ mod struct_test_1 {
         }
     }
 }
+
+#[test]
+fn read_diff_ok() {
+    let mut left = std::fs::File::open("example_plys/house_2_ok_ascii.ply").unwrap();
+    let mut right = std::fs::File::open("example_plys/house_2_ok_little_endian.ply").unwrap();
+    let p = parser::Parser::<ply::DefaultElement>::new();
+    let diff = p
+        .read_diff(&mut left, &mut right, &ply::DiffOptions::default())
+        .unwrap();
+    assert!(diff.is_equal(), "{}", diff);
+    assert_eq!(diff.element("vertex").unwrap().compared, 5);
+}
+
+fn read_tokens(data: &str) -> std::io::Result<Ply> {
+    parser::Parser::new()
+        .with_ascii_mode(parser::AsciiMode::Tokens)
+        .read_ply(&mut data.as_bytes())
+}
+
+#[test]
+fn read_ascii_tokens_ok() {
+    let lines = read_file("example_plys/house_2_ok_ascii.ply");
+    let mut wrapped = String::new();
+    std::fs::File::open("example_plys/house_2_ok_ascii.ply")
+        .and_then(|mut f| std::io::Read::read_to_string(&mut f, &mut wrapped))
+        .unwrap();
+    let (header, payload) = wrapped.split_at(wrapped.find("end_header\n").unwrap() + 11);
+    // one value per line
+    let wrapped = format!(
+        "{}{}",
+        header,
+        payload.split_whitespace().collect::<Vec<_>>().join("\n")
+    );
+    assert_eq!(read_tokens(&wrapped).unwrap(), lines);
+    // everything on one line
+    let joined = format!(
+        "{}{}",
+        header,
+        payload.split_whitespace().collect::<Vec<_>>().join(" \t ")
+    );
+    assert_eq!(read_tokens(&joined).unwrap(), lines);
+}
+#[test]
+fn read_ascii_tokens_streaming_ok() {
+    let data = "ply\nformat ascii 1.0\nelement point 3\nproperty int x\nend_header\n1 2\n3\n";
+    let p = parser::Parser::<ply::DefaultElement>::new().with_ascii_mode(parser::AsciiMode::Tokens);
+    let mut reader = data.as_bytes();
+    let header = p.read_header(&mut reader).unwrap();
+    let mut xs = Vec::new();
+    for _ in 0..3 {
+        let e = p
+            .read_element(&mut reader, &header.elements[0], &header)
+            .unwrap();
+        xs.push(e["x"].clone());
+    }
+    assert_eq!(
+        xs,
+        vec![
+            ply::Property::Int(1),
+            ply::Property::Int(2),
+            ply::Property::Int(3)
+        ]
+    );
+}
+#[test]
+fn read_ascii_tokens_err() {
+    let header =
+        "ply\nformat ascii 1.0\nelement face 2\nproperty list uchar int vertex_index\nend_header\n";
+    let e = read_tokens(&format!("{}3 0 1\n  2 3 x 5 6", header)).unwrap_err();
+    assert!(
+        e.to_string()
+            .starts_with("Line 7, column 5: Couldn't read property 'vertex_index'."),
+        "{}",
+        e
+    );
+    let e = read_tokens(&format!("{}3 0 1\n  2\n3 4", header)).unwrap_err();
+    assert!(
+        e.to_string().contains("found end of input after 1"),
+        "{}",
+        e
+    );
+    assert!(e.to_string().starts_with("Line 8, column 1:"), "{}", e);
+}
+#[test]
+fn read_element_reader_shares_def() {
+    let path = "example_plys/house_2_ok_little_endian.ply";
+    let mut f = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+    let p = parser::Parser::<ply::CompactElement>::new();
+    let header = p.read_header(&mut f).unwrap();
+    let mut vertices = p.element_reader(&header.elements[0], &header);
+    let elements: Vec<ply::CompactElement> = (0..header.elements[0].count)
+        .map(|_| vertices.read(&mut f).unwrap())
+        .collect();
+    let shared = elements
+        .windows(2)
+        .all(|w| std::sync::Arc::ptr_eq(w[0].element_def(), w[1].element_def()));
+    assert!(shared);
+    let expected = read_file(path);
+    let converted: Vec<ply::DefaultElement> = elements
+        .into_iter()
+        .map(ply::DefaultElement::from)
+        .collect();
+    assert_eq!(converted, expected.payload["vertex"]);
+}
+#[test]
+fn read_compact_same_as_default() {
+    for path in [
+        "example_plys/all_atomic_types_ok_ascii.ply",
+        "example_plys/greg_turk_example2_ok_ascii.ply",
+        "example_plys/house_2_ok_ascii.ply",
+        "example_plys/house_2_ok_little_endian.ply",
+        "example_plys/empty_2_ok_little_endian.ply",
+    ] {
+        let expected = read_file(path);
+        let mut f = std::fs::File::open(path).unwrap();
+        let p = parser::Parser::<ply::CompactElement>::new();
+        let ply = p.read_ply(&mut f).unwrap();
+        assert_eq!(ply.header, expected.header);
+        for (name, elements) in ply.payload {
+            if let Some(first) = elements.first() {
+                assert_eq!(first.element_def().name, name);
+            }
+            let shared = elements
+                .windows(2)
+                .all(|w| std::sync::Arc::ptr_eq(w[0].element_def(), w[1].element_def()));
+            assert!(shared, "{}: {}", path, name);
+            let converted: Vec<ply::DefaultElement> = elements
+                .into_iter()
+                .map(ply::DefaultElement::from)
+                .collect();
+            assert_eq!(converted, expected.payload[&name], "{}: {}", path, name);
+        }
+    }
+}
+
+/// Reads an ascii payload with `split_whitespace()` and `str::parse()`, as the parser did before its fast path.
+fn read_with_str_parse(path: &str) -> std::io::Result<ply::Payload<ply::DefaultElement>> {
+    use ply::{Property, PropertyType, ScalarType};
+    use std::io::{BufRead, Error, ErrorKind};
+    fn next<T: std::str::FromStr>(tokens: &mut std::str::SplitWhitespace) -> std::io::Result<T> {
+        let token = tokens
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Missing value."))?;
+        token
+            .parse()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, token))
+    }
+    fn scalar(tokens: &mut std::str::SplitWhitespace, s: ScalarType) -> std::io::Result<Property> {
+        Ok(match s {
+            ScalarType::Char => Property::Char(next(tokens)?),
+            ScalarType::UChar => Property::UChar(next(tokens)?),
+            ScalarType::Short => Property::Short(next(tokens)?),
+            ScalarType::UShort => Property::UShort(next(tokens)?),
+            ScalarType::Int => Property::Int(next(tokens)?),
+            ScalarType::UInt => Property::UInt(next(tokens)?),
+            ScalarType::Float => Property::Float(next(tokens)?),
+            ScalarType::Double => Property::Double(next(tokens)?),
+        })
+    }
+    fn list(tokens: &mut std::str::SplitWhitespace, s: ScalarType) -> std::io::Result<Property> {
+        let count: usize = next(tokens)?;
+        let mut values = Vec::new();
+        for _ in 0..count {
+            values.push(scalar(tokens, s)?);
+        }
+        macro_rules! collect {
+            ($variant:ident, $list:ident) => {
+                Property::$list(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Property::$variant(v) => v,
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                )
+            };
+        }
+        Ok(match s {
+            ScalarType::Char => collect!(Char, ListChar),
+            ScalarType::UChar => collect!(UChar, ListUChar),
+            ScalarType::Short => collect!(Short, ListShort),
+            ScalarType::UShort => collect!(UShort, ListUShort),
+            ScalarType::Int => collect!(Int, ListInt),
+            ScalarType::UInt => collect!(UInt, ListUInt),
+            ScalarType::Float => collect!(Float, ListFloat),
+            ScalarType::Double => collect!(Double, ListDouble),
+        })
+    }
+
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let header = parser::Parser::<ply::DefaultElement>::new().read_header(&mut reader)?;
+    let mut lines = reader.lines();
+    let mut payload = ply::Payload::new();
+    for e in &header.elements {
+        let mut elements = Vec::new();
+        for _ in 0..e.count {
+            let line = lines
+                .next()
+                .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Missing line."))??;
+            let mut tokens = line.split_whitespace();
+            let mut element = ply::DefaultElement::new();
+            for p in &e.properties {
+                let value = match p.data_type {
+                    PropertyType::Scalar(s) => scalar(&mut tokens, s)?,
+                    PropertyType::List(_, s) => list(&mut tokens, s)?,
+                };
+                element.insert(p.name.clone(), value);
+            }
+            elements.push(element);
+        }
+        payload.insert(e.name.clone(), elements);
+    }
+    Ok(payload)
+}
+#[test]
+fn read_ascii_same_as_str_parse() {
+    let mut compared = 0;
+    for entry in std::fs::read_dir("example_plys").unwrap() {
+        let path = entry.unwrap().path();
+        let path = path.to_str().unwrap();
+        if !path.ends_with("_ascii.ply") {
+            continue;
+        }
+        let mut f = std::fs::File::open(path).unwrap();
+        let ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f);
+        match (ply, read_with_str_parse(path)) {
+            (Ok(ply), Ok(expected)) => assert_eq!(ply.payload, expected, "{}", path),
+            (Err(_), Err(_)) => (),
+            (ply, expected) => panic!("{}: {:?} but {:?}", path, ply.err(), expected.err()),
+        }
+        compared += 1;
+    }
+    assert!(compared >= 10, "{}", compared);
+}
+
+/// Stores positions and face indices through the index functions only.
+#[derive(Debug, Default)]
+struct Indexed {
+    position: [f32; 3],
+    indices: Vec<i32>,
+}
+
+impl ply::PropertyAccess for Indexed {
+    fn new() -> Self {
+        Indexed::default()
+    }
+    fn bind_schema(element_def: &ply::ElementDef) -> Option<ply::PropertySchema> {
+        Some(ply::PropertySchema::resolve(element_def, |name| {
+            ["x", "y", "z", "vertex_indices"]
+                .iter()
+                .position(|n| *n == name)
+        }))
+    }
+    fn set_float_at(&mut self, index: usize, value: f32) {
+        self.position[index] = value;
+    }
+    fn set_list_i32_from_at(&mut self, _index: usize, values: &[i32]) {
+        self.indices = values.to_vec();
+    }
+    fn get_float_at(&self, index: usize) -> Option<f32> {
+        self.position.get(index).copied()
+    }
+    fn get_list_int_at(&self, index: usize) -> Option<&[i32]> {
+        (index == 3).then_some(&self.indices[..])
+    }
+}
+
+#[test]
+fn read_index_only_type_same_as_default() {
+    let path = "example_plys/house_2_ok_ascii.ply";
+    let expected = read_file(path);
+    let mut f = std::fs::File::open(path).unwrap();
+    let indexed = parser::Parser::<Indexed>::new().read_ply(&mut f).unwrap();
+    assert_eq!(indexed.statistics(), expected.statistics());
+    let options = ply::FingerprintOptions::default();
+    assert_eq!(
+        indexed.fingerprint(&options),
+        expected.fingerprint(&options)
+    );
+    let mut f = std::fs::File::open(path).unwrap();
+    let (_, statistics) = parser::Parser::<Indexed>::new()
+        .read_statistics(&mut f)
+        .unwrap();
+    assert_eq!(statistics, expected.statistics());
+    let diff = parser::Parser::<Indexed>::new()
+        .read_diff(
+            &mut std::fs::File::open(path).unwrap(),
+            &mut std::fs::File::open("example_plys/house_2_ok_little_endian.ply").unwrap(),
+            &ply::DiffOptions::default(),
+        )
+        .unwrap();
+    assert!(diff.is_equal(), "{}", diff);
+    assert_eq!(diff.element("vertex").unwrap().compared, 5);
+}
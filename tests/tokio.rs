@@ -0,0 +1,194 @@
+#![cfg(feature = "tokio")]
+extern crate ply_rs;
+use ply_rs::ply::*;
+use ply_rs::*;
+use std::future::Future;
+
+type Ply = ply::Ply<ply::DefaultElement>;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+fn house(encoding: Encoding) -> Vec<u8> {
+    let mut f = std::fs::File::open("example_plys/house_2_ok_ascii.ply").unwrap();
+    let mut ply = parser::Parser::<DefaultElement>::new()
+        .read_ply(&mut f)
+        .unwrap();
+    ply.header.encoding = encoding;
+    let mut buf = Vec::new();
+    writer::Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+    buf
+}
+
+#[test]
+fn read_all_encodings() {
+    for encoding in [
+        Encoding::Ascii,
+        Encoding::BinaryBigEndian,
+        Encoding::BinaryLittleEndian,
+    ] {
+        let data = house(encoding);
+        let expected = parser::Parser::<DefaultElement>::new()
+            .read_ply(&mut data.as_slice())
+            .unwrap();
+        // a tiny buffer splits lines and elements across reads
+        let mut reader = tokio::io::BufReader::with_capacity(3, data.as_slice());
+        let ply: Ply = block_on(parser::AsyncParser::new().read_ply(&mut reader)).unwrap();
+        assert_eq!(ply, expected, "{}", encoding);
+    }
+}
+#[test]
+fn read_element_by_element() {
+    let data = house(Encoding::BinaryLittleEndian);
+    let p = parser::AsyncParser::<DefaultElement>::new();
+    block_on(async {
+        let mut reader = data.as_slice();
+        let header = p.read_header(&mut reader).await.unwrap();
+        let def = &header.elements[0];
+        let first = p.read_element(&mut reader, def, &header).await.unwrap();
+        assert!(first.contains_key("x"));
+        let mut count = 1;
+        let mut rest = header.clone();
+        rest.elements[0].count -= 1;
+        p.read_payload_with(&mut reader, &rest, |_, _| {
+            count += 1;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            count,
+            header.elements.iter().map(|e| e.count).sum::<usize>()
+        );
+        assert!(reader.is_empty());
+    });
+}
+#[test]
+fn element_reader_shares_def() {
+    let data = house(Encoding::Ascii);
+    let p = parser::AsyncParser::<CompactElement>::new();
+    block_on(async {
+        let mut reader = data.as_slice();
+        let header = p.read_header(&mut reader).await.unwrap();
+        let mut vertices = p.element_reader(&header.elements[0], &header);
+        let first = vertices.read(&mut reader).await.unwrap();
+        let second = vertices.read(&mut reader).await.unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            first.element_def(),
+            second.element_def()
+        ));
+        assert_eq!(second.get_float("x"), Some(-1.0));
+    });
+}
+#[test]
+fn errors_match_sync() {
+    let mut truncated = house(Encoding::BinaryBigEndian);
+    truncated.truncate(truncated.len() - 3);
+    let mut bad_ascii = house(Encoding::Ascii);
+    let at = bad_ascii.len() - 2;
+    bad_ascii[at] = b'x';
+    let bad_header =
+        b"ply\nformat ascii 1.0\nelement vertex 1\nproperty foo x\nend_header\n".to_vec();
+    for data in [truncated, bad_ascii, bad_header] {
+        let sync = parser::Parser::<DefaultElement>::new()
+            .read_ply(&mut data.as_slice())
+            .unwrap_err();
+        let asynchronous =
+            block_on(parser::AsyncParser::<DefaultElement>::new().read_ply(&mut data.as_slice()))
+                .unwrap_err();
+        assert_eq!(asynchronous.kind(), sync.kind());
+        assert_eq!(asynchronous.to_string(), sync.to_string());
+    }
+}
+#[test]
+fn read_tokens_same_as_sync() {
+    let wrapped = b"ply\nformat ascii 1.0\nelement vertex 3\nproperty int x\n\
+        element face 2\nproperty list uchar int vertex_indices\nend_header\n\
+        1 2\n3 3 0\n1\n  2\n4 0 1 2\n3\n"
+        .to_vec();
+    let mut truncated = wrapped.clone();
+    truncated.truncate(truncated.len() - 4);
+    let sync = parser::Parser::<DefaultElement>::new().with_ascii_mode(parser::AsciiMode::Tokens);
+    let expected = sync.read_ply(&mut wrapped.as_slice()).unwrap();
+    let p = parser::AsyncParser::from(sync);
+    let mut reader = tokio::io::BufReader::with_capacity(3, wrapped.as_slice());
+    let ply: Ply = block_on(p.read_ply(&mut reader)).unwrap();
+    assert_eq!(ply, expected);
+    assert_eq!(
+        ply.payload["face"][1]["vertex_indices"],
+        Property::ListInt(vec![0, 1, 2, 3])
+    );
+
+    let sync = parser::Parser::<DefaultElement>::new()
+        .with_ascii_mode(parser::AsciiMode::Tokens)
+        .read_ply(&mut truncated.as_slice())
+        .unwrap_err();
+    let p = parser::AsyncParser::<DefaultElement>::new().with_ascii_mode(parser::AsciiMode::Tokens);
+    let asynchronous = block_on(p.read_ply(&mut truncated.as_slice())).unwrap_err();
+    assert_eq!(asynchronous.to_string(), sync.to_string());
+}
+#[test]
+fn float_syntax_same_as_sync() {
+    let data = b"ply\nformat ascii 1.0\nelement point 2\nproperty float x\nend_header\n1.5\n1e39\n";
+    let sync = parser::Parser::<DefaultElement>::new()
+        .with_float_syntax(parser::FloatSyntax::Finite)
+        .read_ply(&mut &data[..])
+        .unwrap_err();
+    let p =
+        parser::AsyncParser::<DefaultElement>::new().with_float_syntax(parser::FloatSyntax::Finite);
+    let asynchronous = block_on(p.read_ply(&mut &data[..])).unwrap_err();
+    assert_eq!(asynchronous.to_string(), sync.to_string());
+    let ply: Ply = block_on(parser::AsyncParser::new().read_ply(&mut &data[..])).unwrap();
+    assert_eq!(ply.payload["point"][1]["x"], Property::Float(f32::INFINITY));
+}
+#[test]
+fn write_through_pipe() {
+    let data = house(Encoding::BinaryLittleEndian);
+    let mut ply: Ply = parser::Parser::new()
+        .read_ply(&mut data.as_slice())
+        .unwrap();
+    let expected = ply.clone();
+    let read = block_on(async move {
+        // the pipe holds less than the file, the writer has to wait for the reader
+        let (mut client, server) = tokio::io::duplex(64);
+        let write = tokio::spawn(async move {
+            let w = writer::AsyncWriter::new();
+            w.write_ply(&mut client, &mut ply).await
+        });
+        let mut server = tokio::io::BufReader::new(server);
+        let read: Ply = parser::AsyncParser::new()
+            .read_ply(&mut server)
+            .await
+            .unwrap();
+        assert_eq!(write.await.unwrap().unwrap(), data.len());
+        read
+    });
+    assert_eq!(read, expected);
+}
+#[test]
+fn write_matches_sync() {
+    let data = house(Encoding::Ascii);
+    let ply: Ply = parser::Parser::new()
+        .read_ply(&mut data.as_slice())
+        .unwrap();
+    let w = writer::AsyncWriter::new();
+    let mut buf = Vec::new();
+    block_on(async {
+        w.write_header(&mut buf, &ply.header).await.unwrap();
+        let def = &ply.header.elements[0];
+        for e in &ply.payload[&def.name] {
+            w.write_element(&mut buf, e, def, &ply.header)
+                .await
+                .unwrap();
+        }
+        let def = &ply.header.elements[1];
+        w.write_payload_of_element(&mut buf, &ply.payload[&def.name], def, &ply.header)
+            .await
+            .unwrap();
+    });
+    assert_eq!(buf, data);
+}
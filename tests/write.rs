@@ -129,3 +129,201 @@ fn write_list_elements() {
     let new_ply = read_write_ply(&ply);
     assert_eq!(ply, new_ply);
 }
+#[test]
+fn write_binary_list_length() {
+    // list lengths differ from the element count
+    for encoding in [Encoding::BinaryBigEndian, Encoding::BinaryLittleEndian] {
+        let mut ply = create_list_elements();
+        ply.header.encoding = encoding;
+        let ve = write_buff(&ply);
+        let new_ply = read_buff(&mut BufReader::new(&(*ve)));
+        assert_eq!(ply, new_ply);
+    }
+}
+
+const LAYOUT_HEADER: &str = "ply\r\nformat ascii 1.0\r\ncomment made by hand\r\nobj_info scanner 7\r\ncomment TextureFile tex.png\r\nelement vertex 2\r\nproperty float32 x\r\nproperty  float32 y \r\ncomment between properties\r\nproperty list uint8 int32 l\r\nelement face 1\r\nproperty list uchar int vertex_indices\r\nend_header\r\n";
+
+#[test]
+fn write_non_finite_ascii() {
+    let mut ply = Ply::new();
+    let mut e = ElementDef::new("point");
+    e.properties.push(PropertyDef::new(
+        "x",
+        PropertyType::Scalar(ScalarType::Float),
+    ));
+    e.properties.push(PropertyDef::new(
+        "l",
+        PropertyType::List(ScalarType::UChar, ScalarType::Double),
+    ));
+    ply.header.elements.push(e);
+    let mut pe = KeyMap::new();
+    pe.insert("x".to_string(), Property::Float(-f32::NAN));
+    pe.insert(
+        "l".to_string(),
+        Property::ListDouble(vec![f64::INFINITY, f64::NEG_INFINITY, f64::NAN, 0.5]),
+    );
+    ply.payload.insert("point".to_string(), vec![pe]);
+    assert!(ply.make_consistent().is_ok());
+    let txt = String::from_utf8(write_buff(&ply)).unwrap();
+    assert!(
+        txt.ends_with("end_header\nnan 4 inf -inf nan 0.5 \n"),
+        "{}",
+        txt
+    );
+    let read = read_buff(&mut txt.as_bytes());
+    assert!(read.payload["point"][0]["x"].as_f64().unwrap().is_nan());
+}
+
+fn read_layout(text: &str) -> (Header, HeaderLayout) {
+    let p = parser::Parser::<ply::DefaultElement>::new();
+    p.read_header_with_layout(&mut text.as_bytes()).unwrap()
+}
+
+fn write_layout(header: &Header, layout: &HeaderLayout) -> String {
+    let mut buf = Vec::<u8>::new();
+    let w = writer::Writer::<ply::DefaultElement>::new();
+    let written = w
+        .write_header_with_layout(&mut buf, header, layout)
+        .unwrap();
+    assert_eq!(written, buf.len());
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn write_header_with_layout_unmodified() {
+    let (header, layout) = read_layout(LAYOUT_HEADER);
+    assert_eq!(layout.text(), LAYOUT_HEADER);
+    assert_eq!(write_layout(&header, &layout), LAYOUT_HEADER);
+}
+#[test]
+fn write_header_with_layout_modified() {
+    let (mut header, layout) = read_layout(LAYOUT_HEADER);
+    header.comments.retain(|c| c != "made by hand");
+    header.comments.push("converted".to_string());
+    header.encoding = Encoding::BinaryLittleEndian;
+    header.elements[0].properties[1].data_type = PropertyType::Scalar(ScalarType::Double);
+    header.elements[0].properties.push(PropertyDef::new(
+        "z",
+        PropertyType::Scalar(ScalarType::Float),
+    ));
+    header.elements[1].count = 3;
+    let text = write_layout(&header, &layout);
+    assert_eq!(
+        text,
+        "ply\r\nformat binary_little_endian 1.0\nobj_info scanner 7\r\n\
+         comment TextureFile tex.png\r\nelement vertex 2\r\nproperty float32 x\r\n\
+         property double y\ncomment between properties\r\ncomment converted\n\
+         property list uint8 int32 l\r\nproperty float z\nelement face 3\n\
+         property list uchar int vertex_indices\r\nend_header\r\n"
+    );
+    let (reread, _) = read_layout(&text);
+    assert_eq!(reread, header);
+}
+#[test]
+fn write_header_with_layout_empty() {
+    let ply = create_list_elements();
+    let text = write_layout(&ply.header, &HeaderLayout::new());
+    let mut expected = Vec::new();
+    writer::Writer::<ply::DefaultElement>::new()
+        .write_header(&mut expected, &ply.header)
+        .unwrap();
+    assert_eq!(text.as_bytes(), expected.as_slice());
+}
+
+/// Vertices and faces in one type, addressed by index only.
+#[derive(Debug, Default)]
+struct Indexed {
+    position: [f32; 3],
+    indices: Vec<i32>,
+}
+
+impl PropertyAccess for Indexed {
+    fn new() -> Self {
+        Indexed::default()
+    }
+    fn set_property(&mut self, key: &str, _property: Property) {
+        panic!("Unexpected name based access: {}", key);
+    }
+    fn bind_schema(element_def: &ElementDef) -> Option<PropertySchema> {
+        Some(PropertySchema::resolve(element_def, |name| {
+            ["x", "y", "z", "vertex_indices"]
+                .iter()
+                .position(|n| *n == name)
+        }))
+    }
+    fn set_property_at(&mut self, index: usize, property: Property) {
+        match (index, property) {
+            (i, Property::Float(v)) if i < 3 => self.position[i] = v,
+            (3, Property::ListInt(v)) => self.indices = v,
+            (i, p) => panic!("Unexpected property {}: {:?}", i, p),
+        }
+    }
+    fn get_float_at(&self, index: usize) -> Option<f32> {
+        self.position.get(index).copied()
+    }
+    fn get_list_int_at(&self, index: usize) -> Option<&[i32]> {
+        if index == 3 {
+            Some(&self.indices)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn write_bound_schema() {
+    let text = std::fs::read_to_string("example_plys/house_2_ok_ascii.ply").unwrap();
+    // `red` isn't bound and is skipped while reading
+    let with_color = text
+        .replace(
+            "property float z\n",
+            "property float z\nproperty uchar red\n",
+        )
+        .replace(" 0 \n", " 0 255\n");
+    let p = parser::Parser::<Indexed>::new();
+    let mut ply = p.read_ply(&mut with_color.as_bytes()).unwrap();
+    assert_eq!(ply.payload["vertex"][4].position, [0.0, 2.0, 0.0]);
+    assert_eq!(ply.payload["face"][2].indices, vec![1, 3, 4]);
+
+    let mut expected = read_buff(&mut text.as_bytes());
+    ply.header = expected.header.clone();
+    for encoding in [
+        Encoding::Ascii,
+        Encoding::BinaryBigEndian,
+        Encoding::BinaryLittleEndian,
+    ] {
+        ply.header.encoding = encoding;
+        expected.header.encoding = encoding;
+        let mut buf = Vec::new();
+        writer::Writer::new()
+            .write_ply_unchecked(&mut buf, &ply)
+            .unwrap();
+        let written = read_buff(&mut buf.as_slice());
+        assert_eq!(written.payload, expected.payload, "{}", encoding);
+        let mut reader = buf.as_slice();
+        let header = p.read_header(&mut reader).unwrap();
+        let mut count = 0;
+        p.read_payload_with(&mut reader, &header, |_, _| {
+            count += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 8);
+    }
+}
+
+#[test]
+fn write_compact() {
+    let path = "example_plys/house_2_ok_ascii.ply";
+    let mut f = std::fs::File::open(path).unwrap();
+    let p = parser::Parser::<ply::CompactElement>::new();
+    let mut ply = p.read_ply(&mut f).unwrap();
+    let expected = read_buff(&mut std::fs::File::open(path).unwrap());
+    for encoding in [Encoding::Ascii, Encoding::BinaryLittleEndian] {
+        ply.header.encoding = encoding;
+        let mut buf = Vec::new();
+        writer::Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+        let written = read_buff(&mut buf.as_slice());
+        assert_eq!(written.payload, expected.payload, "{}", encoding);
+    }
+}